@@ -11,6 +11,7 @@
 use core::f64::consts::PI;
 use std::env;
 use std::fs::File;
+use std::process::Command;
 use std::{
     io::{Result, Write},
     path::{Path, PathBuf},
@@ -50,5 +51,44 @@ fn main() -> Result<()> {
     }
     write!(f, "];\n")?;
 
+    // expose the git hash as `GIT_HASH`, printed by `app::trace::print_banner`
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash());
+
     Ok(())
 }
+
+// The short hash of HEAD, with a `-dirty` suffix for uncommitted changes.
+// Set `GIT_HASH` in the environment to override, e.g., when building outside
+// a git checkout. Falls back to "unknown".
+fn git_hash() -> String {
+    println!("cargo:rerun-if-env-changed=GIT_HASH");
+    if let Ok(hash) = env::var("GIT_HASH") {
+        return hash;
+    }
+
+    // re-run on a new commit, a checkout, or a change of the staged files
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference.trim());
+        }
+    }
+
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    match git(&["rev-parse", "--short", "HEAD"]) {
+        Some(hash) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(status) if !status.is_empty() => format!("{}-dirty", hash),
+            _ => hash,
+        },
+        None => "unknown".to_string(),
+    }
+}
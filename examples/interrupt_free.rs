@@ -0,0 +1,148 @@
+//! interrupt_free.rs
+//!
+//! Global critical sections, without RTIC
+//!
+//! What it covers:
+//! - a plain `cortex-m-rt` program, `#[entry]` and a raw `#[interrupt]` handler
+//! - `static` globals behind `Mutex<RefCell<..>>`, accessed in `interrupt::free`
+//! - moving a peripheral (the timer) into a global, for use in the handler
+//! - how this compares to RTIC resources (see notes)
+//!
+//! > cargo run --example interrupt_free
+//!
+//! Same idea as `rtic_lock.rs`, the shared state written by an interrupt
+//! handler and read by the main loop, here with the bookkeeping done by hand.
+
+#![no_main]
+#![no_std]
+
+use core::cell::RefCell;
+// `free` is `cortex_m::interrupt::free`, the `interrupt` name is taken by the
+// PAC (the `#[interrupt]` attribute and the interrupt enumeration)
+use cortex_m::{
+    asm,
+    interrupt::{free, Mutex},
+    peripheral::NVIC,
+};
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{self, interrupt, Interrupt, TIM2},
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// timer interrupts per second
+const TICK_HZ: u32 = 10;
+// main loop print period in clock cycles, ~1 s at 16 MHz
+const PRINT: u32 = 16_000_000;
+
+// Time kept by the handler, `seconds` and `ticks` must be read together
+struct Uptime {
+    seconds: u32,
+    ticks: u32,
+}
+
+// the timer, moved here by `main` once configured (`None` until then)
+static TIMER: Mutex<RefCell<Option<Timer<TIM2>>>> = Mutex::new(RefCell::new(None));
+
+// written by `TIM2`, read by `main`
+static UPTIME: Mutex<RefCell<Uptime>> = Mutex::new(RefCell::new(Uptime {
+    seconds: 0,
+    ticks: 0,
+}));
+
+#[entry]
+fn main() -> ! {
+    trace_init!();
+
+    let device = stm32::Peripherals::take().unwrap();
+
+    let rcc = device.RCC.constrain();
+    let clocks = rcc.cfgr.freeze();
+    print_banner(clocks.sysclk().0);
+
+    // TIM2 update interrupt at TICK_HZ
+    let mut timer = Timer::tim2(device.TIM2, TICK_HZ.hz(), clocks);
+    timer.listen(Event::TimeOut);
+
+    // hand the timer over to the handler, before the interrupt is unmasked
+    free(|cs| TIMER.borrow(cs).replace(Some(timer)));
+
+    // SAFETY: the globals used by `TIM2` are set up, not inside a critical section
+    unsafe { NVIC::unmask(Interrupt::TIM2) };
+
+    loop {
+        asm::delay(PRINT);
+
+        // copy out both values in one critical section, keep it short
+        let (seconds, ticks) = free(|cs| {
+            let uptime = UPTIME.borrow(cs).borrow();
+            (uptime.seconds, uptime.ticks)
+        });
+        rprintln!("uptime {}.{} s", seconds, ticks);
+    }
+}
+
+// A raw handler, the name binds it to the vector table (not an RTIC task)
+#[interrupt]
+fn TIM2() {
+    free(|cs| {
+        if let Some(timer) = TIMER.borrow(cs).borrow_mut().as_mut() {
+            timer.clear_interrupt(Event::TimeOut);
+        }
+
+        let mut uptime = UPTIME.borrow(cs).borrow_mut();
+        uptime.ticks += 1;
+        if uptime.ticks == TICK_HZ {
+            uptime.ticks = 0;
+            uptime.seconds += 1;
+        }
+    });
+}
+
+// Notes:
+//
+// `interrupt::free` disables all interrupts (CPSID I), runs the closure, and
+// re-enables them if they were enabled before. The closure gets a
+// `CriticalSection` token (`cs`), and `Mutex::borrow` requires one, so the
+// global can only be reached while no handler can preempt.
+//
+// The `Mutex` gives shared (`&`) access only, the `RefCell` adds the
+// mutability, checked at run-time (`borrow_mut` panics on a second borrow).
+// The `Option` is there since the timer does not exist when the static is
+// initialized, it is moved in by `main`.
+//
+// Compare with `rtic_lock.rs`, the RTIC version:
+//
+// - RTIC resources need no `Mutex`, `RefCell` or `Option`. Late resources
+//   are moved in from `init`, before any task runs.
+// - The RTIC `lock` only raises the priority (BASEPRI) to the ceiling of the
+//   resource, tasks of higher priority, not using the resource, still run.
+//   `interrupt::free` blocks every interrupt, also unrelated ones.
+// - The highest priority task using a resource accesses it without a lock.
+//   Above, the handler still pays for a critical section (and the `RefCell`
+//   check), as the `Mutex` cannot know that it is not preempted.
+// - RTIC checks at compile time that every shared access is locked, with
+//   globals each access has to be written correctly by hand.
+//
+// Reach for `interrupt::free` where RTIC does not manage the state:
+//
+// - programs (or libraries) not using RTIC, e.g., a driver crate shared with
+//   code outside the app
+// - state shared with `#[exception]` handlers RTIC does not know about,
+//   e.g., the DebugMonitor in `rtic_watchpoint.rs` or a HardFault handler
+// - a short sequence that must not be interrupted at all, e.g., timing
+//   critical bit-banging (see `rtic_ws2812.rs`)
+//
+// Keep the critical sections short, an interrupt arriving meanwhile is
+// delayed until the end of the closure (the latency of every interrupt
+// grows by the longest critical section). Copy the data out and print
+// afterwards, as in the main loop above, never print inside.
+//
+// For a single counter or flag, an atomic (`rtic_atomic.rs`) needs no
+// critical section. The two values here (`seconds`, `ticks`) must be
+// consistent with each other, which atomics cannot guarantee.
@@ -0,0 +1,99 @@
+//! rtic_adc.rs
+//!
+//! Analog to Digital Conversion
+//!
+//! What it covers:
+//! - setting up ADC1 using the HAL (resolution, sample time)
+//! - single-ended sampling of an analog pin
+//! - converting the raw sample to millivolts
+//!
+//! > cargo run --example rtic_adc
+//!
+//! Connect a potentiometer (wiper) to PA0 (CN7-28), and its ends to +3.3V and GND.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc,
+    },
+    gpio::{gpioa::PA0, Analog},
+    prelude::*,
+    stm32::ADC1,
+};
+
+use app::{adc::to_millivolts, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// sample period in clock cycles
+const OFFSET: u32 = 8_000_000;
+// longer sample times allow higher source impedances, RM0368 11.6
+const SAMPLE_TIME: SampleTime = SampleTime::Cycles_480;
+// assumed reference voltage (VREF+) in millivolts
+const VREF_MV: u32 = 3_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: Adc<ADC1>,
+        pin: PA0<Analog>,
+    }
+
+    #[init(schedule = [sample])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa0.into_analog();
+
+        // 12 bit resolution, 0..=4095
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let adc = Adc::adc1(device.ADC1, true, config);
+
+        cx.schedule.sample(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { adc, pin }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [adc, pin], schedule = [sample])]
+    fn sample(cx: sample::Context) {
+        let raw = cx.resources.adc.convert(cx.resources.pin, SAMPLE_TIME);
+        rprintln!("raw {}, {} mV", raw, to_millivolts(raw, VREF_MV));
+
+        try_reschedule(cx.schedule.sample(cx.scheduled + OFFSET.cycles()), "sample");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// On the Nucleo, VREF+ is tied to VDDA (3.3V). The HAL can compute the
+// actual VDDA from the internal reference (`Adc::sample_to_millivolts`),
+// here we use a fixed reference to show the arithmetic.
@@ -0,0 +1,231 @@
+//! rtic_adc_scan_dma.rs
+//!
+//! Multi-channel ADC scan with DMA
+//!
+//! What it covers:
+//! - the ADC regular sequence (SQRx), scan mode and sample times (register level)
+//! - DMA2 stream 0 in circular mode, moving each conversion to a `[u16; N]` buffer
+//! - the DMA transfer complete (TC) interrupt marking the buffer fresh
+//!
+//! > cargo run --example rtic_adc_scan_dma
+//!
+//! Connect analog sources (e.g., potentiometers) to PA0 (CN7-28), PA1 (CN7-30),
+//! PA4 (CN7-32) and PB0 (CN7-34). Unconnected inputs read noise.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{ADC1, DMA2},
+};
+
+use app::{adc::to_millivolts, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// number of channels in the sequence
+const N: usize = 4;
+// the regular sequence, ADC1_IN0, IN1, IN4 and IN8 are on PA0, PA1, PA4 and PB0
+const CHANNELS: [u8; N] = [0, 1, 4, 8];
+// print period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+// assumed reference voltage (VREF+) in millivolts
+const VREF_MV: u32 = 3_300;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: ADC1,
+        dma: DMA2,
+        buffer: &'static mut [u16; N],
+        // set by the TC interrupt, cleared when printed
+        #[init(false)]
+        fresh: bool,
+    }
+
+    #[init(schedule = [report])]
+    fn init(cx: init::Context) -> init::LateResources {
+        // written by the DMA, must be `'static` (see `rtic_dma_mem2mem.rs`)
+        static mut BUFFER: [u16; N] = [0; N];
+
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on ADC1 and DMA2, RM0368 6.3.12 and 6.3.9
+        device.RCC.apb2enr.modify(|_, w| w.adc1en().enabled());
+        device.RCC.ahb1enr.modify(|_, w| w.dma2en().enabled());
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks), ADCCLK = PCLK2 / 2 = 8 MHz (ADCPRE reset value)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // the inputs in analog mode, `split` powers on the ports
+        let gpioa = device.GPIOA.split();
+        let gpiob = device.GPIOB.split();
+        gpioa.pa0.into_analog();
+        gpioa.pa1.into_analog();
+        gpioa.pa4.into_analog();
+        gpiob.pb0.into_analog();
+
+        let buffer: &'static mut [u16; N] = BUFFER;
+        let adc = device.ADC1;
+        let dma = device.DMA2;
+
+        // ADC1 is served by DMA2 stream 0 (or 4), channel 0, RM0368 9.3.3 Table 28
+        let stream = &dma.st[0];
+        stream
+            .par
+            .write(|w| unsafe { w.pa().bits(&adc.dr as *const _ as u32) });
+        stream
+            .m0ar
+            .write(|w| unsafe { w.m0a().bits(buffer.as_ptr() as u32) });
+        // one transfer per conversion, NDTR is reloaded at the end (circular mode)
+        stream.ndtr.write(|w| w.ndt().bits(N as u16));
+        stream.cr.write(|w| {
+            w.chsel()
+                .bits(0)
+                .dir()
+                .peripheral_to_memory()
+                // half words, the data register holds a 12 bit right aligned sample
+                .psize()
+                .bits16()
+                .msize()
+                .bits16()
+                // always read ADC_DR, step through the buffer
+                .pinc()
+                .fixed()
+                .minc()
+                .incremented()
+                .circ()
+                .enabled()
+                .tcie()
+                .enabled()
+                .teie()
+                .enabled()
+        });
+        stream.cr.modify(|_, w| w.en().enabled());
+
+        // 12 bit resolution, scan the whole sequence (not only SQ1), RM0368 11.12.2
+        adc.cr1.write(|w| w.res().twelve_bit().scan().enabled());
+
+        // sample times, longer gives higher accuracy for high impedance sources,
+        // SMPR2 holds channels 0..=9, RM0368 11.12.5
+        adc.smpr2.write(|w| {
+            w.smp0()
+                .cycles480()
+                .smp1()
+                .cycles480()
+                .smp4()
+                .cycles480()
+                .smp8()
+                .cycles480()
+        });
+
+        // The regular sequence, RM0368 11.12.9 - 11.12.11:
+        // - SQR1 L, the length minus one (up to 16 conversions)
+        // - SQR3 SQ1..SQ6, SQR2 SQ7..SQ12, SQR1 SQ13..SQ16, the channel of each rank
+        // A channel may appear more than once, in any order.
+        adc.sqr1.write(|w| w.l().bits(N as u8 - 1));
+        adc.sqr3.write(|w| unsafe {
+            w.sq1()
+                .bits(CHANNELS[0])
+                .sq2()
+                .bits(CHANNELS[1])
+                .sq3()
+                .bits(CHANNELS[2])
+                .sq4()
+                .bits(CHANNELS[3])
+        });
+
+        // DMA requests for each conversion, and keep issuing them after the
+        // last transfer (DDS), as needed with circular mode, RM0368 11.8.1
+        adc.cr2
+            .write(|w| w.dma().enabled().dds().continuous().adon().enabled());
+
+        cx.schedule.report(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { adc, dma, buffer }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [adc, buffer, fresh], schedule = [report])]
+    fn report(mut cx: report::Context) {
+        let fresh = cx.resources.fresh.lock(|fresh| {
+            let was = *fresh;
+            *fresh = false;
+            was
+        });
+
+        if fresh {
+            // no conversion is ongoing (CONT = 0), so the DMA is not writing
+            for (channel, raw) in CHANNELS.iter().zip(cx.resources.buffer.iter()) {
+                rprintln!(
+                    "  ch{} raw {:4}, {} mV",
+                    channel,
+                    raw,
+                    to_millivolts(*raw, VREF_MV)
+                );
+            }
+        }
+
+        // start the next scan, RM0368 11.12.3 (SWSTART)
+        cx.resources.adc.cr2.modify(|_, w| w.swstart().start());
+
+        try_reschedule(cx.schedule.report(cx.scheduled + OFFSET.cycles()), "report");
+    }
+
+    #[task(binds = DMA2_STREAM0, priority = 2, resources = [dma, fresh])]
+    fn dma2_stream0(cx: dma2_stream0::Context) {
+        let dma = cx.resources.dma;
+
+        // read the status, then clear the flags, DMA_LISR/DMA_LIFCR, RM0368 9.5.1
+        let lisr = dma.lisr.read();
+        dma.lifcr
+            .write(|w| w.ctcif0().set_bit().cteif0().set_bit().chtif0().set_bit());
+
+        if lisr.teif0().bit_is_set() {
+            rprintln!("transfer error");
+        } else if lisr.tcif0().bit_is_set() {
+            // all N conversions are in the buffer, NDTR is back at N
+            *cx.resources.fresh = true;
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// Each SWSTART runs the sequence once (CONT = 0), and the circular DMA
+// rewinds to the start of the buffer after the N:th transfer, ready for the
+// next scan. The first report prints nothing, as no scan has been started.
+//
+// For continuous sampling set CONT (the ADC restarts the sequence by itself).
+// The buffer is then overwritten all the time, copy it in the TC handler, or
+// use the half transfer (HT) interrupt and a 2 x N buffer (double buffering),
+// processing one half while the DMA fills the other.
+//
+// Without DMA, scan mode is of little use, ADC_DR only holds the latest
+// conversion and is overwritten by the next one (the overrun flag OVR is set).
+//
+// The ADC must not be reconfigured while the DMA is enabled, and the DMA stream
+// must be configured before the ADC issues its first request.
@@ -0,0 +1,140 @@
+//! rtic_adc_stream.rs
+//!
+//! Streaming ADC samples over the serial port
+//!
+//! What it covers:
+//! - sampling an analog pin at a fixed rate (scheduled task)
+//! - a tiny binary frame format (`app::frame`), sync byte and checksum
+//! - sending binary data over USART2 (stlink virtual COM port)
+//!
+//! > cargo run --example rtic_adc_stream
+//!
+//! Connect a potentiometer (wiper) to PA0 (CN7-28), and its ends to +3.3V and GND.
+//! The frame format is documented in `src/frame.rs`. Read on the host, e.g.,
+//! with Python and `pyserial`:
+//!
+//! ```text
+//! import serial
+//! port = serial.Serial("/dev/ttyACM0", 115200)
+//! while True:
+//!     if port.read(1)[0] != 0xA5:
+//!         continue
+//!     lo, hi, chk = port.read(3)
+//!     if lo ^ hi == chk:
+//!         print(lo | hi << 8)
+//! ```
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc,
+    },
+    gpio::{gpioa::PA0, Analog},
+    nb::block,
+    prelude::*,
+    serial::{config::Config, Serial, Tx},
+    stm32::{ADC1, USART2},
+};
+
+use app::{frame::frame_sample, schedule::try_reschedule, trace::print_banner, trace_init};
+
+const SYSCLK_HZ: u32 = 16_000_000;
+// sample rate, a frame takes 4 x 10 bit times, ~350 us at 115200 baud
+const RATE_HZ: u32 = 500;
+const OFFSET: u32 = SYSCLK_HZ / RATE_HZ;
+const BAUD: u32 = 115_200;
+const SAMPLE_TIME: SampleTime = SampleTime::Cycles_112;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: Adc<ADC1>,
+        pin: PA0<Analog>,
+        tx: Tx<USART2>,
+    }
+
+    #[init(schedule = [sample])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa0.into_analog();
+
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let adc = Adc::adc1(device.ADC1, true, config);
+
+        let serial = Serial::usart2(
+            device.USART2,
+            (
+                gpioa.pa2.into_alternate_af7(),
+                gpioa.pa3.into_alternate_af7(),
+            ),
+            Config::default().baudrate(BAUD.bps()),
+            clocks,
+        )
+        .unwrap();
+        let (tx, _rx) = serial.split();
+
+        rprintln!("streaming {} samples/s", RATE_HZ);
+        cx.schedule.sample(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { adc, pin, tx }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [adc, pin, tx], schedule = [sample])]
+    fn sample(cx: sample::Context) {
+        let raw = cx.resources.adc.convert(cx.resources.pin, SAMPLE_TIME);
+
+        // blocking, the frame must be sent before the next sample is due
+        for byte in frame_sample(raw).iter() {
+            block!(cx.resources.tx.write(*byte)).ok();
+        }
+
+        try_reschedule(cx.schedule.sample(cx.scheduled + OFFSET.cycles()), "sample");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The bandwidth is 11_520 bytes/s at 115200 baud (8N1, 10 bits per byte),
+// 2880 frames/s. Keep a margin, the sample task blocks while transmitting,
+// and the next sample is delayed if the frame is not out in time. For higher
+// rates, raise the baud rate (the stlink VCP handles up to ~2 Mbaud), or
+// send the frames by DMA (see `rtic_dma_serial.rs`).
+//
+// The sync byte alone is ambiguous, a sample with 0xA5 in it looks like the
+// start of a frame. The checksum rejects most false syncs, so a receiver
+// starting mid-stream locks on within a few frames.
+//
+// Binary framing is far more compact than text ("4095\r\n" is 6 bytes) and
+// needs no parsing on the target, but is not human readable in a terminal.
@@ -0,0 +1,123 @@
+//! rtic_atomic.rs
+//!
+//! Lock-free sharing with atomics
+//!
+//! What it covers:
+//! - a `static` `AtomicU32` shared by interrupt handlers and `idle`, no RTIC resource
+//! - atomic read-modify-write (`fetch_add`), no `lock` needed
+//! - when an atomic is sound, and when a `lock` is still required
+//!
+//! > cargo run --example rtic_atomic
+//!
+//! Same setup as `rtic_lock.rs`, with the shared counter as an atomic.
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{TIM2, TIM3},
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// idle print period in clock cycles, ~1 s at 16 MHz
+const PRINT: u32 = 16_000_000;
+
+// shared by `idle`, `tim3` and `tim2`, not an RTIC resource, so never locked
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        fast: Timer<TIM2>,
+        slow: Timer<TIM3>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // TIM2 update interrupt at 1 kHz
+        let mut fast = Timer::tim2(device.TIM2, 1.khz(), clocks);
+        fast.listen(Event::TimeOut);
+
+        // TIM3 update interrupt at 10 Hz
+        let mut slow = Timer::tim3(device.TIM3, 10.hz(), clocks);
+        slow.listen(Event::TimeOut);
+
+        init::LateResources { fast, slow }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            // a single load, always a consistent value
+            rprintln!("counter {}", COUNTER.load(Ordering::Relaxed));
+            asm::delay(PRINT);
+        }
+    }
+
+    // low priority, adds 1000 each time
+    #[task(binds = TIM3, priority = 1, resources = [slow])]
+    fn tim3(cx: tim3::Context) {
+        cx.resources.slow.clear_interrupt(Event::TimeOut);
+
+        // preemption by `tim2` is harmless, see notes
+        COUNTER.fetch_add(1000, Ordering::Relaxed);
+    }
+
+    // high priority, adds 1 each time
+    #[task(binds = TIM2, priority = 2, resources = [fast])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.fast.clear_interrupt(Event::TimeOut);
+
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+    }
+};
+
+// Notes:
+//
+// `fetch_add` compiles to a LDREX/ADD/STREX loop on the Cortex-M4. If an
+// interrupt preempts between the LDREX and the STREX, the exception return
+// clears the exclusive monitor, the STREX fails, and the loop retries with
+// the updated value. No increment is lost, without ever masking interrupts.
+// (The Cortex-M0 has no LDREX/STREX, and no atomic `fetch_add`.)
+//
+// Sound with atomics:
+// - a single value of at most 32 bits (a counter, a flag, a state number)
+// - updated only through atomic operations (`fetch_add`, `swap`,
+//   `compare_exchange`, ...)
+//
+// Still needs a `lock` (an RTIC resource):
+// - a read-modify-write written as `load` then `store`, e.g.,
+//   `COUNTER.store(COUNTER.load(..) + 1000, ..)`. This is the same race as
+//   the unlocked `*counter += 1000` described in `rtic_lock.rs`
+// - several values that must be consistent with each other, e.g., a sum and
+//   a count for an average. Two atomics can each be correct, while a reader
+//   sees the new sum with the old count
+// - anything larger than a word (structs, buffers, u64 on the Cortex-M4)
+// - peripherals, they are not `Sync`
+//
+// `Relaxed` ordering suffices for a standalone counter. When an atomic flag
+// publishes other data (e.g., "buffer ready"), use `Release` on the store and
+// `Acquire` on the load, so the data writes are visible before the flag.
+//
+// Atomics bypass RTIC's analysis, the compiler cannot check that every access
+// is atomic. Prefer resources (zero cost for the highest priority task, see
+// `rtic_lock.rs`), and use atomics where the lock would delay a high priority
+// task, or for state shared with code outside the RTIC app (e.g., a
+// `#[exception]` handler, see `rtic_watchpoint.rs`).
@@ -0,0 +1,109 @@
+//! rtic_backup.rs
+//!
+//! Persisting state in the RTC backup registers
+//!
+//! What it covers:
+//! - enabling backup domain access (PWR DBP, RTC clock)
+//! - reading/writing backup registers (`bkp_read`/`bkp_write`)
+//! - a magic word telling valid contents from garbage (after power loss)
+//!
+//! > cargo run --example rtic_backup
+//!
+//! Press the user button (B1) to reset, the boot counter survives. Press the
+//! black reset button (B2) for a pin reset, it survives that too. Unplug the
+//! board to clear it.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+};
+
+use app::{
+    backup::{bkp_read, bkp_write, enable_backup_access},
+    reset::system_reset,
+    trace::print_banner,
+    trace_init,
+};
+
+// backup register layout
+const BKP_MAGIC: usize = 0;
+const BKP_BOOTS: usize = 1;
+// marks the registers as initialized by this program
+const MAGIC: u32 = 0xB007_C0DE;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        enable_backup_access(&device.RCC, &device.PWR);
+
+        let rtc = &device.RTC;
+        if bkp_read(rtc, BKP_MAGIC) != MAGIC {
+            rprintln!("backup registers not initialized (power on?)");
+            bkp_write(rtc, BKP_BOOTS, 0);
+            bkp_write(rtc, BKP_MAGIC, MAGIC);
+        }
+        let boots = bkp_read(rtc, BKP_BOOTS) + 1;
+        bkp_write(rtc, BKP_BOOTS, boots);
+        rprintln!("boot {}, press the button to reset", boots);
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources { button }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [button])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+        rprintln!("reset");
+        system_reset();
+    }
+};
+
+// Notes:
+//
+// Compared to flash (`rtic_flash_store.rs`), the backup registers:
+// - are written like any register, no erase, no wear, no stall of the CPU
+// - are few (20 words)
+// - are lost without power, unless a battery is connected to VBAT (on the
+//   Nucleo VBAT is tied to VDD, so unplugging clears them)
+//
+// A tamper event (RTC_TAFCR) also clears the backup registers, a feature for
+// storing keys.
+//
+// `enable_backup_access` does not touch the RTC clock, the backup registers
+// need none. The RTC clock source (RTCSEL) can only be selected once per
+// backup domain reset, leave it to the calendar (`rtic_rtc.rs`, or
+// `backup::enable_rtc_clock`).
@@ -12,24 +12,38 @@
 
 use panic_rtt_target as _;
 use rtic::cyccnt::{Instant, U32Ext as _};
-use rtt_target::{rprintln, rtt_init_print};
+use rtt_target::rprintln;
 use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
     prelude::*,
-    stm32::{self, GPIOC, RCC},
 };
 
-const OFFSET: u32 = 24_000_000;
+use app::{
+    clock::{dump_clocks, SysClkConfig},
+    delay::ms_to_cycles,
+    led::Led,
+    mco::{ClockOut, Mco2Prescaler},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// blink frequency in Hz, independent of SYSCLK
+const BLINK_HZ: u32 = 1;
+// time between toggles (half a blink period) in milliseconds
+const PERIOD_MS: u32 = 1_000 / (2 * BLINK_HZ);
 
 #[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
 const APP: () = {
     struct Resources {
         // late resources
-        GPIOA: stm32::GPIOA,
+        led: Led<PA5<Output<PushPull>>>,
+        // SYSCLK frequency in Hz, as returned by `freeze`
+        sysclk: u32,
     }
     #[init(schedule = [toggle])]
     fn init(cx: init::Context) -> init::LateResources {
-        rtt_init_print!();
-        rprintln!("init");
+        trace_init!();
 
         let mut core = cx.core;
         let device = cx.device;
@@ -38,52 +52,50 @@ const APP: () = {
         core.DCB.enable_trace();
         core.DWT.enable_cycle_counter();
 
-        // semantically, the monotonic timer is frozen at time "zero" during `init`
-        // NOTE do *not* call `Instant::now` in this context; it will return a nonsense value
-        let now = cx.start; // the start time of the system
-
-        // Schedule `toggle` to run 8e6 cycles (clock cycles) in the future
-        cx.schedule.toggle(now + OFFSET.cycles()).unwrap();
-
-        // setup LED
-        // power on GPIOA, RM0368 6.3.11
-        device.RCC.ahb1enr.modify(|_, w| w.gpioaen().set_bit());
-        // configure PA5 as output, RM0368 8.4.1
-        device.GPIOA.moder.modify(|_, w| w.moder5().bits(1));
-
-        clock_out(&device.RCC, &device.GPIOC);
+        // setup LED, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
 
-        let rcc = device.RCC.constrain();
+        // SYSCLK / 4 on MCO2 (PC9)
+        ClockOut::mco2_sysclk(&device.RCC, &device.GPIOC, Mco2Prescaler::Div4);
 
-        //let _clocks = rcc.cfgr.freeze();
+        // setup the clocks, pick ONE of the configurations
+        //
+        // `apply` checks the requested SYSCLK (`clock::validate_sysclk`), and warns
+        // over RTT if it cannot be produced or the achieved frequency differs.
+        //
+        // 16 MHz (default, all clocks)
+        // let clocks = SysClkConfig::new().apply(device.RCC);
+        //
+        // 48 MHz
+        // let clocks = SysClkConfig::new().sysclk(48).pclk1(24).apply(device.RCC);
+        //
+        // 7.1 (panicks at run-time)
+        // let clocks = SysClkConfig::new().sysclk(64).pclk1(64).pclk2(64).apply(device.RCC);
+        //
+        // 7.2
+        let clocks = SysClkConfig::new()
+            .sysclk(84)
+            .pclk1(42)
+            .pclk2(64)
+            .apply(device.RCC);
+        print_banner(clocks.sysclk().0);
+        // all the derived clocks, including the timer clocks
+        dump_clocks(&clocks);
 
-        //Set up the system clock. 48 MHz?
-        // let _clocks = rcc.cfgr.sysclk(48.mhz()).pclk1(24.mhz()).freeze();
+        // the cycle count depends on SYSCLK, read it back from the actual clocks
+        let sysclk = clocks.sysclk().0;
+        let offset = ms_to_cycles(PERIOD_MS, sysclk);
 
-        // 7.1 (panicks at run-time)
-        // let _clocks = rcc.cfgr.sysclk(64.mhz()).pclk1(64.mhz()).pclk2(64.mhz()).freeze();
-        
-        // 7.2 
-        rcc.cfgr.sysclk(84.mhz()).pclk1(42.mhz()).pclk2(64.mhz()).freeze();
+        // semantically, the monotonic timer is frozen at time "zero" during `init`
+        // NOTE do *not* call `Instant::now` in this context; it will return a nonsense value
+        let now = cx.start; // the start time of the system
 
-        // let _clocks = rcc
-        //     .cfgr
-        //     .sysclk(64.mhz())
-        //     .pclk1(64.mhz())
-        //     .pclk2(64.mhz())
-        //     .freeze();
-        //
-        // let _clocks = rcc
-        //     .cfgr
-        //     .sysclk(84.mhz())
-        //     .pclk1(42.mhz())
-        //     .pclk2(64.mhz())
-        //     .freeze();
+        // Schedule `toggle` to run `offset` cycles (PERIOD_MS) in the future
+        cx.schedule.toggle(now + offset.cycles()).unwrap();
 
         // pass on late resources
-        init::LateResources {
-            GPIOA: device.GPIOA,
-        }
+        init::LateResources { led, sysclk }
     }
 
     #[idle]
@@ -94,19 +106,19 @@ const APP: () = {
         }
     }
 
-    #[task(resources = [GPIOA], schedule = [toggle])]
+    #[task(resources = [led, sysclk], schedule = [toggle])]
     fn toggle(cx: toggle::Context) {
-        static mut TOGGLE: bool = false;
         rprintln!("toggle  @ {:?}", Instant::now());
 
-        if *TOGGLE {
-            cx.resources.GPIOA.bsrr.write(|w| w.bs5().set_bit());
-        } else {
-            cx.resources.GPIOA.bsrr.write(|w| w.br5().set_bit());
-        }
+        cx.resources.led.toggle().ok();
 
-        *TOGGLE = !*TOGGLE;
-        cx.schedule.toggle(cx.scheduled + OFFSET.cycles()).unwrap();
+        // BLINK_HZ for any clock configuration, no need to tune the offset by hand
+        let offset = ms_to_cycles(PERIOD_MS, *cx.resources.sysclk);
+        // a wrapping add, the CYCCNT wraps every ~51 s at 84 MHz (see `delay::next_deadline`)
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + offset.cycles()), "toggle") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
     }
 
     extern "C" {
@@ -114,34 +126,6 @@ const APP: () = {
     }
 };
 
-// see the Reference Manual RM0368 (www.st.com/resource/en/reference_manual/dm00096844.pdf)
-// rcc,     chapter 6
-// gpio,    chapter 8
-
-fn clock_out(rcc: &RCC, gpioc: &GPIOC) {
-    // output MCO2 to pin PC9v
-
-    // mco2 	: SYSCLK = 0b00
-    // mcopre 	: divide by 4 = 0b110
-    rcc.cfgr
-        .modify(|_, w| unsafe { w.mco2().sysclk().mco2pre().div4() });
-
-    // power on GPIOC, RM0368 6.3.11
-    rcc.ahb1enr.modify(|_, w| w.gpiocen().enabled());
-
-    // MCO_2 alternate function AF0, STM32F401xD STM32F401xE data sheet
-    // table 9
-    // AF0, gpioc reset value = AF0
-
-    // configure PC9 as alternate function 0b10, RM0368 6.2.10
-    gpioc.moder.modify(|_, w| w.moder9().alternate());
-
-    // otyper reset state push/pull, in reset state (don't need to change)
-
-    // ospeedr 0b11 = very high speed
-    gpioc.ospeedr.modify(|_, w| w.ospeedr9().low_speed());
-}
-
 // 0. Background reading:
 //
 //    Clock trees:
@@ -163,8 +147,12 @@ fn clock_out(rcc: &RCC, gpioc: &GPIOC) {
 //    CST    - CoreSystemTimer drives the SysTick counter, HCLK/(1 or 8)
 //    PCLK1  - The clock driving the APB1 (<= 42 MHz)
 //             Timers on the APB1 bus will be triggered at PCLK1 * 2
+//             (if APB1 is divided, else at PCLK1)
 //    PCLK2  - The clock driving the APB2 (<= 84 MHz)
 //             Timers on the APB2 bus will be triggered at PCLK2
+//             (if APB2 is not divided, else at PCLK2 * 2)
+//
+//    `clock::dump_clocks` prints them all, including the timer clocks.
 //
 //    Configuration:
 //
@@ -287,6 +275,10 @@ fn clock_out(rcc: &RCC, gpioc: &GPIOC) {
 //    Now change the constant `OFFSET` so you get the same blinking frequency as in 1.
 //    Test and validate that you got the desired behavior.
 //
+//    (The toggle task now computes the offset from the `sysclk` resource,
+//    read back from `clocks`, so the blinking stays at BLINK_HZ for any
+//    configuration. Replace `offset` with a hard coded value to redo the exercise.)
+//
 //    Commit your answers (bare6_3)
 //
 // 4. Repeat experiment 2
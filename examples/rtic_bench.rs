@@ -0,0 +1,101 @@
+//! rtic_bench.rs
+//!
+//! Measuring execution time in clock cycles
+//!
+//! What it covers:
+//! - timing a piece of code with `cycle_count` (src/bench.rs)
+//! - calibrating the measurement overhead (`calibrate`)
+//! - integer vs. single precision floating point arithmetic on the Cortex-M4F
+//!
+//! > cargo run --example rtic_bench --release
+
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+
+use app::{
+    bench::{calibrate, cycle_count},
+    trace_init,
+};
+
+// number of loop iterations
+const N: u32 = 1_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+
+        // the cycle counter must be enabled before measuring
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // the cost of the CYCCNT reads, subtracted by `cycle_count`
+        let overhead = calibrate();
+        rprintln!("overhead {} cycles", overhead);
+
+        // read the loop count through a volatile read, so the compiler cannot
+        // compute the results at compile time
+        let n = unsafe { ptr::read_volatile(&N) };
+
+        let empty = cycle_count(|| {});
+        rprintln!("empty closure {} cycles (should be 0)", empty);
+
+        let mut int_result = 0u32;
+        let int_cycles = cycle_count(|| {
+            let mut acc = 1u32;
+            for i in 0..n {
+                acc = acc.wrapping_mul(3).wrapping_add(i) ^ (acc >> 7);
+            }
+            int_result = acc;
+        });
+
+        let mut float_result = 0f32;
+        let float_cycles = cycle_count(|| {
+            let mut acc = 1.0f32;
+            for i in 0..n {
+                acc = acc * 0.999 + i as f32 * 0.5;
+            }
+            float_result = acc;
+        });
+
+        rprintln!(
+            "integer: {} cycles, {} per iteration (result {})",
+            int_cycles,
+            int_cycles / n,
+            int_result
+        );
+        rprintln!(
+            "float:   {} cycles, {} per iteration (result {})",
+            float_cycles,
+            float_cycles / n,
+            float_result
+        );
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Notes:
+//
+// The Cortex-M4F has a single precision FPU, a multiply-add takes a few
+// cycles, comparable to integer arithmetic. Double precision (`f64`) is done
+// in software, try changing `f32` to `f64` and compare.
+//
+// The target must be `thumbv7em-none-eabihf` for the FPU to be used,
+// with `thumbv7em-none-eabi` floats are emulated in software (soft-float).
+//
+// Flash wait states affect the timing at higher clock frequencies, the
+// ART accelerator (instruction cache/prefetch) hides most of it for loops.
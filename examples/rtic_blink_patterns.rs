@@ -0,0 +1,128 @@
+//! rtic_blink_patterns.rs
+//!
+//! Blink patterns, a small state machine
+//!
+//! What it covers:
+//! - keeping the state (current pattern) in an `enum` resource
+//! - changing the state from a button interrupt
+//! - rendering the state from a periodic task, using pure logic (src/pattern.rs)
+//!
+//! > cargo run --example rtic_blink_patterns
+//!
+//! Press the user button (B1) to cycle Steady -> Fast -> SOS.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, gpioc::PC13, Edge, ExtiPin, Floating, Input, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    delay::ms_to_cycles,
+    led::Led,
+    pattern::{next_step, BlinkPattern, TICK_MS},
+    trace::print_banner,
+    trace_init,
+};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        led: Led<PA5<Output<PushPull>>>,
+        // tick period in clock cycles
+        offset: u32,
+        #[init(BlinkPattern::Steady)]
+        pattern: BlinkPattern,
+        // ticks since the pattern was selected
+        #[init(0)]
+        tick: u32,
+    }
+
+    #[init(schedule = [render])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        let offset = ms_to_cycles(TICK_MS, clocks.sysclk().0);
+        cx.schedule.render(cx.start + offset.cycles()).unwrap();
+
+        init::LateResources {
+            button,
+            led,
+            offset,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // same priority as `render`, so no locks are needed
+    #[task(binds = EXTI15_10, resources = [button, pattern, tick])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        // TODO: debounce, see rtic_exti_debounce.rs
+        *cx.resources.pattern = cx.resources.pattern.next();
+        // start the new pattern from the beginning
+        *cx.resources.tick = 0;
+        rprintln!("pattern {:?}", cx.resources.pattern);
+    }
+
+    #[task(resources = [led, pattern, tick, offset], schedule = [render])]
+    fn render(cx: render::Context) {
+        if next_step(*cx.resources.pattern, *cx.resources.tick) {
+            cx.resources.led.on().ok();
+        } else {
+            cx.resources.led.off().ok();
+        }
+        *cx.resources.tick = cx.resources.tick.wrapping_add(1);
+
+        cx.schedule
+            .render(cx.scheduled + cx.resources.offset.cycles())
+            .unwrap();
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// `next_step` is a pure function of (pattern, tick), it does not touch the
+// hardware. It can thus be tested on the host, and the rendering is trivially
+// restartable: resetting `tick` restarts the pattern.
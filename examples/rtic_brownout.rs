@@ -0,0 +1,111 @@
+//! rtic_brownout.rs
+//!
+//! Brown-out reset level, and the reset cause
+//!
+//! What it covers:
+//! - decoding and clearing the reset flags (`take_reset_cause`)
+//! - reading the brown-out reset (BOR) level from the option bytes (FLASH_OPTCR)
+//! - (optionally) programming a new BOR level
+//!
+//! > cargo run --example rtic_brownout
+//!
+//! Set `NEW_BOR` to program the BOR level. The option bytes are kept in flash,
+//! the setting persists across power cycles and re-flashing.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, stm32::FLASH};
+
+use app::{
+    reset::{take_reset_cause, BorLevel},
+    trace::print_banner,
+    trace_init,
+};
+
+// `None` leaves the option bytes untouched, e.g., `Some(BorLevel::Level3)`
+const NEW_BOR: Option<BorLevel> = None;
+
+// FLASH_OPTKEYR unlock sequence, RM0368 3.6.2
+const OPTKEY1: u32 = 0x0819_2A3B;
+const OPTKEY2: u32 = 0x4C5D_6E7F;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let device = cx.device;
+
+        // read and clear the reset flags, RCC_CSR, RM0368 6.3.21
+        let cause = take_reset_cause(&device.RCC);
+        rprintln!("reset cause {:?}", cause);
+
+        let bor = BorLevel::from_optcr(device.FLASH.optcr.read().bits());
+        report(bor);
+
+        if let Some(level) = NEW_BOR {
+            if level != bor {
+                set_bor_level(&device.FLASH, level);
+                report(BorLevel::from_optcr(device.FLASH.optcr.read().bits()));
+            }
+        }
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+};
+
+fn report(bor: BorLevel) {
+    match bor.threshold_mv() {
+        Some(mv) => rprintln!("BOR {:?}, reset below ~{} mV", bor, mv),
+        None => rprintln!("BOR off, reset below ~1.7 V (POR/PDR)"),
+    }
+}
+
+// Program BOR_LEV, RM0368 3.6.2 (option bytes programming)
+fn set_bor_level(flash: &FLASH, level: BorLevel) {
+    // unlock the option bytes (OPTLOCK)
+    flash.optkeyr.write(|w| w.optkey().bits(OPTKEY1));
+    flash.optkeyr.write(|w| w.optkey().bits(OPTKEY2));
+
+    while flash.sr.read().bsy().bit_is_set() {}
+    // only the BOR_LEV field is changed, the read protection (RDP) and the
+    // write protection (nWRP) are kept, a mistake there may lock the device
+    flash
+        .optcr
+        .modify(|_, w| unsafe { w.bor_lev().bits(level.bits()) });
+    // start the programming, and wait for it to complete
+    flash.optcr.modify(|_, w| w.optstrt().set_bit());
+    while flash.sr.read().bsy().bit_is_set() {}
+
+    // lock again
+    flash.optcr.modify(|_, w| w.optlock().set_bit());
+    rprintln!("BOR programmed");
+}
+
+// Notes:
+//
+// The BOR keeps the device in reset while VDD is below the threshold, instead
+// of running (and writing flash) at a voltage where the behavior is not
+// guaranteed. The factory setting is off (only the POR/PDR at ~1.7 V). For
+// battery powered designs, set a level matching the lowest supply where the
+// rest of the circuit still works.
+//
+// A BOR reset also sets PINRSTF (the NRST pin is driven low), and a power-on
+// also sets BORRSTF. `ResetCause` reports the most specific flag.
+//
+// On the Nucleo, VDD is a regulated 3.3 V, a brown-out needs a weak supply
+// (e.g., a lab supply ramped down slowly on the 3V3 pin with the USB
+// disconnected).
@@ -0,0 +1,139 @@
+//! rtic_button_pull.rs
+//!
+//! Internal pull-up/pull-down for buttons
+//!
+//! What it covers:
+//! - the internal pull resistors (`into_pull_up_input`, `into_pull_down_input`, `into_floating_input`)
+//! - the level of a released button, and the EXTI edge of a press, for each setting
+//! - reading the configuration back from GPIOA_PUPDR
+//!
+//! > cargo run --example rtic_button_pull
+//!
+//! Wiring for the external button on PA0 (CN7-28), depending on `PULL`:
+//! - `Pull::Up`, button between PA0 and GND
+//! - `Pull::Down`, button between PA0 and 3V3 (CN7-16)
+//! - `Pull::Floating`, button and an external resistor (e.g., 10k to 3V3)
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA0, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+    stm32::GPIOA,
+};
+
+use app::{
+    button::{PressEdge, Pull},
+    trace::print_banner,
+    trace_init,
+};
+
+// change to try the other configurations
+const PULL: Pull = Pull::Up;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PA0<Input<Floating>>,
+        #[init(0)]
+        presses: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+
+        // The HAL tracks the pull in the pin type (`PA0<Input<PullUp>>` etc.), so
+        // selecting it by a constant would give a different resource type for each
+        // choice. Instead the pin is configured floating, and the pull is set in
+        // GPIOA_PUPDR directly (`into_floating_input` clears the field).
+        let mut button = gpioa.pa0.into_floating_input();
+        // SAFETY: PA0 is owned here, and no other PUPDR field is changed
+        let pupdr = unsafe { &(*GPIOA::ptr()).pupdr };
+        pupdr.modify(|r, w| unsafe { w.bits(PULL.pupdr(r.bits(), 0)) });
+
+        let edge = match PULL.press_edge() {
+            Some(PressEdge::Falling) => Edge::FALLING,
+            Some(PressEdge::Rising) => Edge::RISING,
+            // depends on the external resistor, trigger on both and inspect the level
+            None => Edge::RISING_FALLING,
+        };
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, edge);
+        button.enable_interrupt(&mut device.EXTI);
+
+        rprintln!(
+            "PA0 {}, PUPDR0 = {:02b}",
+            PULL.name(),
+            pupdr.read().bits() & 0b11
+        );
+        match PULL.idle_high() {
+            Some(high) => rprintln!("  released: {}", if high { "high" } else { "low" }),
+            None => rprintln!("  released: given by the external circuit"),
+        }
+        rprintln!(
+            "  read now: {}",
+            if button.is_high().unwrap() {
+                "high"
+            } else {
+                "low"
+            }
+        );
+
+        init::LateResources { button }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI0, resources = [button, presses])]
+    fn exti0(cx: exti0::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+        *cx.resources.presses += 1;
+        rprintln!(
+            "edge {}, level {}",
+            cx.resources.presses,
+            if cx.resources.button.is_high().unwrap() {
+                "high"
+            } else {
+                "low"
+            }
+        );
+    }
+};
+
+// Notes:
+//
+// A floating input without an external resistor picks up noise, touching the
+// wire is often enough to trigger interrupts. Try `Pull::Floating` with nothing
+// connected.
+//
+// With the wrong combination (e.g., button to GND and `Pull::Down`) the pin
+// is low whether pressed or not, and no edge is ever seen. This is the most
+// common wiring mistake.
+//
+// The internal pulls are weak, 30..50 kOhm (datasheet, I/O static
+// characteristics). An external resistor in parallel dominates. The user
+// button (B1) on PC13 has an external pull-up (4.7 kOhm, and an RC filter)
+// on the Nucleo board, so PC13 is used floating in the other examples.
+// Enabling the internal pull-down on PC13 forms a divider with the external
+// pull-up, which still reads high, but wastes current (~70 uA) while released.
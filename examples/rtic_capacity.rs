@@ -0,0 +1,114 @@
+//! rtic_capacity.rs
+//!
+//! Software task capacity
+//!
+//! What it covers:
+//! - passing a payload to a software task as an argument
+//! - the message queue of a software task (`capacity = N`)
+//! - handling a full queue (`spawn` returns the payload as `Err`)
+//!
+//! > cargo run --example rtic_capacity
+//!
+//! Press the user button (B1), each press spawns a burst of `BURST` messages.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+};
+
+use app::{trace::print_banner, trace_init};
+
+// messages spawned per button press, more than the capacity of `process`
+const BURST: u32 = 10;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        // sequence number of the next message
+        #[init(0)]
+        seq: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // setup the user button (B1) on PC13, the Nucleo has an external pull-up
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources { button }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle, press the button");
+        loop {
+            continue;
+        }
+    }
+
+    // Runs at the same priority (1) as `process`, so none of the spawned
+    // messages are processed before the whole burst is spawned.
+    #[task(binds = EXTI15_10, resources = [button, seq], spawn = [process])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        rprintln!("burst of {}", BURST);
+        for _ in 0..BURST {
+            let seq = *cx.resources.seq;
+            match cx.spawn.process(seq) {
+                Ok(()) => *cx.resources.seq += 1,
+                // the queue is full, the payload is handed back to us
+                Err(seq) => {
+                    rprintln!("  queue full, message {} dropped", seq);
+                    // no point in trying the rest of the burst
+                    break;
+                }
+            }
+        }
+    }
+
+    // Up to 8 pending messages, the default capacity is 1
+    #[task(capacity = 8)]
+    fn process(_cx: process::Context, seq: u32) {
+        rprintln!("  process {}", seq);
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// Each software task has a statically allocated queue holding `capacity`
+// messages (payloads), so a larger capacity costs RAM (capacity x payload size,
+// plus some book keeping).
+//
+// With `capacity = 8` and `BURST = 10`, the first 8 messages are queued and
+// the 9th spawn fails. The dropped sequence number is re-used for the next
+// burst, so no number is skipped in the output.
+//
+// Try giving `process` a higher priority (e.g., `priority = 2`), now each
+// message is processed as soon as it is spawned (preempting the button task)
+// and the queue never fills.
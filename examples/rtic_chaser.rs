@@ -0,0 +1,129 @@
+//! rtic_chaser.rs
+//!
+//! LED chaser ("knight rider")
+//!
+//! What it covers:
+//! - downgrading typed pins (PA5, PA6, ...) to a common type (PA)
+//! - storing pins in an array resource
+//! - keeping animation state (position, direction) in resources
+//!
+//! > cargo run --example rtic_chaser
+//!
+//! Connect LEDs (with series resistors) to PA6 (CN10-13), PA7 (CN10-15)
+//! and PA8 (CN10-23). PA5 drives the on-board LED (LD2).
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA, Output, PushPull},
+    prelude::*,
+};
+
+use app::{schedule::try_reschedule, trace_init};
+
+// step interval in clock cycles, 100 ms at 16 MHz
+const STEP: u32 = 1_600_000;
+// number of LEDs
+const N: usize = 4;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        leds: [PA<Output<PushPull>>; N],
+        #[init(0)]
+        position: usize,
+        #[init(true)]
+        forward: bool,
+    }
+
+    #[init(schedule = [step])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // setup LEDs, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+
+        // Each pin has its own type (PA5<MODE>, PA6<MODE>, ...), so they
+        // cannot be stored in the same array. `downgrade` erases the pin number
+        // (kept at run-time instead), giving the common type PA<MODE>.
+        let leds = [
+            gpioa.pa5.into_push_pull_output().downgrade(),
+            gpioa.pa6.into_push_pull_output().downgrade(),
+            gpioa.pa7.into_push_pull_output().downgrade(),
+            gpioa.pa8.into_push_pull_output().downgrade(),
+        ];
+
+        cx.schedule.step(cx.start + STEP.cycles()).unwrap();
+
+        init::LateResources { leds }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [leds, position, forward], schedule = [step])]
+    fn step(cx: step::Context) {
+        let leds = cx.resources.leds;
+        let position = cx.resources.position;
+        let forward = cx.resources.forward;
+
+        // light up only the LED at `position`
+        for (i, led) in leds.iter_mut().enumerate() {
+            if i == *position {
+                led.set_high().ok();
+            } else {
+                led.set_low().ok();
+            }
+        }
+
+        // bounce at the ends
+        if *forward && *position == N - 1 {
+            *forward = false;
+        } else if !*forward && *position == 0 {
+            *forward = true;
+        }
+
+        if *forward {
+            *position += 1;
+        } else {
+            *position -= 1;
+        }
+
+        if !try_reschedule(cx.schedule.step(cx.scheduled + STEP.cycles()), "step") {
+            // the task stopped, leave all LEDs off
+            for led in leds.iter_mut() {
+                led.set_low().ok();
+            }
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The downgraded pins are slightly less efficient, as the pin number is
+// stored in the struct and used at run-time to compute the register masks.
+// This is rarely a problem, and it allows iterating over the pins.
+//
+// Pins of different ports (e.g., PA5 and PB3) cannot be stored together
+// this way, as `downgrade` only erases the pin number, not the port.
@@ -0,0 +1,90 @@
+//! rtic_crc.rs
+//!
+//! Hardware CRC
+//!
+//! What it covers:
+//! - computing a CRC32 using the CRC peripheral, RM0368 chapter 4
+//! - comparing with a software implementation (`app::crc::crc32`)
+//! - the execution time of both (cycles, CYCCNT)
+//!
+//! > cargo run --example rtic_crc --release
+//!
+//! The CRC unit has a fixed polynomial (0x04C1_1DB7) and takes 32 bit words
+//! as input, see `src/crc.rs` for the exact CRC definition.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::stm32::{CRC, RCC};
+
+use app::{crc::crc32, trace_init};
+
+// some data
+const DATA: [u32; 8] = [
+    0x0000_0000,
+    0x1234_5678,
+    0xDEAD_BEEF,
+    0xFFFF_FFFF,
+    0x3132_3334,
+    0x3536_3738,
+    0x0BAD_F00D,
+    0x8000_0001,
+];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the cycle counter (CYCCNT) for measurements
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        enable_crc(&device.RCC);
+
+        let start = DWT::get_cycle_count();
+        let hw = hw_crc32(&device.CRC, &DATA);
+        let hw_cycles = DWT::get_cycle_count().wrapping_sub(start);
+
+        let start = DWT::get_cycle_count();
+        let sw = crc32(&DATA);
+        let sw_cycles = DWT::get_cycle_count().wrapping_sub(start);
+
+        rprintln!("hardware 0x{:08x}, {} cycles", hw, hw_cycles);
+        rprintln!("software 0x{:08x}, {} cycles", sw, sw_cycles);
+        rprintln!("{}", if hw == sw { "PASS" } else { "FAIL" });
+    }
+};
+
+// power on the CRC unit, RCC_AHB1ENR, RM0368 6.3.9
+fn enable_crc(rcc: &RCC) {
+    rcc.ahb1enr.modify(|_, w| w.crcen().enabled());
+}
+
+/// CRC32 of `data` using the CRC peripheral
+fn hw_crc32(crc: &CRC, data: &[u32]) -> u32 {
+    // reset to the initial value 0xFFFF_FFFF, CRC_CR, RM0368 4.4.3
+    crc.cr.write(|w| w.reset().reset());
+    for word in data {
+        // each write takes 4 AHB cycles, stalling the bus (no polling needed)
+        crc.dr.write(|w| w.dr().bits(*word));
+    }
+    crc.dr.read().dr().bits()
+}
+
+// Notes:
+//
+// The hardware CRC is roughly one word per 4 cycles, while the bitwise software
+// implementation needs a few cycles per bit. A table driven software CRC is
+// faster, at the cost of a 1 KB table in flash.
+//
+// To match the "standard" CRC32 (zip, Ethernet), bit reverse the input words
+// (`u32::reverse_bits`), bit reverse the result and invert it. Byte streams
+// not a multiple of 4 bytes must be padded (or the tail computed in software).
@@ -0,0 +1,224 @@
+//! rtic_css.rs
+//!
+//! Clock Security System (CSS), surviving a HSE failure
+//!
+//! What it covers:
+//! - enabling the CSS on the HSE (RCC_CR CSSON)
+//! - the NMI raised on a HSE failure, a `cortex-m-rt` `#[exception]` handler
+//! - clearing the CSS flag, and restoring SYSCLK with the PLL from the HSI
+//! - reporting the failure over RTT, from a task pended by the NMI
+//!
+//! > cargo run --example rtic_css
+//!
+//! The HSE is the 8 MHz MCO of the ST-LINK (bypass mode), see `rtic_hse.rs`.
+//! To simulate a failure, stop the clock at OSC_IN (PH0, CN7-29), see notes.
+//! The LED (LD2, PA5) keeps blinking at the same rate before and after.
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m_rt::exception;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+    stm32::{self, Interrupt, RCC},
+};
+
+use app::{
+    clock::{start_hse, HSE_TIMEOUT},
+    delay::ms_to_cycles,
+    led::Led,
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// HSE frequency in MHz, the ST-LINK MCO on the Nucleo
+const HSE_MHZ: u32 = 8;
+// OSC_IN driven by an external clock (true), or a crystal (false)
+const BYPASS: bool = true;
+// SYSCLK in Hz, from the HSE, and after a failure from the HSI
+const SYSCLK_HZ: u32 = 84_000_000;
+// LED toggle period in ms
+const PERIOD_MS: u32 = 500;
+
+// HSE failures detected, written by the NMI handler
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // check the HSE before handing the RCC to the HAL, see `rtic_hse.rs`
+        let hse = start_hse(&device.RCC, BYPASS, HSE_TIMEOUT);
+        if hse {
+            // monitor the HSE, RCC_CR CSSON, RM0368 6.2.7
+            device.RCC.cr.modify(|_, w| w.csson().on());
+            rprintln!("HSE ready, {} MHz, CSS on", HSE_MHZ);
+        } else {
+            rprintln!("warning: HSE not ready, running from HSI, nothing to monitor");
+        }
+
+        let rcc = device.RCC.constrain();
+        let cfgr = rcc.cfgr.sysclk(SYSCLK_HZ.hz());
+        let cfgr = if hse {
+            cfgr.use_hse(HSE_MHZ.mhz())
+        } else {
+            cfgr
+        };
+        let clocks = cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        cx.schedule.toggle(cx.start).unwrap();
+
+        init::LateResources { led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // blink, the period stays PERIOD_MS as SYSCLK is restored to SYSCLK_HZ
+    #[task(resources = [led], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        cx.resources.led.toggle().ok();
+
+        let offset = ms_to_cycles(PERIOD_MS, SYSCLK_HZ);
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + offset.cycles()), "toggle") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    // pended by the NMI handler, reports the failure outside of the NMI
+    #[task(binds = EXTI1)]
+    fn clock_failure(_cx: clock_failure::Context) {
+        // SAFETY: read only access
+        let rcc = unsafe { &*RCC::ptr() };
+        let source = if rcc.pllcfgr.read().pllsrc().is_hse() {
+            "HSE"
+        } else {
+            "HSI"
+        };
+        rprintln!(
+            "HSE failure ({} so far), SYSCLK from the PLL ({})",
+            FAILURES.load(Ordering::Relaxed),
+            source
+        );
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// The CSS raises the NMI, which cannot be masked, not even by RTIC locks
+#[exception]
+fn NonMaskableInt() {
+    // SAFETY: the RCC is only accessed here after `init`, `clock_failure` only reads
+    let rcc = unsafe { &*RCC::ptr() };
+
+    // the CSS flag, RCC_CIR CSSF, RM0368 6.3.4
+    if rcc.cir.read().cssf().is_interrupted() {
+        // clear the flag, else the NMI is taken again on return
+        rcc.cir.modify(|_, w| w.cssc().clear());
+        pll_from_hsi(rcc);
+        FAILURES.fetch_add(1, Ordering::Relaxed);
+        // printing here could break an `rprintln!` in progress, leave it to a task
+        rtic::pend(Interrupt::EXTI1);
+    }
+}
+
+// Restore SYSCLK (SYSCLK_HZ), with the PLL from the HSI
+//
+// On a failure, the hardware has already switched SYSCLK to the HSI, and
+// turned off the HSE and the PLL (fed by the HSE), RM0368 6.2.7.
+fn pll_from_hsi(rcc: &stm32::rcc::RegisterBlock) {
+    // make sure SYSCLK is on the HSI and the PLL is off, RM0368 6.3.2
+    rcc.cfgr.modify(|_, w| w.sw().hsi());
+    while !rcc.cfgr.read().sws().is_hsi() {}
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    // HSI 16 MHz / M 16 x N 336 / P 4 = 84 MHz, Q 7 gives 48 MHz (USB)
+    rcc.pllcfgr.write(|w| unsafe {
+        w.pllsrc()
+            .hsi()
+            .pllm()
+            .bits(16)
+            .plln()
+            .bits(336)
+            .pllp()
+            .div4()
+            .pllq()
+            .bits(7)
+    });
+    rcc.cr.modify(|_, w| w.pllon().on());
+    while rcc.cr.read().pllrdy().is_not_ready() {}
+
+    // the flash wait states and the APB prescalers are kept from `freeze`
+    rcc.cfgr.modify(|_, w| w.sw().pll());
+    while !rcc.cfgr.read().sws().is_pll() {}
+}
+
+// Notes:
+//
+// The CSS watches the HSE once it is ready. If the HSE clock stops, the
+// hardware (RM0368 6.2.7):
+//
+// - turns off the HSE (and the PLL if fed by the HSE, in use as SYSCLK)
+// - switches SYSCLK to the HSI
+// - sets CSSF in RCC_CIR, and raises the NMI (Non Maskable Interrupt)
+//
+// The core keeps running, at 16 MHz from the HSI. Without the handler
+// restoring the PLL, everything timed in clock cycles (the CYCCNT schedule,
+// the UART baud rates, the timers) would run 84 / 16 = 5.25 times slower.
+// The CSS switch is a safe fall back, the application decides how to
+// continue (reduced function, a controlled shut down, ...).
+//
+// The NMI is entered again as long as CSSF is set, so clearing it (CSSC) is
+// the first thing to do. The NMI preempts everything, including critical
+// sections and RTIC locks, so it must not touch any RTIC resource, and
+// should do as little as possible. Here it only restores the clock, counts
+// (an atomic) and pends `clock_failure` for the report.
+//
+// Simulating a failure: the HSE of the Nucleo is the ST-LINK MCO, routed to
+// OSC_IN by solder bridge SB50 (UM1724 6.7.1). Briefly pull OSC_IN (PH0,
+// CN7-29) to GND through a resistor (~100 Ohm, not a plain wire, to protect
+// the MCO output). With a crystal, touching or removing the crystal stops
+// it. Expected output:
+//
+//   HSE failure (1 so far), SYSCLK from the PLL (HSI)
+//
+// and the LED keeps blinking at the same rate. The HSE stays off after the
+// failure, restart it (`clock::start_hse`) to switch back, as in
+// `rtic_pll_switch.rs`.
+//
+// Triggering the NMI by software (SCB ICSR NMIPENDSET) runs the handler
+// without CSSF set, it then does nothing. That is a test of the flag check,
+// not of the CSS.
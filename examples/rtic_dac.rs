@@ -0,0 +1,142 @@
+//! rtic_dac.rs
+//!
+//! Sine wave output from a lookup table
+//!
+//! What it covers:
+//! - generating a lookup table at compile time (`const fn`)
+//! - stepping through the table from a timer task at a fixed rate
+//! - relating the output frequency to the table length and step rate
+//!
+//! > cargo run --example rtic_dac
+//!
+//! NOTE: The STM32F401 has no DAC. Here the 12 bit samples are output as the
+//! duty cycle of TIM3 CH1 (PA6, CN10-13), filter with an RC low-pass
+//! (e.g., 1k + 100nF, cut-off ~1.6 kHz) to get the analog waveform.
+//!
+//! On devices with a DAC (e.g., STM32F2xx, STM32F405/407, STM32F446), DAC1
+//! is on PA4, and each sample is written to DAC_DHR12R1 instead:
+//!
+//! ```ignore
+//! dac.dhr12r1.write(|w| unsafe { w.dacc1dhr().bits(sample) });
+//! ```
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    pwm::{self, PwmChannels, C1},
+    stm32::{TIM2, TIM3},
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// number of samples per period
+const N: usize = 32;
+// sample (step) rate in Hz
+const STEP_HZ: u32 = 3_200;
+// output frequency, f_out = STEP_HZ / N = 100 Hz
+const F_OUT: u32 = STEP_HZ / N as u32;
+
+// one period of a sine, 12 bit unsigned (0..=4095, mid-scale 2048)
+const TABLE: [u16; N] = sine_table();
+
+// Bhaskara I's approximation, sin(pi t) ~ 16 t (1 - t) / (5 - 4 t (1 - t)), 0 <= t <= 1
+//
+// Floating point is not allowed in `const fn`, so the approximation is
+// evaluated in integer arithmetic (max error ~0.2% of full scale).
+const fn sine_table() -> [u16; N] {
+    let half = (N / 2) as i32;
+    let mut table = [0u16; N];
+    let mut i = 0;
+    while i < N {
+        // k in 0..half, over the positive or negative half period
+        let k = (i % (N / 2)) as i32;
+        let p = k * (half - k);
+        let sin = 2047 * 16 * p / (5 * half * half - 4 * p);
+        table[i] = if i < N / 2 {
+            (2048 + sin) as u16
+        } else {
+            (2048 - sin) as u16
+        };
+        i += 1;
+    }
+    table
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        timer: Timer<TIM2>,
+        pwm: PwmChannels<TIM3, C1>,
+        #[init(0)]
+        index: usize,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).pclk1(42.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        // "DAC" output, 84 MHz / 20 kHz gives 4200 duty steps (> 12 bits)
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa6.into_alternate_af2();
+        let mut pwm = pwm::tim3(device.TIM3, pin, clocks, 20.khz());
+        pwm.enable();
+
+        // step rate
+        let mut timer = Timer::tim2(device.TIM2, STEP_HZ.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        rprintln!("{:?}", TABLE);
+        rprintln!("{} samples at {} Hz, f_out {} Hz", N, STEP_HZ, F_OUT);
+
+        init::LateResources { timer, pwm }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = TIM2, resources = [timer, pwm, index])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        let sample = TABLE[*cx.resources.index];
+        // scale 12 bit to the PWM range
+        let max = cx.resources.pwm.get_max_duty() as u32;
+        cx.resources
+            .pwm
+            .set_duty((sample as u32 * max / 4096) as u16);
+
+        *cx.resources.index = (*cx.resources.index + 1) % N;
+    }
+};
+
+// Notes:
+//
+// The output frequency is f_out = STEP_HZ / N. To tune it:
+// - change STEP_HZ (limited by the interrupt load, each step is an interrupt)
+// - change N (a longer table gives a smoother waveform, at the cost of
+//   a lower frequency for the same STEP_HZ)
+// - step by more than one entry per interrupt (f_out = STEP_HZ * step / N),
+//   i.e., direct digital synthesis (DDS)
+//
+// A 100 Hz tone is audible through a small speaker (with a series resistor
+// and a capacitor to block DC), or observe it on a scope.
+//
+// For higher rates, let a timer trigger the DAC and feed it from DMA, then
+// no CPU time is spent per sample.
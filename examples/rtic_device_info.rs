@@ -0,0 +1,87 @@
+//! rtic_device_info.rs
+//!
+//! Identify the MCU
+//!
+//! What it covers:
+//! - reading fixed address registers using `core::ptr::read_volatile`
+//! - the 96 bit unique device ID, RM0368 24.2
+//! - the flash size register, RM0368 24.3
+//! - the DBGMCU_IDCODE (device and revision), RM0368 23.6.1
+//!
+//! > cargo run --example rtic_device_info
+
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+
+use app::{
+    trace::{chip, DBGMCU_IDCODE},
+    trace_init,
+};
+
+// unique device ID, 3 words, RM0368 24.2
+const UID_BASE: usize = 0x1FFF_7A10;
+// flash size in KB (16 bits), RM0368 24.3
+const FLASH_SIZE: usize = 0x1FFF_7A22;
+
+#[rtic::app(device = stm32f4xx_hal::stm32)]
+const APP: () = {
+    #[init]
+    fn init(_cx: init::Context) {
+        trace_init!();
+
+        // SAFETY: read only accesses to always present system memory/debug registers
+        let (uid, flash_kb, idcode) = unsafe {
+            (
+                [
+                    ptr::read_volatile(UID_BASE as *const u32),
+                    ptr::read_volatile((UID_BASE + 4) as *const u32),
+                    ptr::read_volatile((UID_BASE + 8) as *const u32),
+                ],
+                ptr::read_volatile(FLASH_SIZE as *const u16),
+                ptr::read_volatile(DBGMCU_IDCODE as *const u32),
+            )
+        };
+
+        let dev_id = idcode & 0xfff;
+        let rev_id = (idcode >> 16) as u16;
+
+        rprintln!("IDCODE   0x{:08x}", idcode);
+        rprintln!("device   0x{:03x}, {}", dev_id, chip(dev_id));
+        rprintln!("revision 0x{:04x}, {}", rev_id, revision(dev_id, rev_id));
+        rprintln!("flash    {} KB", flash_kb);
+        rprintln!("UID      {:08x}-{:08x}-{:08x}", uid[2], uid[1], uid[0]);
+
+        // UID[31:0] holds the X and Y coordinates of the die on the wafer,
+        // UID[39:32] the wafer number, and UID[95:40] the lot number (ASCII)
+        rprintln!(
+            "  wafer x {}, y {}, wafer {}",
+            uid[0] >> 16,
+            uid[0] & 0xffff,
+            uid[1] & 0xff
+        );
+    }
+};
+
+fn revision(dev_id: u32, rev_id: u16) -> &'static str {
+    match (dev_id, rev_id) {
+        (0x423, 0x1000) | (0x433, 0x1000) | (0x431, 0x1000) => "A",
+        (0x423, 0x1001) | (0x433, 0x1001) => "Z",
+        _ => "unknown",
+    }
+}
+
+// Notes:
+//
+// The Nucleo-F401RE should report 0x433 and 512 KB. (Some F401 devices have
+// more flash than marked, the flash size register reports the marked size.)
+//
+// On some devices DBGMCU_IDCODE reads as 0 unless a debugger is attached,
+// see the errata sheet (ES0222 for the F401). Use the UID to identify a
+// board in the field.
+//
+// The UID is unique per device, and is sometimes used as a (non secret)
+// serial number, e.g., for USB descriptors.
@@ -0,0 +1,265 @@
+//! rtic_dma_double_buffer.rs
+//!
+//! Gap free streaming with a DMA ping-pong buffer
+//!
+//! What it covers:
+//! - a circular DMA transfer from a `'static` buffer to a peripheral, paced by a timer
+//! - the half transfer (HT) and transfer complete (TC) interrupts
+//! - refilling one half of the buffer while the DMA reads the other half
+//! - detecting that the refill fell behind (underrun)
+//!
+//! > cargo run --example rtic_dma_double_buffer --release
+//!
+//! An "audio" stream, a 440 Hz sine generated sample by sample (direct digital
+//! synthesis) at 20 kHz. The STM32F401 has no DAC, the samples are written to
+//! the duty cycle of TIM3 CH1 (PA6, CN10-13), filter as in `rtic_dac.rs`.
+//! The TIM3 update event requests one DMA transfer (one sample) per PWM period.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    pwm,
+    stm32::{DMA1, TIM3},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// sample rate in Hz, the PWM frequency
+const SAMPLE_HZ: u32 = 20_000;
+// output frequency in Hz
+const F_OUT: u32 = 440;
+// samples per half buffer, 10 ms at 20 kHz
+const HALF: usize = 200;
+// halves per report, 1 s
+const REPORT: u32 = SAMPLE_HZ / HALF as u32;
+
+// sine table length, a power of 2, indexed by the top bits of the phase
+const TABLE_BITS: u32 = 6;
+const N: usize = 1 << TABLE_BITS;
+// phase increment per sample, F_OUT / SAMPLE_HZ of a full turn (2^32)
+const STEP: u32 = (((F_OUT as u64) << 32) / SAMPLE_HZ as u64) as u32;
+
+// one period of a sine, 12 bit unsigned (0..=4095), see `rtic_dac.rs`
+const TABLE: [u16; N] = sine_table();
+
+const fn sine_table() -> [u16; N] {
+    let half = (N / 2) as i32;
+    let mut table = [0u16; N];
+    let mut i = 0;
+    while i < N {
+        let k = (i % (N / 2)) as i32;
+        let p = k * (half - k);
+        let sin = 2047 * 16 * p / (5 * half * half - 4 * p);
+        table[i] = if i < N / 2 {
+            (2048 + sin) as u16
+        } else {
+            (2048 - sin) as u16
+        };
+        i += 1;
+    }
+    table
+}
+
+// the sample source, a phase accumulator stepping through TABLE
+struct Synth {
+    phase: u32,
+    // the PWM duty of full scale
+    max_duty: u32,
+}
+
+impl Synth {
+    // fill one half of the buffer with the next samples
+    fn fill(&mut self, half: &mut [u16]) {
+        for sample in half.iter_mut() {
+            let raw = TABLE[(self.phase >> (32 - TABLE_BITS)) as usize] as u32;
+            *sample = (raw * self.max_duty / 4096) as u16;
+            self.phase = self.phase.wrapping_add(STEP);
+        }
+    }
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        dma: DMA1,
+        buffer: &'static mut [u16; 2 * HALF],
+        synth: Synth,
+        // number of halves refilled, and of halves refilled too late
+        #[init(0)]
+        halves: u32,
+        #[init(0)]
+        underruns: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        // Read by the DMA, independently of the CPU, so it must stay valid and
+        // at a fixed address for as long as the stream runs (forever here).
+        // A `static` has both, and `'static` proves it to the compiler.
+        static mut BUFFER: [u16; 2 * HALF] = [0; 2 * HALF];
+
+        trace_init!();
+
+        let device = cx.device;
+
+        // power on DMA1, RM0368 6.3.9
+        device.RCC.ahb1enr.modify(|_, w| w.dma1en().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).pclk1(42.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        // 84 MHz / 20 kHz gives 4200 duty steps (> 12 bits)
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa6.into_alternate_af2();
+        let mut pwm = pwm::tim3(device.TIM3, pin, clocks, SAMPLE_HZ.hz());
+        let mut synth = Synth {
+            phase: 0,
+            max_duty: pwm.get_max_duty() as u32,
+        };
+
+        // both halves hold valid samples before the DMA starts
+        let buffer: &'static mut [u16; 2 * HALF] = BUFFER;
+        synth.fill(&mut buffer[..]);
+
+        // SAFETY: the HAL owns TIM3, we only set the DMA request enable, and
+        // take the address of CCR1
+        let tim3 = unsafe { &*TIM3::ptr() };
+
+        // TIM3_UP is served by DMA1 stream 2, channel 5, RM0368 9.3.3 Table 27
+        let dma = device.DMA1;
+        let stream = &dma.st[2];
+        stream
+            .par
+            .write(|w| unsafe { w.pa().bits(&tim3.ccr1 as *const _ as u32) });
+        stream
+            .m0ar
+            .write(|w| unsafe { w.m0a().bits(buffer.as_ptr() as u32) });
+        // the whole buffer, NDTR is reloaded at the end (circular mode)
+        stream.ndtr.write(|w| w.ndt().bits((2 * HALF) as u16));
+        stream.cr.write(|w| {
+            w.chsel()
+                .bits(5)
+                .dir()
+                .memory_to_peripheral()
+                .psize()
+                .bits16()
+                .msize()
+                .bits16()
+                // always write CCR1, step through the buffer
+                .pinc()
+                .fixed()
+                .minc()
+                .incremented()
+                .circ()
+                .enabled()
+                // an interrupt when the DMA is done with each half
+                .htie()
+                .enabled()
+                .tcie()
+                .enabled()
+                .teie()
+                .enabled()
+        });
+        stream.cr.modify(|_, w| w.en().enabled());
+
+        // a DMA request on each update event, RM0368 13.4.4
+        tim3.dier.modify(|_, w| w.ude().enabled());
+        pwm.enable();
+
+        rprintln!(
+            "{} Hz at {} Hz, 2 x {} samples ({} ms per half)",
+            F_OUT,
+            SAMPLE_HZ,
+            HALF,
+            1000 * HALF as u32 / SAMPLE_HZ
+        );
+
+        init::LateResources { dma, buffer, synth }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = DMA1_STREAM2, priority = 2, resources = [dma, buffer, synth, halves, underruns], spawn = [report])]
+    fn dma1_stream2(cx: dma1_stream2::Context) {
+        let dma = cx.resources.dma;
+
+        // read the status, then clear the flags, DMA_LISR/DMA_LIFCR, RM0368 9.5.1
+        let lisr = dma.lisr.read();
+        dma.lifcr
+            .write(|w| w.chtif2().set_bit().ctcif2().set_bit().cteif2().set_bit());
+
+        if lisr.teif2().bit_is_set() {
+            rprintln!("transfer error");
+            return;
+        }
+
+        // both flags set, one half was not refilled in time and is played again
+        if lisr.htif2().bit_is_set() && lisr.tcif2().bit_is_set() {
+            *cx.resources.underruns += 1;
+        }
+
+        let (first, second) = cx.resources.buffer.split_at_mut(HALF);
+        if lisr.tcif2().bit_is_set() {
+            // the DMA wrapped around and reads the first half, refill the second
+            cx.resources.synth.fill(second);
+        } else if lisr.htif2().bit_is_set() {
+            // the DMA reads the second half, refill the first
+            cx.resources.synth.fill(first);
+        }
+
+        *cx.resources.halves += 1;
+        if *cx.resources.halves % REPORT == 0 {
+            cx.spawn
+                .report(*cx.resources.halves, *cx.resources.underruns)
+                .ok();
+        }
+    }
+
+    // off the DMA interrupt, printing takes a while
+    #[task(priority = 1)]
+    fn report(_cx: report::Context, halves: u32, underruns: u32) {
+        rprintln!("halves {}, underruns {}", halves, underruns);
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The buffer is split in two halves, A and B. The DMA reads A, raises HT and
+// goes on with B, while the CPU refills A. At TC the DMA wraps around to A
+// (circular mode), and the CPU refills B. The DMA never waits for the CPU, so
+// the stream has no gaps, as long as each refill finishes within the time the
+// DMA takes for the other half (HALF / SAMPLE_HZ, 10 ms here).
+//
+// With a single buffer the CPU would have to refill it between the TC
+// interrupt and the next request, i.e., within one sample period (50 us). The
+// DMA would otherwise play a mix of old and new samples, or, if stopped for
+// the refill, leave a gap in the output. The ping-pong buffer turns the
+// deadline from one sample period into HALF sample periods, at the cost of
+// HALF samples of latency.
+//
+// The halves are disjoint (`split_at_mut`), and the handler only writes the
+// half the DMA has just finished reading. This is not checked by the compiler,
+// the DMA accesses the buffer behind its back. An underrun (both HT and TC
+// set) means the DMA has passed the other half before the handler ran, the
+// flags no longer tell which half is free, and the output glitches.
+//
+// The DMA streams also have a hardware double buffer mode (DBM in DMA_SxCR,
+// RM0368 9.3.9), with two separate buffers at M0AR and M1AR, swapped at each
+// TC. The CT bit tells which buffer is in use, the other one may be refilled,
+// and the buffers need not be adjacent.
@@ -0,0 +1,157 @@
+//! rtic_dma_mem2mem.rs
+//!
+//! DMA memory to memory transfer
+//!
+//! What it covers:
+//! - setting up a DMA2 stream for memory to memory transfers (register level)
+//! - the transfer complete (TC) interrupt
+//! - buffer requirements (`'static` lifetime, alignment)
+//!
+//! > cargo run --example rtic_dma_mem2mem
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, stm32::DMA2};
+
+use app::{trace::print_banner, trace_init};
+
+// number of words to copy
+const N: usize = 256;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        dma: DMA2,
+        src: &'static [u32; N],
+        dst: &'static mut [u32; N],
+        // cycle count at the start of the transfer
+        start: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        // The DMA accesses the buffers independently of the CPU, so they must
+        // stay valid (and must not move) until the transfer completes.
+        // `static` buffers live for the entire program (`'static`), and their
+        // addresses are fixed. `u32` arrays are word aligned, as required
+        // for word (MSIZE/PSIZE = 32 bit) transfers.
+        static mut SRC: [u32; N] = [0; N];
+        static mut DST: [u32; N] = [0; N];
+
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // the cycle counter is used to time the transfer
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on DMA2, RM0368 6.3.9
+        device.RCC.ahb1enr.modify(|_, w| w.dma2en().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        for (i, word) in SRC.iter_mut().enumerate() {
+            *word = (i as u32).wrapping_mul(0x9E37_79B9);
+        }
+        let src: &'static [u32; N] = SRC;
+        let dst: &'static mut [u32; N] = DST;
+
+        // Only DMA2 can do memory to memory transfers, RM0368 9.3.6
+        // In this mode the "peripheral" port (PAR) is the source, and the
+        // memory port (M0AR) is the destination.
+        let dma = device.DMA2;
+        let stream = &dma.st[0];
+
+        stream
+            .par
+            .write(|w| unsafe { w.pa().bits(src.as_ptr() as u32) });
+        stream
+            .m0ar
+            .write(|w| unsafe { w.m0a().bits(dst.as_ptr() as u32) });
+        stream.ndtr.write(|w| w.ndt().bits(N as u16));
+        // FIFO mode (direct mode disabled) is mandatory for memory to memory
+        stream.fcr.write(|w| w.dmdis().disabled().fth().full());
+        stream.cr.write(|w| {
+            w.dir()
+                .memory_to_memory()
+                .psize()
+                .bits32()
+                .msize()
+                .bits32()
+                .pinc()
+                .incremented()
+                .minc()
+                .incremented()
+                .tcie()
+                .enabled()
+                .teie()
+                .enabled()
+        });
+
+        rprintln!("copy {} words", N);
+        let start = DWT::get_cycle_count();
+        // start the transfer
+        stream.cr.modify(|_, w| w.en().enabled());
+
+        init::LateResources {
+            dma,
+            src,
+            dst,
+            start,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = DMA2_STREAM0, resources = [dma, src, dst, start])]
+    fn dma2_stream0(cx: dma2_stream0::Context) {
+        let end = DWT::get_cycle_count();
+        let dma = cx.resources.dma;
+
+        // read the status, then clear the flags, DMA_LISR/DMA_LIFCR, RM0368 9.5.1
+        let lisr = dma.lisr.read();
+        dma.lifcr
+            .write(|w| w.ctcif0().set_bit().cteif0().set_bit().chtif0().set_bit());
+
+        if lisr.teif0().bit_is_set() {
+            rprintln!("transfer error");
+            return;
+        }
+
+        if lisr.tcif0().bit_is_set() {
+            // the transfer is complete, the CPU may now access the buffers
+            let ok = cx.resources.src == cx.resources.dst;
+            rprintln!(
+                "transfer complete in {} cycles, {}",
+                end.wrapping_sub(*cx.resources.start),
+                if ok { "verified" } else { "MISMATCH" }
+            );
+        }
+    }
+};
+
+// Notes:
+//
+// Compare with a CPU copy (`dst.copy_from_slice(src)`), and try byte transfers
+// (MSIZE/PSIZE = 8 bit, 4 x NDTR). Larger FIFO thresholds and bursts improve
+// the throughput on the bus matrix.
+//
+// While the transfer is ongoing, the CPU is free to do other work (here it
+// returns from `init` and enters `idle`). Touching `dst` before the TC
+// interrupt would race with the DMA, which is why the buffers are only
+// accessed in the `dma2_stream0` handler.
@@ -0,0 +1,221 @@
+//! rtic_dma_serial.rs
+//!
+//! DMA driven serial transmit
+//!
+//! What it covers:
+//! - transmitting over USART2 using DMA1 (stream 6, channel 4)
+//! - handing a `&'static mut` buffer over to the transfer, and reclaiming it
+//! - queueing messages, the transfer complete interrupt starts the next one
+//!
+//! > cargo run --example rtic_dma_serial
+//!
+//! On the Nucleo, USART2 (PA2/PA3) is connected to the stlink virtual COM port,
+//! e.g., use `moserial` or `screen /dev/ttyACM0 115200` on the host.
+
+#![no_main]
+#![no_std]
+
+use heapless::{consts::*, spsc::Queue};
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    serial::{config::Config, Serial},
+    stm32::{DMA1, USART2},
+};
+
+use app::{schedule::try_reschedule, trace::print_banner, trace_init};
+
+// message period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+// DMA buffer size, longer messages are truncated
+const BUF: usize = 64;
+
+const MESSAGES: [&str; 3] = [
+    "Hello from DMA!\r\n",
+    "The CPU is free during the transfer.\r\n",
+    "Each message is queued, then sent back to back.\r\n",
+];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        dma: DMA1,
+        // messages waiting for the DMA
+        queue: Queue<&'static str, U8>,
+        // the buffer, `Some` when the DMA is idle
+        free: Option<&'static mut [u8; BUF]>,
+        // the buffer, `Some` while owned by an ongoing transfer
+        in_flight: Option<&'static mut [u8; BUF]>,
+    }
+
+    #[init(schedule = [produce])]
+    fn init(cx: init::Context) -> init::LateResources {
+        // read by the DMA while the CPU runs other code, hence `'static`
+        static mut BUFFER: [u8; BUF] = [0; BUF];
+
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on DMA1, RM0368 6.3.9
+        device.RCC.ahb1enr.modify(|_, w| w.dma1en().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let tx = gpioa.pa2.into_alternate_af7();
+        let rx = gpioa.pa3.into_alternate_af7();
+
+        // let the HAL set up the baud rate, then take the peripheral back
+        let serial = Serial::usart2(
+            device.USART2,
+            (tx, rx),
+            Config::default().baudrate(115_200.bps()),
+            clocks,
+        )
+        .unwrap();
+        let (usart, _pins) = serial.release();
+
+        // let the DMA serve TXE requests, USART_CR3 DMAT, RM0368 19.6.6
+        usart.cr3.modify(|_, w| w.dmat().enabled());
+
+        // USART2_TX is mapped to DMA1 stream 6, channel 4, RM0368 9.3.3 (table 27)
+        let dma = device.DMA1;
+        let stream = &dma.st[6];
+        stream
+            .par
+            .write(|w| unsafe { w.pa().bits(&usart.dr as *const _ as u32) });
+        stream.cr.write(|w| {
+            w.chsel()
+                .bits(4)
+                .dir()
+                .memory_to_peripheral()
+                .msize()
+                .bits8()
+                .psize()
+                .bits8()
+                .minc()
+                .incremented()
+                .pinc()
+                .fixed()
+                .tcie()
+                .enabled()
+        });
+
+        cx.schedule.produce(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources {
+            dma,
+            queue: Queue::new(),
+            free: Some(BUFFER),
+            in_flight: None,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // queue a burst of messages, and kick off the DMA if idle
+    #[task(resources = [dma, queue, free, in_flight], schedule = [produce])]
+    fn produce(cx: produce::Context) {
+        for message in MESSAGES.iter() {
+            if cx.resources.queue.enqueue(*message).is_err() {
+                rprintln!("queue full, message dropped");
+            }
+        }
+
+        if let Some(buffer) = cx.resources.free.take() {
+            // the DMA is idle, the queue cannot be empty here
+            let message = cx.resources.queue.dequeue().unwrap();
+            *cx.resources.in_flight = Some(start(cx.resources.dma, buffer, message));
+        }
+
+        try_reschedule(
+            cx.schedule.produce(cx.scheduled + OFFSET.cycles()),
+            "produce",
+        );
+    }
+
+    // same priority as `produce`, so no locks are needed
+    #[task(binds = DMA1_STREAM6, resources = [dma, queue, free, in_flight])]
+    fn dma1_stream6(cx: dma1_stream6::Context) {
+        // clear the transfer complete flag, DMA_HIFCR, RM0368 9.5.4
+        cx.resources.dma.hifcr.write(|w| w.ctcif6().set_bit());
+
+        // the transfer is done, reclaim the buffer
+        let buffer = cx.resources.in_flight.take().unwrap();
+
+        match cx.resources.queue.dequeue() {
+            Some(message) => {
+                *cx.resources.in_flight = Some(start(cx.resources.dma, buffer, message));
+            }
+            None => *cx.resources.free = Some(buffer),
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Copy `message` to `buffer`, and start the transfer
+//
+// The buffer is moved in, and handed back to the caller to be kept as
+// `in_flight`, so it cannot be touched (or reused) until the transfer completes.
+fn start(dma: &DMA1, buffer: &'static mut [u8; BUF], message: &str) -> &'static mut [u8; BUF] {
+    let bytes = message.as_bytes();
+    let len = bytes.len().min(BUF);
+    buffer[..len].copy_from_slice(&bytes[..len]);
+
+    let stream = &dma.st[6];
+    stream
+        .m0ar
+        .write(|w| unsafe { w.m0a().bits(buffer.as_ptr() as u32) });
+    stream.ndtr.write(|w| w.ndt().bits(len as u16));
+    // clear stale flags before enabling the stream, RM0368 9.3.17
+    dma.hifcr.write(|w| {
+        w.ctcif6()
+            .set_bit()
+            .chtif6()
+            .set_bit()
+            .cteif6()
+            .set_bit()
+            .cdmeif6()
+            .set_bit()
+            .cfeif6()
+            .set_bit()
+    });
+    stream.cr.modify(|_, w| w.en().enabled());
+
+    buffer
+}
+
+// Notes:
+//
+// The DMA reads the buffer behind the back of the compiler. If the buffer
+// were a local (stack) variable, it could be overwritten (or go out of scope)
+// while still being transmitted. A `&'static mut` guarantees that the memory
+// is valid for the entire program, and being a unique reference, that nobody
+// else can access it.
+//
+// RTIC resources carry the ownership between the tasks: the buffer is either
+// in `free` or in `in_flight`, never both. The `unwrap` in `dma1_stream6`
+// cannot fail, as the interrupt only occurs for a transfer we started.
+//
+// The TC flag of the DMA fires when the last byte is written to USART_DR,
+// the USART may still be shifting out the final byte (see USART_SR TC).
@@ -0,0 +1,152 @@
+//! rtic_edge_rate.rs
+//!
+//! Counting edges, a software frequency counter
+//!
+//! What it covers:
+//! - counting rising edges on an input pin (EXTI)
+//! - computing the edge rate (Hz) once a second, and resetting the count
+//! - a counter shared between a high priority handler and a lower priority task
+//!
+//! > cargo run --example rtic_edge_rate --release
+//!
+//! Connect the signal to PB0 (CN8-4). As a known reference, the example outputs
+//! the LSE (32.768 kHz crystal, X2) on MCO1, connect PA8 (CN9-8) to PB0.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpiob::PB0, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+};
+
+use app::{
+    backup::enable_backup_access,
+    mco::{ClockOut, Mco1Prescaler, Mco1Source},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+const SYSCLK_HZ: u32 = 84_000_000;
+// report period, 1 s
+const PERIOD: u32 = SYSCLK_HZ;
+// LSE start-up timeout in clock cycles, 2 s at 16 MHz (before `freeze`)
+const LSE_TIMEOUT: u32 = 32_000_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        input: PB0<Input<Floating>>,
+        // shared by `exti0` (2) and `report` (1), ceiling 2
+        #[init(0)]
+        edges: u32,
+    }
+
+    #[init(schedule = [report])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        // the LSE is in the backup domain, RM0368 6.3.20
+        enable_backup_access(&device.RCC, &device.PWR);
+        device.RCC.bdcr.modify(|_, w| w.lseon().on());
+        let start = DWT::get_cycle_count();
+        while device.RCC.bdcr.read().lserdy().is_not_ready() {
+            if DWT::get_cycle_count().wrapping_sub(start) > LSE_TIMEOUT {
+                rprintln!("LSE failed to start (no X2 crystal?), connect another source");
+                break;
+            }
+        }
+        ClockOut::mco1(
+            &device.RCC,
+            &device.GPIOA,
+            Mco1Source::Lse,
+            Mco1Prescaler::Div1,
+        );
+
+        // high SYSCLK, for the highest edge rate
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(SYSCLK_HZ.hz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpiob = device.GPIOB.split();
+        let mut input = gpiob.pb0.into_floating_input();
+        input.make_interrupt_source(&mut device.SYSCFG);
+        input.trigger_on_edge(&mut device.EXTI, Edge::RISING);
+        input.enable_interrupt(&mut device.EXTI);
+
+        cx.schedule.report(cx.start + PERIOD.cycles()).unwrap();
+
+        init::LateResources { input }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    // one interrupt per rising edge, kept as short as possible
+    #[task(binds = EXTI0, priority = 2, resources = [input, edges])]
+    fn exti0(cx: exti0::Context) {
+        cx.resources.input.clear_interrupt_pending_bit();
+        // no lock, `exti0` runs at the ceiling
+        *cx.resources.edges += 1;
+    }
+
+    #[task(priority = 1, resources = [edges], schedule = [report])]
+    fn report(mut cx: report::Context) {
+        // read and reset in one critical section, or edges counted in between
+        // the read and the reset would be lost
+        let edges = cx.resources.edges.lock(|edges| {
+            let n = *edges;
+            *edges = 0;
+            n
+        });
+
+        // counted over PERIOD cycles (1 s), so the count is the rate in Hz
+        rprintln!("{} Hz", edges);
+
+        try_reschedule(cx.schedule.report(cx.scheduled + PERIOD.cycles()), "report");
+    }
+
+    extern "C" {
+        fn EXTI1();
+    }
+};
+
+// Notes:
+//
+// Expect 32768 Hz from the LSE, +/- a few Hz, the error of the HSI based
+// SYSCLK (+/- 1%) shows up as a wrong gate time. The count is exact, the time
+// base is not. Compare with `rtic_input_capture.rs`, measuring the period in
+// timer ticks.
+//
+// Each edge costs an interrupt, entry and exit alone take 24 cycles, plus the
+// handler. At 84 MHz this tops out at roughly 1 M edges per second, with the
+// CPU fully loaded (`report` and `idle` starve). The MCO2 output of
+// `rtic_bare6.rs` (SYSCLK / 4, 4 MHz) is too fast to count this way, edges
+// are missed while the handler runs and the count saturates. Count MHz
+// signals in hardware instead, e.g., a timer clocked by its external trigger
+// input (ETR) and read once a second.
+//
+// The lock in `report` is held for a few instructions only. An edge arriving
+// meanwhile stays pending in the EXTI, and `exti0` runs as soon as the lock is
+// released, so it is counted towards the next period. Without the lock (if it
+// were possible), an edge counted between the read and the reset is lost.
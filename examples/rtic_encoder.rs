@@ -0,0 +1,143 @@
+//! rtic_encoder.rs
+//!
+//! Quadrature encoder
+//!
+//! What it covers:
+//! - setting up TIM4 in encoder mode using the HAL (`Qei`)
+//! - reading the count and direction
+//! - computing signed deltas across the 16 bit counter wrap around
+//! - the position as an angle, in integer arithmetic (`fixed::scale_i32`)
+//!
+//! > cargo run --example rtic_encoder
+//!
+//! Wiring (rotary encoder with A/B outputs and common to GND):
+//!
+//! A -> PB6 (CN10-17, TIM4_CH1)
+//! B -> PB7 (CN7-21, TIM4_CH2)
+//! C -> GND
+//!
+//! The inputs use the internal pull-ups. Turning clockwise, A leads B by
+//! 90 degrees and the counter counts up (swap A and B to reverse).
+
+#![no_main]
+#![no_std]
+
+use embedded_hal::{Direction, Qei as _};
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpiob::{PB6, PB7},
+        Alternate, AF2,
+    },
+    prelude::*,
+    qei::Qei,
+    stm32::TIM4,
+};
+
+use app::{fixed::scale_i32, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// poll period in clock cycles, 100 ms at 16 MHz
+const OFFSET: u32 = 1_600_000;
+// counts per revolution, 20 detents x 4 (x4 mode), adjust for your encoder
+const COUNTS_PER_REV: u32 = 80;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        qei: Qei<TIM4, (PB6<Alternate<AF2>>, PB7<Alternate<AF2>>)>,
+        // last counter value
+        last: u16,
+        // accumulated position, not limited to 16 bits
+        #[init(0)]
+        position: i32,
+    }
+
+    #[init(schedule = [poll])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpiob = device.GPIOB.split();
+        let a = gpiob.pb6.into_pull_up_input().into_alternate_af2();
+        let b = gpiob.pb7.into_pull_up_input().into_alternate_af2();
+
+        // encoder mode 3, counting on both edges of both inputs (x4),
+        // RM0368 13.3.16
+        let qei = Qei::tim4(device.TIM4, (a, b));
+        let last = qei.count();
+
+        cx.schedule.poll(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { qei, last }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [qei, last, position], schedule = [poll])]
+    fn poll(cx: poll::Context) {
+        let count = cx.resources.qei.count();
+
+        // The difference modulo 2^16, interpreted as signed, is correct as long
+        // as the encoder moves less than 32768 counts between two polls.
+        let delta = count.wrapping_sub(*cx.resources.last) as i16;
+        *cx.resources.last = count;
+
+        if delta != 0 {
+            *cx.resources.position += delta as i32;
+            let direction = match cx.resources.qei.direction() {
+                Direction::Upcounting => "up",
+                Direction::Downcounting => "down",
+            };
+            // total rotation, not wrapped to one turn
+            let degrees = scale_i32(*cx.resources.position, COUNTS_PER_REV, 360);
+            rprintln!(
+                "count {}, delta {}, position {} ({} deg), {}",
+                count,
+                delta,
+                cx.resources.position,
+                degrees,
+                direction
+            );
+        }
+
+        try_reschedule(cx.schedule.poll(cx.scheduled + OFFSET.cycles()), "poll");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// In x4 mode a typical detented encoder gives 4 counts per detent, divide
+// the position by 4 to get the number of "clicks".
+//
+// The angle is position x 360 / COUNTS_PER_REV, rounded to whole degrees.
+// `scale_i32` computes the product in 64 bits, so it does not overflow for
+// any position, and needs no floating point.
+//
+// Mechanical encoders bounce. The timer input filter (CCMR1 IC1F/IC2F) can
+// be set to suppress short glitches, the HAL leaves it off.
+//
+// `direction` reflects the last counted edge, it may toggle back and forth
+// on a bouncing contact. The sign of `delta` is the robust indicator.
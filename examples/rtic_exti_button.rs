@@ -0,0 +1,91 @@
+//! rtic_exti_button.rs
+//!
+//! GPIO interrupts
+//!
+//! What it covers:
+//! - configuring a pin as an EXTI (external interrupt) source
+//! - routing the EXTI line through SYSCFG
+//! - binding a hardware task to the EXTI interrupt
+//!
+//! > cargo run --example rtic_exti_button
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, gpioc::PC13, Edge, ExtiPin, Floating, Input, Output, PushPull},
+    prelude::*,
+};
+
+use app::{trace::print_banner, trace_init};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        led: PA5<Output<PushPull>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // setup LED, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+        let led = gpioa.pa5.into_push_pull_output();
+
+        // setup the user button (B1) on PC13, the Nucleo has an external pull-up
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+
+        // route EXTI13 to port C, SYSCFG_EXTICR4, RM0368 7.2.6
+        button.make_interrupt_source(&mut device.SYSCFG);
+        // trigger on the rising edge, EXTI_RTSR, RM0368 10.3.3
+        button.trigger_on_edge(&mut device.EXTI, Edge::RISING);
+        // unmask the EXTI13 line, EXTI_IMR, RM0368 10.3.1
+        button.enable_interrupt(&mut device.EXTI);
+
+        // The EXTI15_10 interrupt is enabled (unmasked) in the NVIC by RTIC,
+        // as it is bound to the `exti15_10` hardware task.
+
+        init::LateResources { button, led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // EXTI lines 10..15 share a single interrupt vector
+    #[task(binds = EXTI15_10, resources = [button, led])]
+    fn exti15_10(cx: exti15_10::Context) {
+        // clear the pending bit, EXTI_PR, RM0368 10.3.6
+        // otherwise the handler is re-entered as soon as it returns
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        // TODO: debounce, the mechanical switch may produce several edges per press
+        rprintln!("button");
+        cx.resources.led.toggle().ok();
+    }
+};
+
+// Notes:
+//
+// The user button B1 on the Nucleo is active low, i.e., PC13 reads high when
+// released and low when pressed. The rising edge thus occurs when the button
+// is released. Use `Edge::FALLING` to react on the press instead.
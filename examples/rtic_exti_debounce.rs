@@ -0,0 +1,126 @@
+//! rtic_exti_debounce.rs
+//!
+//! Debouncing GPIO interrupts
+//!
+//! What it covers:
+//! - masking an EXTI line from within its handler
+//! - scheduling a software task to confirm the input after a settling time
+//! - compare to `rtic_exti_button.rs` (naive handler)
+//!
+//! > cargo run --example rtic_exti_debounce
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, gpioc::PC13, Edge, ExtiPin, Floating, Input, Output, PushPull},
+    prelude::*,
+    stm32,
+};
+
+use app::{trace::print_banner, trace_init};
+
+// settling time in CYCCNT cycles, 20 ms at 16 MHz
+const DEBOUNCE: u32 = 320_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        led: PA5<Output<PushPull>>,
+        EXTI: stm32::EXTI,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // setup LED, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+        let led = gpioa.pa5.into_push_pull_output();
+
+        // setup the user button (B1) on PC13, active low
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources {
+            button,
+            led,
+            EXTI: device.EXTI,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // first edge of a (potential) press
+    #[task(binds = EXTI15_10, resources = [button, EXTI], schedule = [confirm_press])]
+    fn exti15_10(cx: exti15_10::Context) {
+        let button = cx.resources.button;
+
+        // mask the line, further edges (bounces) are ignored until confirmed
+        button.disable_interrupt(cx.resources.EXTI);
+        button.clear_interrupt_pending_bit();
+
+        rprintln!("edge    @ {:?}", Instant::now());
+        cx.schedule
+            .confirm_press(Instant::now() + DEBOUNCE.cycles())
+            .unwrap();
+    }
+
+    // re-read the pin after the settling time
+    #[task(resources = [button, led, EXTI])]
+    fn confirm_press(cx: confirm_press::Context) {
+        let button = cx.resources.button;
+
+        if button.is_low().unwrap() {
+            rprintln!("press   @ {:?}", Instant::now());
+            cx.resources.led.toggle().ok();
+        } else {
+            rprintln!("bounce  @ {:?}", Instant::now());
+        }
+
+        // discard edges latched while masked, and unmask the line
+        button.clear_interrupt_pending_bit();
+        button.enable_interrupt(cx.resources.EXTI);
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The EXTI pending bit (EXTI_PR) may latch an edge while the line is masked
+// in EXTI_IMR, thus it is cleared before unmasking, otherwise bounces occurring
+// during the settling time would re-trigger the handler.
+//
+// The DEBOUNCE constant is given in clock cycles, so it must be adjusted
+// if SYSCLK is changed. Mechanical switches typically settle within 5-20 ms.
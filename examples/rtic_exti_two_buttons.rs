@@ -0,0 +1,119 @@
+//! rtic_exti_two_buttons.rs
+//!
+//! Two buttons on different EXTI lines
+//!
+//! What it covers:
+//! - routing two EXTI lines to different ports (SYSCFG_EXTICR1 and EXTICR4)
+//! - dedicated (EXTI0) vs. shared (EXTI15_10) interrupt vectors
+//! - two hardware tasks sharing a resource
+//!
+//! > cargo run --example rtic_exti_two_buttons
+//!
+//! Connect a push button between PA0 (CN7-28) and GND, the internal pull-up is used.
+//! The user button (B1) on PC13 decrements, the external button on PA0 increments.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA0, gpioc::PC13, Edge, ExtiPin, Floating, Input, PullUp},
+    prelude::*,
+};
+
+use app::{trace::print_banner, trace_init};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        up: PA0<Input<PullUp>>,
+        down: PC13<Input<Floating>>,
+        #[init(0)]
+        counter: i32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let gpioc = device.GPIOC.split();
+
+        let mut up = gpioa.pa0.into_pull_up_input();
+        let mut down = gpioc.pc13.into_floating_input();
+
+        // Each EXTI line n can be routed to pin n of exactly one port, selected by
+        // a 4 bit field in SYSCFG_EXTICR1..4 (RM0368 7.2.3 - 7.2.6):
+        // - EXTI0, EXTICR1[3:0] = 0b0000 (port A)
+        // - EXTI13, EXTICR4[7:4] = 0b0010 (port C)
+        // So PA0 and PC0 (or PA13 and PC13) can not both be interrupt sources.
+        up.make_interrupt_source(&mut device.SYSCFG);
+        down.make_interrupt_source(&mut device.SYSCFG);
+        rprintln!(
+            "EXTICR1 0x{:04x}, EXTICR4 0x{:04x}",
+            device.SYSCFG.exticr1.read().bits(),
+            device.SYSCFG.exticr4.read().bits()
+        );
+
+        // both buttons are active low, trigger on press
+        up.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        down.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+
+        // unmask the lines in EXTI_IMR, RM0368 10.3.1
+        up.enable_interrupt(&mut device.EXTI);
+        down.enable_interrupt(&mut device.EXTI);
+
+        // The EXTI0 and EXTI15_10 interrupts are enabled separately in the NVIC
+        // (ISER0 bits 6 and 40), RTIC does this for each bound hardware task.
+
+        init::LateResources { up, down }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // EXTI lines 0..4 have dedicated vectors, only PA0 (EXTI0) can cause this interrupt
+    #[task(binds = EXTI0, resources = [up, counter])]
+    fn exti0(cx: exti0::Context) {
+        cx.resources.up.clear_interrupt_pending_bit();
+
+        *cx.resources.counter += 1;
+        rprintln!("up   {}", cx.resources.counter);
+    }
+
+    // EXTI lines 10..15 share a single vector, with more than one source the handler
+    // must check which pending bit is set (EXTI_PR, RM0368 10.3.6)
+    #[task(binds = EXTI15_10, resources = [down, counter])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.down.clear_interrupt_pending_bit();
+
+        *cx.resources.counter -= 1;
+        rprintln!("down {}", cx.resources.counter);
+    }
+};
+
+// Notes:
+//
+// The tasks run at the same priority (1), so they never preempt each other,
+// and `counter` can be accessed without a `lock`.
+//
+// The vectors are: EXTI0..EXTI4 one per line, EXTI9_5 for lines 5..9,
+// and EXTI15_10 for lines 10..15 (RM0368 10.2, Table 38).
+//
+// Neither button is debounced, see `rtic_exti_debounce.rs`.
@@ -0,0 +1,208 @@
+//! rtic_flash_store.rs
+//!
+//! Persistent storage in the embedded flash
+//!
+//! What it covers:
+//! - the FLASH controller unlock sequence, sector erase and word programming
+//! - guarding against erasing the sector holding the application
+//! - reading back a stored struct, persistent across resets
+//!
+//! > cargo run --example rtic_flash_store
+//!
+//! Each boot increments a boot counter stored in flash. Press reset (B2) and
+//! watch the counter increase.
+//!
+//! IMPORTANT: Flash can only be erased a whole sector at the time, RM0368 3.3:
+//!
+//! | Sector | Address     | Size   |
+//! | ------ | ----------- | ------ |
+//! | 0..3   | 0x0800_0000 | 16 KB  |
+//! | 4      | 0x0801_0000 | 64 KB  |
+//! | 5..7   | 0x0802_0000 | 128 KB |
+//!
+//! Erasing sets all bits to 1, programming can only clear bits. So updating
+//! a single word means erasing (and rewriting) the whole sector. A sector
+//! endures ~10k erase cycles (see the data sheet), don't erase in a loop.
+
+#![no_main]
+#![no_std]
+
+use core::{mem, ptr};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::stm32::FLASH;
+
+use app::trace_init;
+
+// sector 5, outside the 128K FLASH region given to the linker in memory.x
+const SECTOR: u8 = 5;
+const SECTOR_ADDR: usize = 0x0802_0000;
+
+// FLASH_KEYR unlock sequence, RM0368 3.5.1
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xCDEF_89AB;
+
+// marks an initialized store, an erased word reads 0xFFFF_FFFF
+const MAGIC: u32 = 0xC0FF_EE01;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct Config {
+    magic: u32,
+    boots: u32,
+    // some setting, e.g., a calibration value
+    value: u32,
+}
+
+// error flags in FLASH_SR, RM0368 3.8.4
+#[derive(Debug)]
+enum FlashError {
+    // the sector is (partly) used by the application
+    Overlap,
+    // the error bits of FLASH_SR (PGSERR, PGPERR, PGAERR, WRPERR, OPERR)
+    Status(u32),
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let flash = cx.device.FLASH;
+
+        let config = match read() {
+            Some(config) => {
+                rprintln!("stored {:?}", config);
+                Config {
+                    boots: config.boots + 1,
+                    ..config
+                }
+            }
+            None => {
+                rprintln!("no stored config (erased sector)");
+                Config {
+                    magic: MAGIC,
+                    boots: 1,
+                    value: 42,
+                }
+            }
+        };
+
+        match store(&flash, &config) {
+            Ok(()) => rprintln!("stored, boot {}", config.boots),
+            Err(err) => rprintln!("store failed {:?}", err),
+        }
+    }
+};
+
+// The stored config, if any
+fn read() -> Option<Config> {
+    // SAFETY: the sector is memory mapped (read only), and aligned for `Config`
+    let config = unsafe { ptr::read_volatile(SECTOR_ADDR as *const Config) };
+    if config.magic == MAGIC {
+        Some(config)
+    } else {
+        None
+    }
+}
+
+// Erase the sector and program `config`, RM0368 3.5
+fn store(flash: &FLASH, config: &Config) -> Result<(), FlashError> {
+    if image_end() > SECTOR_ADDR {
+        return Err(FlashError::Overlap);
+    }
+
+    unlock(flash);
+    let result = erase(flash).and_then(|_| program(flash, config));
+    // lock again, protecting against accidental writes
+    flash.cr.modify(|_, w| w.lock().set_bit());
+    result
+}
+
+// End of the application image in flash (vector table, code, read only data,
+// and the initial values of `.data`), symbols from the cortex-m-rt linker script
+fn image_end() -> usize {
+    extern "C" {
+        static __sidata: u32;
+        static __sdata: u32;
+        static __edata: u32;
+    }
+    // SAFETY: only the addresses are used
+    unsafe {
+        let data_size = &__edata as *const u32 as usize - &__sdata as *const u32 as usize;
+        &__sidata as *const u32 as usize + data_size
+    }
+}
+
+fn unlock(flash: &FLASH) {
+    if flash.cr.read().lock().bit_is_set() {
+        flash.keyr.write(|w| w.key().bits(KEY1));
+        flash.keyr.write(|w| w.key().bits(KEY2));
+    }
+}
+
+fn wait(flash: &FLASH) -> Result<(), FlashError> {
+    while flash.sr.read().bsy().bit_is_set() {}
+
+    // PGSERR | PGPERR | PGAERR | WRPERR | OPERR
+    let errors = flash.sr.read().bits() & 0xf2;
+    // clear the flags (write 1 to clear) for the next operation
+    flash.sr.write(|w| unsafe { w.bits(errors) });
+    if errors == 0 {
+        Ok(())
+    } else {
+        Err(FlashError::Status(errors))
+    }
+}
+
+fn erase(flash: &FLASH) -> Result<(), FlashError> {
+    wait(flash)?;
+    // 32 bit parallelism, requires VDD 2.7..3.6 V (3.3 V on the Nucleo), RM0368 3.5.2
+    flash
+        .cr
+        .modify(|_, w| unsafe { w.psize().psize32().ser().set_bit().snb().bits(SECTOR) });
+    flash.cr.modify(|_, w| w.strt().set_bit());
+    // up to a few seconds for a 128 KB sector, the CPU is stalled on flash reads meanwhile
+    let result = wait(flash);
+    flash.cr.modify(|_, w| w.ser().clear_bit());
+    result
+}
+
+fn program(flash: &FLASH, config: &Config) -> Result<(), FlashError> {
+    let words = mem::size_of::<Config>() / 4;
+    let src = config as *const Config as *const u32;
+
+    wait(flash)?;
+    flash.cr.modify(|_, w| w.psize().psize32().pg().set_bit());
+    let mut result = Ok(());
+    for i in 0..words {
+        // SAFETY: `Config` is `repr(C)` with `u32` fields only, the
+        // destination is in the erased sector
+        unsafe {
+            let word = ptr::read(src.add(i));
+            ptr::write_volatile((SECTOR_ADDR as *mut u32).add(i), word);
+        }
+        result = wait(flash);
+        if result.is_err() {
+            break;
+        }
+    }
+    flash.cr.modify(|_, w| w.pg().clear_bit());
+    result
+}
+
+// Notes:
+//
+// The code runs from flash, and the CPU stalls on any flash read while an
+// erase or program operation is in progress (the code is not corrupted, but
+// interrupts are delayed). Sectors 4..7 may take 1-2 s to erase, so never erase
+// from a time critical context.
+//
+// If the application grows beyond the FLASH length in memory.x, the linker
+// fails. If the length in memory.x is increased to cover sector 5, the
+// `image_end` guard catches an application overlapping the store.
+//
+// The F401 has a single flash bank, a safer scheme for frequently updated data
+// writes records one after the other in the erased sector, erasing only when
+// the sector is full (as an EEPROM emulation, see ST application note AN3969).
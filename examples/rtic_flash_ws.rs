@@ -0,0 +1,226 @@
+//! rtic_flash_ws.rs
+//!
+//! Flash wait states vs. clock speed
+//!
+//! What it covers:
+//! - the flash access latency (wait states) required at each HCLK, FLASH_ACR LATENCY
+//! - the ART accelerator (prefetch, instruction and data caches) hiding the wait states
+//! - the cost in cycles and in wall-clock time, measured with `cycle_count`
+//! - what happens with too few wait states (guarded by `TOO_FEW_WAIT_STATES`)
+//!
+//! > cargo run --example rtic_flash_ws --release
+//!
+//! The same benchmark (a software CRC over a table in flash) runs at 16 MHz
+//! (HSI) and at 84 MHz (HSI driven PLL), with different FLASH_ACR settings.
+
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::stm32::{FLASH, RCC};
+
+use app::{bench::cycle_count, crc::crc32, trace::print_banner, trace_init};
+
+// DANGER: runs the benchmark at 84 MHz with 0 wait states, outside the flash
+// specification. The result is undefined, typically a HardFault or a wrong CRC.
+const TOO_FEW_WAIT_STATES: bool = false;
+
+// benchmark data size in words
+const N: usize = 1024;
+
+// the benchmark data, in flash (`static` without `mut` goes to .rodata)
+static DATA: [u32; N] = data();
+
+const fn data() -> [u32; N] {
+    let mut data = [0; N];
+    let mut i = 0;
+    while i < N {
+        data[i] = (i as u32).wrapping_mul(0x9E37_79B9);
+        i += 1;
+    }
+    data
+}
+
+// a clock and flash configuration to benchmark
+struct Config {
+    sysclk_mhz: u32,
+    wait_states: u8,
+    // prefetch, instruction and data caches
+    art: bool,
+}
+
+const CONFIGS: [Config; 5] = [
+    // the reset state
+    Config {
+        sysclk_mhz: 16,
+        wait_states: 0,
+        art: false,
+    },
+    // wait states at a low clock only cost cycles
+    Config {
+        sysclk_mhz: 16,
+        wait_states: 2,
+        art: false,
+    },
+    Config {
+        sysclk_mhz: 16,
+        wait_states: 2,
+        art: true,
+    },
+    // 2 wait states are required above 64 MHz
+    Config {
+        sysclk_mhz: 84,
+        wait_states: 2,
+        art: false,
+    },
+    Config {
+        sysclk_mhz: 84,
+        wait_states: 2,
+        art: true,
+    },
+];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // the cycle counter must be enabled before measuring
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        print_banner(16_000_000);
+
+        // read the size through a volatile read, so the compiler cannot
+        // compute the result at compile time
+        let n = unsafe { ptr::read_volatile(&N) };
+        let reference = crc32(&DATA[..n]);
+
+        for config in CONFIGS.iter() {
+            set_clock(&device.RCC, &device.FLASH, config);
+            run(config, n, reference);
+        }
+
+        if TOO_FEW_WAIT_STATES {
+            let config = Config {
+                sysclk_mhz: 84,
+                wait_states: 0,
+                art: false,
+            };
+            rprintln!("DANGER: 0 wait states at 84 MHz");
+            set_clock(&device.RCC, &device.FLASH, &config);
+            run(&config, n, reference);
+        }
+
+        // back to the reset state
+        set_clock(&device.RCC, &device.FLASH, &CONFIGS[0]);
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Run the benchmark and print the cycles and the time in us
+fn run(config: &Config, n: usize, reference: u32) {
+    let mut crc = 0;
+    let cycles = cycle_count(|| crc = crc32(&DATA[..n]));
+    rprintln!(
+        "{:2} MHz, {} WS, ART {:5}: {:7} cycles, {:5} us{}",
+        config.sysclk_mhz,
+        config.wait_states,
+        config.art,
+        cycles,
+        cycles / config.sysclk_mhz,
+        if crc == reference { "" } else { ", WRONG CRC" }
+    );
+}
+
+// Set SYSCLK (16 MHz HSI or 84 MHz PLL) and the flash access, see notes
+fn set_clock(rcc: &RCC, flash: &FLASH, config: &Config) {
+    // SYSCLK to the HSI, PLL off, RCC_CFGR SW/SWS, RM0368 6.3.3
+    rcc.cfgr.modify(|_, w| w.sw().hsi());
+    while !rcc.cfgr.read().sws().is_hsi() {}
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    // 16 MHz now, any number of wait states is within the specification.
+    // The caches must be reset while disabled, RM0368 3.5.2
+    flash
+        .acr
+        .modify(|_, w| w.prften().disabled().icen().disabled().dcen().disabled());
+    flash
+        .acr
+        .modify(|_, w| w.icrst().set_bit().dcrst().set_bit());
+    flash
+        .acr
+        .modify(|_, w| w.icrst().clear_bit().dcrst().clear_bit());
+    flash
+        .acr
+        .modify(|_, w| unsafe { w.latency().bits(config.wait_states) });
+    // the new latency is in effect once read back, RM0368 3.4.1
+    while flash.acr.read().latency().bits() != config.wait_states {}
+    if config.art {
+        flash
+            .acr
+            .modify(|_, w| w.prften().enabled().icen().enabled().dcen().enabled());
+    }
+
+    if config.sysclk_mhz == 84 {
+        // HSI / 8 * 168 / 4 = 84 MHz, APB1 at most 42 MHz
+        rcc.cfgr.modify(|_, w| w.ppre1().div2());
+        rcc.pllcfgr.write(|w| unsafe {
+            w.pllsrc()
+                .hsi()
+                .pllm()
+                .bits(8)
+                .plln()
+                .bits(168)
+                .pllp()
+                .div4()
+                .pllq()
+                .bits(7)
+        });
+        rcc.cr.modify(|_, w| w.pllon().on());
+        while rcc.cr.read().pllrdy().is_not_ready() {}
+        rcc.cfgr.modify(|_, w| w.sw().pll());
+        while !rcc.cfgr.read().sws().is_pll() {}
+    }
+}
+
+// Notes:
+//
+// The flash is slower than the core, a read takes about 30 ns. At HCLK above
+// 30 MHz the CPU must wait extra cycles (wait states) per flash access,
+// RM0368 3.4.1 Table 6 (2.7 - 3.6 V):
+//
+//   0 WS: HCLK <= 30 MHz, 1 WS: <= 64 MHz, 2 WS: <= 84 MHz
+//
+// (At lower supply voltages more wait states are needed.)
+//
+// Typical results (in cycles, without/with ART):
+// - at 16 MHz, 2 WS cost cycles, for no benefit
+// - at 84 MHz, the cycle count is about the same as at 16 MHz / 2 WS, but
+//   each cycle is 5.25 times shorter, the wall-clock time improves by ~5x
+// - the ART hides most of the wait states, a small loop runs from the
+//   instruction cache, and the flash is read 128 bits at a time (4 words)
+//
+// When raising HCLK, increase the wait states first, then switch the clock.
+// When lowering HCLK, switch the clock first, then decrease the wait states.
+// `set_clock` is always at 16 MHz while changing the latency, so the order
+// is correct both ways.
+//
+// With too few wait states (TOO_FEW_WAIT_STATES), the flash returns data
+// before it is stable. The result ranges from working "by luck" (e.g., at
+// room temperature), to wrong data, to a HardFault from a garbled
+// instruction. Never rely on it working, the margin is not specified.
@@ -0,0 +1,116 @@
+//! rtic_fpu.rs
+//!
+//! Floating Point Unit (FPU)
+//!
+//! What it covers:
+//! - checking that the FPU is enabled (SCB_CPACR)
+//! - hardware vs. software (emulated) single precision arithmetic
+//!
+//! > cargo run --example rtic_fpu --release
+//!
+//! Build for `thumbv7em-none-eabihf` (see `.cargo/config`), otherwise all
+//! floating point is emulated in software, also in the "hardware" loop.
+
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use cortex_m::peripheral::{scb::FpuAccessMode, SCB};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+
+use app::{
+    bench::{calibrate, cycle_count},
+    trace_init,
+};
+
+// number of loop iterations
+const N: u32 = 1_000;
+
+// The soft-float routines from `compiler_builtins` (ARM EABI run-time helpers).
+// These are what the compiler calls for `f32` arithmetic when there is no FPU.
+// They use the base procedure call standard (arguments in core registers).
+extern "aapcs" {
+    fn __aeabi_fadd(a: f32, b: f32) -> f32;
+    fn __aeabi_fmul(a: f32, b: f32) -> f32;
+    fn __aeabi_ui2f(a: u32) -> f32;
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+        calibrate();
+
+        // Coprocessor Access Control Register, PM0214 4.6.1
+        // CP10 (bits 21:20) and CP11 (bits 23:22) control the FPU access:
+        // 0b00 denied (any FPU instruction faults), 0b01 privileged, 0b11 full
+        let cpacr = core.SCB.cpacr.read();
+        rprintln!(
+            "CPACR 0x{:08x}, CP10 {:02b}, CP11 {:02b}",
+            cpacr,
+            (cpacr >> 20) & 0b11,
+            (cpacr >> 22) & 0b11
+        );
+        match SCB::fpu_access_mode() {
+            FpuAccessMode::Enabled => rprintln!("FPU enabled"),
+            FpuAccessMode::Privileged => rprintln!("FPU enabled (privileged only)"),
+            FpuAccessMode::Disabled => rprintln!("FPU disabled"),
+        }
+
+        // volatile read, so the compiler cannot compute the results at compile time
+        let n = unsafe { ptr::read_volatile(&N) };
+
+        let mut hw = 0f32;
+        let hw_cycles = cycle_count(|| {
+            let mut acc = 1.0f32;
+            for i in 0..n {
+                acc = acc * 0.999 + i as f32 * 0.5;
+            }
+            hw = acc;
+        });
+
+        // the same computation, calling the emulation routines explicitly
+        let mut sw = 0f32;
+        let sw_cycles = cycle_count(|| {
+            let mut acc = 1.0f32;
+            for i in 0..n {
+                unsafe {
+                    acc =
+                        __aeabi_fadd(__aeabi_fmul(acc, 0.999), __aeabi_fmul(__aeabi_ui2f(i), 0.5));
+                }
+            }
+            sw = acc;
+        });
+
+        rprintln!("hardware {} cycles, result {}", hw_cycles, hw);
+        rprintln!("software {} cycles, result {}", sw_cycles, sw);
+        rprintln!("speedup x{}", sw_cycles / hw_cycles.max(1));
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Notes:
+//
+// `cortex-m-rt` enables the FPU (CP10/CP11 full access) in the reset handler
+// when built for an `eabihf` target, before `main`. If it is not enabled, the
+// first FPU instruction raises a UsageFault (NOCP), see rtic_hardfault.rs.
+//
+// Typical results (--release): a few cycles per iteration in hardware, and
+// an order of magnitude more in software. The results should be identical,
+// both implement IEEE 754 single precision (with round to nearest).
+//
+// `f64` is always emulated in software on the Cortex-M4F.
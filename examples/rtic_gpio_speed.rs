@@ -0,0 +1,94 @@
+//! rtic_gpio_speed.rs
+//!
+//! GPIO output speed (slew rate)
+//!
+//! What it covers:
+//! - setting the output speed (GPIOx_OSPEEDR) using the HAL `set_speed`
+//! - comparing the edges of a low speed and a very high speed output
+//!
+//! > cargo run --example rtic_gpio_speed --release
+//!
+//! Connect two scope probes to PA6 (CN10-13, low speed) and PA7 (CN10-15,
+//! very high speed). Trigger on PA7, and zoom in on a rising edge (~10 ns/div).
+//! Use short ground leads, otherwise ringing hides the difference.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpioa::{PA6, PA7},
+        Output, PushPull, Speed,
+    },
+    prelude::*,
+};
+
+use app::{trace::print_banner, trace_init};
+
+// half period in clock cycles, for a ~1 MHz square wave at 84 MHz
+// (including the loop and pin access overhead)
+const HALF_PERIOD: u32 = 36;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        slow: PA6<Output<PushPull>>,
+        fast: PA7<Output<PushPull>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        // OSPEEDR 0b00, the reset default for most pins
+        let slow = gpioa.pa6.into_push_pull_output().set_speed(Speed::Low);
+        // OSPEEDR 0b11, as set for MCO2 in src/mco.rs
+        let fast = gpioa.pa7.into_push_pull_output().set_speed(Speed::VeryHigh);
+
+        // The same using the PAC (named variants), GPIOx_OSPEEDR, RM0368 8.4.3
+        // gpioa.ospeedr.modify(|_, w| w.ospeedr6().low_speed().ospeedr7().very_high_speed());
+
+        init::LateResources { slow, fast }
+    }
+
+    #[idle(resources = [slow, fast])]
+    fn idle(cx: idle::Context) -> ! {
+        rprintln!("idle, toggling PA6 (low speed) and PA7 (very high speed)");
+        let slow = cx.resources.slow;
+        let fast = cx.resources.fast;
+        loop {
+            slow.set_high().ok();
+            fast.set_high().ok();
+            asm::delay(HALF_PERIOD);
+            slow.set_low().ok();
+            fast.set_low().ok();
+            asm::delay(HALF_PERIOD);
+        }
+    }
+};
+
+// Notes:
+//
+// Expected rise/fall times (STM32F401xD/E data sheet, I/O AC characteristics,
+// VDD = 3.3V), roughly:
+//
+// - Low speed:       up to ~100 ns (CL = 50 pF), max ~2..4 MHz
+// - Very high speed: ~2..6 ns (CL = 10..30 pF), max ~100 MHz
+//
+// So at 1 MHz the low speed output is still a usable square wave, but with
+// visibly rounded edges. At higher frequencies (e.g., MCO2 at 21 MHz) it
+// never reaches the rails, which is why src/mco.rs uses very high speed.
+//
+// Faster edges mean more EMI (electromagnetic interference) and ringing,
+// so use the lowest speed that meets the timing requirements.
@@ -0,0 +1,212 @@
+//! rtic_hardfault.rs
+//!
+//! Decoding a HardFault
+//!
+//! What it covers:
+//! - overriding the `HardFault` exception handler
+//! - reading and decoding the fault status registers (CFSR, HFSR)
+//! - reading the fault address registers (BFAR, MMFAR)
+//!
+//! > cargo run --example rtic_hardfault
+//!
+//! Press the user button (B1) to trigger the fault selected by `FAULT`.
+//!
+//! `Fault::Unaligned` emits the `ldr` with inline assembly, thus requires a
+//! nightly toolchain:
+//!
+//! > cargo run --example rtic_hardfault --features nightly
+
+#![no_main]
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(asm))]
+
+use core::ptr;
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::{exception, ExceptionFrame};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+};
+
+use app::{trace::print_banner, trace_init};
+
+#[allow(dead_code)]
+enum Fault {
+    // read outside of any memory region, a (precise) bus fault
+    InvalidAddress,
+    // word read from an odd address, a usage fault (with UNALIGN_TRP set)
+    Unaligned,
+    // an undefined instruction, a usage fault
+    Undefined,
+}
+
+// the fault triggered by the button
+const FAULT: Fault = Fault::InvalidAddress;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // trap on unaligned word accesses, CCR UNALIGN_TRP, PM0214 4.4.7.
+        // The trap bit is what makes `Fault::Unaligned` fault, by default the
+        // Cortex-M4 silently performs an unaligned LDR/STR.
+        unsafe { core.SCB.ccr.modify(|r| r | (1 << 3)) };
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // setup the user button (B1) on PC13, the Nucleo has an external pull-up
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources { button }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle, press the button to fault");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [button])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        match FAULT {
+            Fault::InvalidAddress => {
+                rprintln!("read from 0x2FFF_FFF0");
+                unsafe { ptr::read_volatile(0x2FFF_FFF0 as *const u32) };
+            }
+            Fault::Unaligned => {
+                rprintln!("read from 0x2000_0001");
+                // a plain `ldr`, `ptr::read_volatile` requires an aligned
+                // pointer (undefined behaviour otherwise)
+                #[cfg(feature = "nightly")]
+                unsafe {
+                    asm!("ldr {0}, [{1}]", out(reg) _, in(reg) 0x2000_0001u32)
+                };
+                #[cfg(not(feature = "nightly"))]
+                rprintln!("requires `--features nightly` (inline assembly)");
+            }
+            Fault::Undefined => {
+                rprintln!("execute udf");
+                cortex_m::asm::udf();
+            }
+        }
+    }
+};
+
+// The configurable faults (MemManage, BusFault, UsageFault) are disabled
+// by default (SHCSR), and thus escalated to HardFault (HFSR FORCED).
+#[exception]
+fn HardFault(ef: &ExceptionFrame) -> ! {
+    // SAFETY: read only access, we never return
+    let scb = unsafe { &*SCB::ptr() };
+
+    let cfsr = scb.cfsr.read();
+    let hfsr = scb.hfsr.read();
+    let mmfar = scb.mmfar.read();
+    let bfar = scb.bfar.read();
+
+    rprintln!("HardFault");
+    rprintln!("{:#?}", ef);
+    rprintln!("HFSR  0x{:08x}, CFSR 0x{:08x}", hfsr, cfsr);
+
+    // HardFault Status Register, PM0214 4.4.16
+    if hfsr & (1 << 1) != 0 {
+        rprintln!("  VECTTBL: bus fault on vector table read");
+    }
+    if hfsr & (1 << 30) != 0 {
+        rprintln!("  FORCED: escalated configurable fault");
+    }
+
+    // MemManage Status Register, CFSR[7:0], PM0214 4.4.14
+    let mmfsr = cfsr & 0xff;
+    decode(
+        mmfsr,
+        &[
+            (0, "IACCVIOL: instruction access violation"),
+            (1, "DACCVIOL: data access violation"),
+            (3, "MUNSTKERR: fault on unstacking"),
+            (4, "MSTKERR: fault on stacking"),
+            (5, "MLSPERR: fault on FP lazy state preservation"),
+        ],
+    );
+    if mmfsr & (1 << 7) != 0 {
+        rprintln!("  MMFAR valid: 0x{:08x}", mmfar);
+    }
+
+    // BusFault Status Register, CFSR[15:8]
+    let bfsr = (cfsr >> 8) & 0xff;
+    decode(
+        bfsr,
+        &[
+            (0, "IBUSERR: instruction bus error"),
+            (1, "PRECISERR: precise data bus error"),
+            (2, "IMPRECISERR: imprecise data bus error"),
+            (3, "UNSTKERR: fault on unstacking"),
+            (4, "STKERR: fault on stacking"),
+            (5, "LSPERR: fault on FP lazy state preservation"),
+        ],
+    );
+    if bfsr & (1 << 7) != 0 {
+        rprintln!("  BFAR valid: 0x{:08x}", bfar);
+    }
+
+    // UsageFault Status Register, CFSR[31:16]
+    let ufsr = cfsr >> 16;
+    decode(
+        ufsr,
+        &[
+            (0, "UNDEFINSTR: undefined instruction"),
+            (1, "INVSTATE: invalid state (EPSR)"),
+            (2, "INVPC: invalid PC load (EXC_RETURN)"),
+            (3, "NOCP: no coprocessor"),
+            (8, "UNALIGNED: unaligned access"),
+            (9, "DIVBYZERO: divide by zero"),
+        ],
+    );
+
+    loop {
+        continue;
+    }
+}
+
+// print the description of each set bit
+fn decode(status: u32, bits: &[(u32, &str)]) {
+    for (bit, description) in bits {
+        if status & (1 << bit) != 0 {
+            rprintln!("  {}", description);
+        }
+    }
+}
+
+// Notes:
+//
+// The `pc` in the exception frame points at (or close to) the faulting
+// instruction, look it up in the disassembly (`cargo objdump`).
+//
+// BFAR/MMFAR are only valid if BFARVALID/MMARVALID is set. For an imprecise
+// bus fault (e.g., a buffered write) the address is not known, and the stacked
+// `pc` points a few instructions after the store.
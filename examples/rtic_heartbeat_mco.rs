@@ -0,0 +1,130 @@
+//! rtic_heartbeat_mco.rs
+//!
+//! Heartbeat LED correlated with the MCO2 clock output
+//!
+//! What it covers:
+//! - SYSCLK / prescaler on MCO2 (PC9)
+//! - toggling the LED (PA5) every 2^24 SYSCLK cycles
+//! - the exact (integer) ratio between the two signals, computed from `Clocks`
+//!
+//! > cargo run --example rtic_heartbeat_mco
+//!
+//! Connect a scope to PC9 (CN10-1) and PA5 (CN10-11). With the scope triggered
+//! on the LED edge, the number of MCO2 periods between two LED edges is the
+//! printed ratio, for any SYSCLK setting.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    clock::SysClkConfig,
+    led::Led,
+    mco::{ClockOut, Mco2Prescaler},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// cycles between LED toggles, ~0.2 s at 84 MHz, ~1 s at 16 MHz
+const TOGGLE_CYCLES: u32 = 1 << 24;
+
+// use a power of two (Div1, Div2, Div4), as it divides TOGGLE_CYCLES
+const PRESCALER: Mco2Prescaler = Mco2Prescaler::Div4;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        ClockOut::mco2_sysclk(&device.RCC, &device.GPIOC, PRESCALER);
+
+        let clocks = SysClkConfig::new().sysclk(84).apply(device.RCC);
+        print_banner(clocks.sysclk().0);
+
+        // all frequencies derived from the actual SYSCLK
+        let sysclk = clocks.sysclk().0;
+        let mco_hz = sysclk / PRESCALER.divisor();
+        // the LED period is two toggles
+        let led_period = 2 * TOGGLE_CYCLES as u64;
+        rprintln!("MCO2 {} Hz", mco_hz);
+        rprintln!("LED  {} mHz", sysclk as u64 * 1_000 / led_period);
+
+        // MCO2 periods per LED toggle, exact as the prescaler divides 2^24
+        if TOGGLE_CYCLES % PRESCALER.divisor() == 0 {
+            rprintln!(
+                "ratio {} MCO2 periods per LED toggle",
+                TOGGLE_CYCLES / PRESCALER.divisor()
+            );
+        } else {
+            rprintln!(
+                "ratio {}.{:03} MCO2 periods per LED toggle (not an integer)",
+                TOGGLE_CYCLES / PRESCALER.divisor(),
+                (TOGGLE_CYCLES % PRESCALER.divisor()) * 1_000 / PRESCALER.divisor()
+            );
+        }
+
+        cx.schedule
+            .toggle(cx.start + TOGGLE_CYCLES.cycles())
+            .unwrap();
+
+        init::LateResources { led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [led], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        cx.resources.led.toggle().ok();
+        if !try_reschedule(
+            cx.schedule.toggle(cx.scheduled + TOGGLE_CYCLES.cycles()),
+            "toggle",
+        ) {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The ratio only depends on the prescaler, not on SYSCLK, as both signals are
+// derived from the same clock. Changing `sysclk(..)` changes both frequencies,
+// but not their ratio, which is a quick check that the LED timing is indeed
+// driven by SYSCLK (and not some other clock).
+//
+// The scheduled task is released at `cx.scheduled`, while the actual toggle
+// happens a few hundred cycles later (the dispatch latency). This shows up as
+// a constant phase offset on the scope, not as drift.
@@ -1,16 +1,40 @@
+//! rtic_hello.rs
+//!
+//! > cargo run --example rtic_hello --features semihosting
+//!
+//! > cargo run --example rtic_hello --features rtt
+//!
+//! > cargo run --example rtic_hello --features defmt-log
+//! (with `-Tdefmt.x` enabled in `.cargo/config`, see `src/log.rs`)
+//!
+//! Running without a debugger: semihosting write errors are ignored by `log!`,
+//! but the semihosting call itself (a `bkpt` instruction) faults if no debugger
+//! is attached. Build with `--features rtt` (or `defmt-log`) to run standalone,
+//! the RTT output is simply discarded until a host connects.
+
 #![no_main]
 #![no_std]
 
-use cortex_m_semihosting::hprintln;
-use panic_halt as _;
-use stm32f2;
+// the panic handler matching the log backend
+#[cfg(feature = "defmt-log")]
+use panic_probe as _;
+#[cfg(feature = "rtt")]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use app::{log, log_init};
 
-#[rtic::app(device = stm32f2)]
+// STM32F401RE (Nucleo-F401RE), the PAC as re-exported by the HAL (as in the
+// other examples), the device (F401/F411) is selected by the HAL feature.
+// The flash/RAM layout is given by memory.x (128K/64K fits both devices).
+#[rtic::app(device = stm32f4xx_hal::stm32)]
 const APP: () = {
     #[init]
     fn init(_cx: init::Context) {
+        log_init!();
         for a in 0..11 {
-            hprintln!("RTIC Says Hello, to all students!! {}", a).unwrap();
+            log!("RTIC Says Hello, to all students!! {}", a);
         }
     }
 };
@@ -0,0 +1,91 @@
+//! rtic_hse.rs
+//!
+//! External clock (HSE) with HSI fall back
+//!
+//! What it covers:
+//! - starting the HSE with a timeout (`clock::start_hse`)
+//! - falling back to the HSI if no external clock is present
+//! - reporting the clock source and the achieved SYSCLK
+//!
+//! > cargo run --example rtic_hse
+//!
+//! The Nucleo-64 has no crystal populated (X3), the HSE is instead fed by
+//! the 8 MHz MCO of the ST-LINK, which requires bypass mode (`BYPASS = true`).
+//! Set `BYPASS = false` to see the fall back to HSI.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, stm32};
+
+use app::{
+    clock::{start_hse, HSE_TIMEOUT},
+    trace::print_banner,
+    trace_init,
+};
+
+// HSE frequency in MHz, the ST-LINK MCO on the Nucleo
+const HSE_MHZ: u32 = 8;
+// OSC_IN driven by an external clock (true), or a crystal (false)
+const BYPASS: bool = true;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let device = cx.device;
+
+        // check the HSE before handing the RCC to the HAL,
+        // `use_hse` would wait forever for a missing clock
+        let hse = start_hse(&device.RCC, BYPASS, HSE_TIMEOUT);
+        if hse {
+            rprintln!("HSE ready, {} MHz (bypass {})", HSE_MHZ, BYPASS);
+        } else {
+            rprintln!("warning: HSE not ready, falling back to HSI");
+        }
+
+        let rcc = device.RCC.constrain();
+        let cfgr = rcc.cfgr.sysclk(84.mhz());
+        let cfgr = if hse {
+            cfgr.use_hse(HSE_MHZ.mhz())
+        } else {
+            cfgr
+        };
+        let clocks = cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // SAFETY: read only access, after `freeze`
+        let rcc = unsafe { &*stm32::RCC::ptr() };
+
+        // the actual system clock switch status, RCC_CFGR SWS, RM0368 6.3.3
+        let cfgr = rcc.cfgr.read();
+        let source = if cfgr.sws().is_hsi() {
+            "HSI"
+        } else if cfgr.sws().is_hse() {
+            "HSE"
+        } else if rcc.pllcfgr.read().pllsrc().is_hse() {
+            "PLL (HSE)"
+        } else {
+            "PLL (HSI)"
+        };
+
+        rprintln!("source {}, sysclk {} Hz", source, clocks.sysclk().0);
+    }
+};
+
+// Notes:
+//
+// Both the HSI and the ST-LINK MCO are accurate enough for a blinking LED,
+// but the HSI is specified to +/- 1% (over temperature), which may be too
+// much for, e.g., USB (which requires a 48 MHz clock within 0.25%).
+//
+// The ST-LINK MCO is derived from the ST-LINK crystal, and is thus as accurate
+// as a crystal, but only available as long as the ST-LINK is powered.
+//
+// The fall back decision is taken once at startup. The Clock Security System
+// (CSS, RCC_CR CSSON) can detect a HSE failure at run-time, switching to HSI
+// and raising an NMI, see `rtic_css.rs`.
@@ -0,0 +1,146 @@
+//! rtic_i2c_scan.rs
+//!
+//! I2C bus scanner
+//!
+//! What it covers:
+//! - setting up I2C1 using the HAL (pins, bus speed)
+//! - probing addresses at register level, with NACK and timeout handling
+//!
+//! > cargo run --example rtic_i2c_scan
+//!
+//! Connect the device(s) to SCL PB6 (CN10-17) and SDA PB9 (CN10-5), with
+//! pull-up resistors (typically 4.7k to +3.3V) unless the breakout has them.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use heapless::{consts::*, Vec};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{i2c::I2c, prelude::*, stm32::I2C1};
+
+use app::{trace::print_banner, trace_init};
+
+// 7 bit addresses, 0x00..0x07 and 0x78..0x7F are reserved
+const FIRST: u8 = 0x08;
+const LAST: u8 = 0x77;
+// per step timeout in clock cycles, 1 ms at 16 MHz
+const TIMEOUT: u32 = 16_000;
+
+#[derive(Debug)]
+enum Probe {
+    Ack,
+    Nack,
+    Timeout,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // the cycle counter is used for timeouts
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpiob = device.GPIOB.split();
+        let scl = gpiob.pb6.into_alternate_af4().set_open_drain();
+        let sda = gpiob.pb9.into_alternate_af4().set_open_drain();
+
+        // let the HAL compute the timing for 100 kHz (standard mode)
+        let i2c = I2c::i2c1(device.I2C1, (scl, sda), 100.khz(), clocks);
+        // take back the (configured) peripheral for register level access
+        let (i2c, _pins) = i2c.release();
+
+        let mut found: Vec<u8, U16> = Vec::new();
+        for addr in FIRST..=LAST {
+            match probe(&i2c, addr) {
+                Probe::Ack => {
+                    rprintln!("0x{:02x} ACK", addr);
+                    found.push(addr).ok();
+                }
+                Probe::Nack => {}
+                Probe::Timeout => {
+                    rprintln!("0x{:02x} timeout, bus stuck? check the pull-ups", addr);
+                    break;
+                }
+            }
+        }
+
+        rprintln!("found {} device(s): {:02x?}", found.len(), found);
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Zero length write to `addr`, RM0368 18.3.3
+//
+// The HAL `write` waits for the address to be acknowledged without checking
+// for NACK (AF), so it would hang on the first missing device.
+fn probe(i2c: &I2C1, addr: u8) -> Probe {
+    // START condition
+    i2c.cr1.modify(|_, w| w.start().set_bit());
+    if !wait(|| i2c.sr1.read().sb().bit_is_set()) {
+        return Probe::Timeout;
+    }
+
+    // address + write (R/W = 0), reading SR1 above and writing DR clears SB
+    i2c.dr.write(|w| unsafe { w.bits(u32::from(addr) << 1) });
+
+    let mut result = Probe::Timeout;
+    wait(|| {
+        let sr1 = i2c.sr1.read();
+        if sr1.addr().bit_is_set() {
+            result = Probe::Ack;
+        } else if sr1.af().bit_is_set() {
+            result = Probe::Nack;
+        } else {
+            return false;
+        }
+        true
+    });
+
+    // clear ADDR (by reading SR2) and AF, then release the bus (STOP condition)
+    i2c.sr2.read();
+    i2c.sr1.modify(|_, w| w.af().clear_bit());
+    i2c.cr1.modify(|_, w| w.stop().set_bit());
+    wait(|| i2c.cr1.read().stop().bit_is_clear());
+
+    result
+}
+
+// Polls `done` until it returns true, or TIMEOUT cycles have passed
+fn wait(mut done: impl FnMut() -> bool) -> bool {
+    let start = DWT::get_cycle_count();
+    while DWT::get_cycle_count().wrapping_sub(start) < TIMEOUT {
+        if done() {
+            return true;
+        }
+    }
+    false
+}
+
+// Notes:
+//
+// A timeout typically means that SDA or SCL is held low, e.g., missing
+// pull-ups or a slave stuck in the middle of a transfer (after a reset).
+// The latter can be fixed by clocking SCL manually (up to 9 times) until the
+// slave releases SDA, and then issuing a STOP.
+//
+// Some devices respond to several addresses, or only after being powered up
+// (or enabled) by a separate pin. Check the data sheet of your device.
@@ -0,0 +1,202 @@
+//! rtic_input_capture.rs
+//!
+//! Input capture
+//!
+//! What it covers:
+//! - setting up a timer channel (TIM3 CH1) in input capture mode (register level)
+//! - measuring the period between consecutive (rising) edges
+//! - extending the 16 bit counter by counting overflows (update events)
+//!
+//! > cargo run --example rtic_input_capture
+//!
+//! The example outputs the LSE (32.768 kHz) on MCO1 (PA8, CN9-8).
+//! Wire PA8 to the capture input PA6 (CN10-13), or connect any other
+//! 3.3V square wave (up to ~100 kHz) to PA6.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, stm32::TIM3};
+
+use app::{
+    mco::{ClockOut, Mco1Prescaler, Mco1Source},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// print period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+// capture every edge (0), every 2nd (1), 4th (2) or 8th (3), CCMR1 IC1PSC
+const ICPSC: u8 = 0;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        TIM3: TIM3,
+        // timer clock in Hz
+        timclk: u32,
+        // last measured period in timer ticks (0 if none)
+        #[init(0)]
+        period: u32,
+    }
+
+    #[init(schedule = [report])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // start the LSE (backup domain), see also rtic_rtc.rs
+        device.RCC.apb1enr.modify(|_, w| w.pwren().enabled());
+        device.PWR.cr.modify(|_, w| w.dbp().set_bit());
+        device.RCC.bdcr.modify(|_, w| w.lseon().on());
+        while device.RCC.bdcr.read().lserdy().is_not_ready() {}
+
+        // LSE on MCO1 (PA8)
+        ClockOut::mco1(
+            &device.RCC,
+            &device.GPIOA,
+            Mco1Source::Lse,
+            Mco1Prescaler::Div1,
+        );
+
+        // power on TIM3, RM0368 6.3.11
+        device.RCC.apb1enr.modify(|_, w| w.tim3en().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // APB1 timers run at 2 x PCLK1, unless the APB1 prescaler is 1
+        let timclk = if clocks.ppre1() == 1 {
+            clocks.pclk1().0
+        } else {
+            clocks.pclk1().0 * 2
+        };
+
+        // PA6 is TIM3_CH1 (AF2)
+        let gpioa = device.GPIOA.split();
+        let _capture = gpioa.pa6.into_alternate_af2();
+
+        let tim = device.TIM3;
+        // count at the timer clock, using the full 16 bit range
+        tim.psc.write(|w| w.psc().bits(0));
+        tim.arr.write(|w| w.arr().bits(0xffff));
+        // CC1 as input mapped on TI1, with a small filter, RM0368 13.4.7
+        tim.ccmr1_input()
+            .write(|w| unsafe { w.cc1s().ti1().ic1f().fck_int_n4().ic1psc().bits(ICPSC) });
+        // capture on the rising edge (CC1P = CC1NP = 0), RM0368 13.4.9
+        tim.ccer
+            .write(|w| w.cc1p().clear_bit().cc1np().clear_bit().cc1e().set_bit());
+        // interrupt on capture and overflow (update)
+        tim.dier.write(|w| w.cc1ie().set_bit().uie().set_bit());
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        rprintln!("timer clock {} Hz", timclk);
+        cx.schedule.report(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { TIM3: tim, timclk }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = TIM3, priority = 2, resources = [TIM3, period])]
+    fn tim3(cx: tim3::Context) {
+        // number of overflows since the last capture
+        static mut OVERFLOWS: u32 = 0;
+        // last captured counter value (None before the first edge)
+        static mut LAST: Option<u16> = None;
+
+        let tim = cx.resources.TIM3;
+        let sr = tim.sr.read();
+        // clear only the flags handled below (rc_w0, writing 1 has no effect)
+        tim.sr.write(|w| unsafe { w.bits(!0b11) }); // UIF | CC1IF
+
+        let overflow = sr.uif().bit_is_set();
+
+        if sr.cc1if().bit_is_set() {
+            let capture = tim.ccr1.read().ccr().bits();
+
+            // If both flags are set, decide which came first. A small capture
+            // value means that the counter had already wrapped when the edge came.
+            let overflow_before = overflow && capture < 0x8000;
+            if overflow_before {
+                *OVERFLOWS += 1;
+            }
+
+            if let Some(last) = *LAST {
+                *cx.resources.period = (*OVERFLOWS << 16)
+                    .wrapping_add(capture as u32)
+                    .wrapping_sub(last as u32);
+            }
+            *LAST = Some(capture);
+
+            *OVERFLOWS = if overflow && !overflow_before { 1 } else { 0 };
+        } else if overflow {
+            *OVERFLOWS += 1;
+        }
+
+        if sr.cc1of().bit_is_set() {
+            // an edge was missed (the signal is too fast for the interrupt)
+            tim.sr.write(|w| unsafe { w.bits(!(1 << 9)) }); // CC1OF
+        }
+    }
+
+    #[task(resources = [period, timclk], schedule = [report])]
+    fn report(mut cx: report::Context) {
+        // taken (cleared), "no signal" unless an edge was captured since the last report
+        let period = cx
+            .resources
+            .period
+            .lock(|period| core::mem::replace(period, 0));
+
+        if period == 0 {
+            rprintln!("no signal");
+        } else {
+            // with the input prescaler, each period spans 2^ICPSC signal periods
+            let edges = 1u64 << ICPSC;
+            let hz = (*cx.resources.timclk as u64 * edges) / period as u64;
+            rprintln!("period {} ticks, {} Hz", period, hz);
+        }
+
+        try_reschedule(cx.schedule.report(cx.scheduled + OFFSET.cycles()), "report");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The resolution is one timer tick (1/16 us at 16 MHz). For a 32.768 kHz
+// signal, a period is ~488 ticks, so the frequency is known to ~0.2%.
+// Increasing ICPSC averages over more periods, improving the resolution.
+//
+// The interrupt must keep up with the signal. MCO2 (PC9) gives at least
+// SYSCLK / 5, which is far too fast for an interrupt per edge (even with
+// ICPSC = 3). For such signals, count edges over a fixed time instead
+// (external clock mode) or capture with DMA.
+//
+// For signals slower than timclk / 65536 (~244 Hz at 16 MHz) the overflow
+// counting is essential, otherwise the period is computed modulo 65536.
+//
+// `report` clears the period it reads, so a signal that stops shows as
+// "no signal" in the next report. Signals with a period longer than the
+// report interval (1 s) show "no signal" in between the readings.
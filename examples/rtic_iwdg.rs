@@ -0,0 +1,119 @@
+//! rtic_iwdg.rs
+//!
+//! Independent watchdog
+//!
+//! What it covers:
+//! - starting the independent watchdog (IWDG)
+//! - feeding the watchdog from a periodic task
+//! - recovering from a stuck task by a watchdog reset
+//! - reading (and clearing) the reset flags in RCC_CSR
+//!
+//! > cargo run --example rtic_iwdg
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, watchdog::IndependentWatchdog};
+
+use app::{
+    reset::{take_reset_cause, ResetCause},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// watchdog timeout in ms
+const TIMEOUT_MS: u32 = 1_000;
+// feed period in clock cycles, 250 ms at 16 MHz
+const OFFSET: u32 = 4_000_000;
+// number of feeds before the task "gets stuck"
+const FEEDS: u32 = 20;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        watchdog: IndependentWatchdog,
+    }
+
+    #[init(schedule = [feed])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // check the reset cause, RCC_CSR, RM0368 6.3.21
+        // the flags are sticky, `take_reset_cause` removes them (RMVF) so the
+        // next boot reports correctly
+        match take_reset_cause(&device.RCC) {
+            ResetCause::IndependentWatchdog => rprintln!("reset cause: independent watchdog"),
+            cause => rprintln!("reset cause: other ({:?})", cause),
+        }
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let mut watchdog = IndependentWatchdog::new(device.IWDG);
+        // keep the watchdog from firing while halted in the debugger
+        watchdog.stop_on_debug(&device.DBGMCU, true);
+        // once started, the IWDG cannot be stopped (except by a reset)
+        watchdog.start(TIMEOUT_MS.ms());
+
+        cx.schedule.feed(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { watchdog }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [watchdog], schedule = [feed])]
+    fn feed(cx: feed::Context) {
+        static mut COUNTER: u32 = 0;
+
+        *COUNTER += 1;
+        if *COUNTER > FEEDS {
+            // simulate a stuck task, stop feeding (and rescheduling)
+            rprintln!(
+                "stuck @ {:?}, expect a reset in {} ms",
+                Instant::now(),
+                TIMEOUT_MS
+            );
+            return;
+        }
+
+        rprintln!("feed {} @ {:?}", COUNTER, Instant::now());
+        cx.resources.watchdog.feed();
+
+        if !try_reschedule(cx.schedule.feed(cx.scheduled + OFFSET.cycles()), "feed") {
+            rprintln!("no longer fed, expect a reset in {} ms", TIMEOUT_MS);
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The IWDG is clocked by the LSI (~32 kHz), independent of the main clock.
+// Thus it keeps running even if the main clock fails. The LSI is inaccurate
+// (17..47 kHz), so the actual timeout may deviate from TIMEOUT_MS.
+//
+// The feed period must be well below the timeout. Here we feed every 250 ms,
+// with a 1 s timeout.
@@ -0,0 +1,156 @@
+//! rtic_jitter.rs
+//!
+//! Scheduling jitter
+//!
+//! What it covers:
+//! - comparing the actual start time (`Instant::now()`) with `cx.scheduled`
+//! - collecting min/max/last statistics in a resource shared with `idle`
+//! - a higher priority task inducing jitter
+//!
+//! > cargo run --example rtic_jitter --release
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{led::Led, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// toggle period in clock cycles, 10 ms at 84 MHz
+const PERIOD: u32 = 840_000;
+// load period, not a multiple of PERIOD, so the tasks drift in and out of phase
+const LOAD_PERIOD: u32 = 1_234_567;
+// execution time of the load, set to 0 for an unloaded system
+const LOAD_WORK: u32 = 200_000;
+// idle print interval in cycles (busy wait, ~1 s)
+const REPORT: u32 = 84_000_000;
+
+// start latency statistics in clock cycles
+#[derive(Clone, Copy)]
+struct Jitter {
+    min: u32,
+    max: u32,
+    last: u32,
+    count: u32,
+}
+
+impl Jitter {
+    const fn new() -> Self {
+        Jitter {
+            min: u32::MAX,
+            max: 0,
+            last: 0,
+            count: 0,
+        }
+    }
+
+    fn record(&mut self, late: u32) {
+        self.min = self.min.min(late);
+        self.max = self.max.max(late);
+        self.last = late;
+        self.count += 1;
+    }
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+        #[init(Jitter::new())]
+        jitter: Jitter,
+    }
+
+    #[init(schedule = [toggle, load])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        cx.schedule.toggle(cx.start + PERIOD.cycles()).unwrap();
+        cx.schedule.load(cx.start + LOAD_PERIOD.cycles()).unwrap();
+
+        init::LateResources { led }
+    }
+
+    #[idle(resources = [jitter])]
+    fn idle(mut cx: idle::Context) -> ! {
+        loop {
+            asm::delay(REPORT);
+            // take a copy and reset, the lock is held only briefly
+            let j = cx.resources.jitter.lock(|jitter| {
+                let j = *jitter;
+                *jitter = Jitter::new();
+                j
+            });
+            rprintln!(
+                "{} runs, late min {}, max {}, last {} cycles",
+                j.count,
+                j.min,
+                j.max,
+                j.last
+            );
+        }
+    }
+
+    #[task(priority = 1, resources = [led, jitter], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        // first thing, the time since the scheduled release
+        let late = Instant::now().duration_since(cx.scheduled).as_cycles();
+        cx.resources.jitter.record(late);
+
+        cx.resources.led.toggle().ok();
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + PERIOD.cycles()), "toggle") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    // competing work, preempts `toggle` (and delays its start)
+    #[task(priority = 2, schedule = [load])]
+    fn load(cx: load::Context) {
+        asm::delay(LOAD_WORK);
+        try_reschedule(
+            cx.schedule.load(cx.scheduled + LOAD_PERIOD.cycles()),
+            "load",
+        );
+    }
+
+    // one dispatcher per software task priority
+    extern "C" {
+        fn EXTI0();
+        fn EXTI1();
+    }
+};
+
+// Notes:
+//
+// Without load (LOAD_WORK = 0) the start latency is (almost) constant, the
+// time for the SysTick handler to move the task from the timer queue to the
+// ready queue and for the dispatcher to start it, a few hundred cycles.
+//
+// With load, `toggle` is delayed whenever it is released while `load` runs,
+// up to LOAD_WORK cycles. The max is bounded by the response time of the
+// higher priority work, that is what schedulability analysis is about.
+//
+// Since `toggle` reschedules relative to `cx.scheduled` (not `Instant::now()`),
+// the jitter does not accumulate, the period stays exact on average.
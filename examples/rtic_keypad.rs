@@ -0,0 +1,200 @@
+//! rtic_keypad.rs
+//!
+//! 4x4 matrix keypad scanner
+//!
+//! What it covers:
+//! - scanning a key matrix, driving one row at a time and reading the columns
+//! - arrays of (port) erased pins, `downgrade`
+//! - debouncing the whole matrix in software, from a periodic task
+//! - rejecting ambiguous key combinations (ghosting)
+//!
+//! > cargo run --example rtic_keypad
+//!
+//! Connect the keypad rows to PC0..PC3 (CN7-38, CN7-36, CN7-35, CN7-37) and
+//! the columns to PB12..PB15 (CN10-16, CN10-30, CN10-28, CN10-26).
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpiob::PB, gpioc::PC, Input, OpenDrain, Output, PullUp},
+    prelude::*,
+};
+
+use app::{schedule::try_reschedule, trace::print_banner, trace_init};
+
+// scan period in clock cycles, 10 ms at 16 MHz
+const SCAN: u32 = 160_000;
+// consecutive equal scans for a state to be accepted, 30 ms
+const DEBOUNCE: u8 = 3;
+// settling time after selecting a row, in clock cycles
+const SETTLE: u32 = 100;
+
+const ROWS: usize = 4;
+const COLS: usize = 4;
+
+// the key labels, [row][column]
+const KEYS: [[char; COLS]; ROWS] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+// One bit per key, bit `row * COLS + col`
+type Keys = u16;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        rows: [PC<Output<OpenDrain>>; ROWS],
+        cols: [PB<Input<PullUp>>; COLS],
+        // the last raw scan, and for how many scans it has been the same
+        #[init(0)]
+        raw: Keys,
+        #[init(0)]
+        count: u8,
+        // the debounced state
+        #[init(0)]
+        stable: Keys,
+    }
+
+    #[init(schedule = [scan])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // Rows as open drain outputs, released (not driven) when not selected,
+        // so two keys in the same column never short a high row to a low one.
+        let gpioc = device.GPIOC.split();
+        let mut rows = [
+            gpioc.pc0.into_open_drain_output().downgrade(),
+            gpioc.pc1.into_open_drain_output().downgrade(),
+            gpioc.pc2.into_open_drain_output().downgrade(),
+            gpioc.pc3.into_open_drain_output().downgrade(),
+        ];
+        for row in rows.iter_mut() {
+            row.set_high().ok();
+        }
+
+        // Columns with pull-ups, read low when a key connects them to the
+        // selected row (a released row is only pulled up through pressed keys).
+        let gpiob = device.GPIOB.split();
+        let cols = [
+            gpiob.pb12.into_pull_up_input().downgrade(),
+            gpiob.pb13.into_pull_up_input().downgrade(),
+            gpiob.pb14.into_pull_up_input().downgrade(),
+            gpiob.pb15.into_pull_up_input().downgrade(),
+        ];
+
+        cx.schedule.scan(cx.start + SCAN.cycles()).unwrap();
+
+        init::LateResources { rows, cols }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [rows, cols, raw, count, stable], schedule = [scan])]
+    fn scan(cx: scan::Context) {
+        let keys = read_matrix(cx.resources.rows, cx.resources.cols);
+
+        // the state must stay the same for DEBOUNCE scans
+        if keys == *cx.resources.raw {
+            *cx.resources.count = cx.resources.count.saturating_add(1);
+        } else {
+            *cx.resources.raw = keys;
+            *cx.resources.count = 1;
+        }
+
+        let stable = cx.resources.stable;
+        if *cx.resources.count == DEBOUNCE && keys != *stable {
+            if is_ambiguous(keys) {
+                // keep the previous state, the pressed keys cannot be told apart
+                rprintln!("ambiguous, ignored");
+            } else {
+                // report the newly pressed keys, in scan order
+                let pressed = keys & !*stable;
+                for (bit, key) in KEYS.iter().flatten().enumerate() {
+                    if pressed & (1 << bit) != 0 {
+                        rprintln!("key {}", key);
+                    }
+                }
+                *stable = keys;
+            }
+        }
+
+        try_reschedule(cx.schedule.scan(cx.scheduled + SCAN.cycles()), "scan");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Select each row in turn (low), and read the columns
+fn read_matrix(rows: &mut [PC<Output<OpenDrain>>; ROWS], cols: &[PB<Input<PullUp>>; COLS]) -> Keys {
+    let mut keys = 0;
+    for (r, row) in rows.iter_mut().enumerate() {
+        row.set_low().ok();
+        // let the column lines settle (charge through the pull-ups)
+        asm::delay(SETTLE);
+        for (c, col) in cols.iter().enumerate() {
+            if col.is_low().unwrap() {
+                keys |= 1 << (r * COLS + c);
+            }
+        }
+        row.set_high().ok();
+    }
+    keys
+}
+
+// The keys of `row`, one bit per column
+fn row_bits(keys: Keys, row: usize) -> Keys {
+    (keys >> (row * COLS)) & ((1 << COLS) - 1)
+}
+
+// Ghosting, see notes: two rows with (at least) two columns in common
+fn is_ambiguous(keys: Keys) -> bool {
+    (0..ROWS)
+        .any(|a| (a + 1..ROWS).any(|b| (row_bits(keys, a) & row_bits(keys, b)).count_ones() >= 2))
+}
+
+// Notes:
+//
+// Without a diode per key, the current can also flow backwards through
+// pressed keys. Press '1', '2' and '4' (three corners of a rectangle), and
+// select row 1: col 0 is pulled low through '4', and col 1 through the path
+// '4' - col 0 - '1' - row 0 - '2'. So '5' reads as pressed although it is
+// not (a ghost key). The scan sees all four corners, and cannot tell which
+// three (or four) keys are really down. Any two rows sharing two columns form
+// such a rectangle, and the state is ignored. Single keys, and two keys at
+// any positions, are never ambiguous.
+//
+// The debouncing treats the matrix as one 16 bit value, a new state is
+// accepted once it has been read DEBOUNCE times in a row. A bouncing key
+// thus delays the whole state change by up to DEBOUNCE * SCAN (30 ms).
+//
+// Scanning costs ROWS * SETTLE cycles (plus the reads) every 10 ms, well
+// below 1% of the CPU. For lower power, select all rows and use an EXTI
+// interrupt on the columns to wake up and start scanning on a key press.
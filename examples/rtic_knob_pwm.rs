@@ -0,0 +1,124 @@
+//! rtic_knob_pwm.rs
+//!
+//! Light follows knob, ADC to PWM
+//!
+//! What it covers:
+//! - a control loop stepped at a fixed rate by a hardware timer (TIM2)
+//! - sampling a potentiometer (ADC1) and scaling the 12 bit value to the PWM duty
+//! - driving an LED by PWM (TIM3)
+//!
+//! > cargo run --example rtic_knob_pwm
+//!
+//! Connect a potentiometer (wiper) to PA0 (CN7-28), and its ends to +3.3V and GND.
+//! Connect an LED (with a series resistor) between PA6 (CN10-13) and GND.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc,
+    },
+    gpio::{gpioa::PA0, Analog},
+    prelude::*,
+    pwm::{self, PwmChannels, C1},
+    stm32::{ADC1, TIM2, TIM3},
+    timer::{Event, Timer},
+};
+
+use app::{adc::to_duty, trace::print_banner, trace_init};
+
+// control loop rate
+const LOOP_HZ: u32 = 100;
+// print every PRINT_EVERY steps (once a second)
+const PRINT_EVERY: u32 = LOOP_HZ;
+const SAMPLE_TIME: SampleTime = SampleTime::Cycles_112;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: Adc<ADC1>,
+        knob: PA0<Analog>,
+        pwm: PwmChannels<TIM3, C1>,
+        timer: Timer<TIM2>,
+        #[init(0)]
+        steps: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let knob = gpioa.pa0.into_analog();
+
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let adc = Adc::adc1(device.ADC1, true, config);
+
+        // TIM3 channel 1 on PA6, alternate function AF2
+        let mut pwm = pwm::tim3(device.TIM3, gpioa.pa6.into_alternate_af2(), clocks, 1.khz());
+        pwm.set_duty(0);
+        pwm.enable();
+        rprintln!("max_duty {}", pwm.get_max_duty());
+
+        let mut timer = Timer::tim2(device.TIM2, LOOP_HZ.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        init::LateResources {
+            adc,
+            knob,
+            pwm,
+            timer,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    // one step of the control loop: sample -> scale -> actuate
+    #[task(binds = TIM2, resources = [adc, knob, pwm, timer, steps])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        let raw = cx.resources.adc.convert(cx.resources.knob, SAMPLE_TIME);
+        let pwm = cx.resources.pwm;
+        let duty = to_duty(raw, pwm.get_max_duty());
+        pwm.set_duty(duty);
+
+        *cx.resources.steps += 1;
+        if *cx.resources.steps % PRINT_EVERY == 0 {
+            rprintln!("raw {}, duty {}/{}", raw, duty, pwm.get_max_duty());
+        }
+    }
+};
+
+// Notes:
+//
+// The loop is open (the light level is not measured), the "control" is just
+// a mapping from input to output. Replace the potentiometer by a light
+// dependent resistor (LDR) pointing at the LED and compute the duty from the
+// error (setpoint - reading) to close the loop.
+//
+// The perceived brightness is roughly logarithmic in the duty, so the lower
+// end of the knob changes the brightness much more than the upper end. A
+// squared (or table based) mapping gives a more even feel.
+//
+// The ADC conversion (112 + 12 ADC clock cycles at 8 MHz, ~16 us) is done in
+// the handler, blocking. At 100 Hz that is a small fraction of the period.
+// For higher loop rates, trigger the ADC from the timer in hardware (TIM2 TRGO,
+// EXTSEL) and run the loop from the ADC end of conversion interrupt instead.
@@ -0,0 +1,119 @@
+//! rtic_late_resources.rs
+//!
+//! Several peripherals as late resources
+//!
+//! What it covers:
+//! - constructing a serial transmitter, an LED and a timer in `init`
+//! - the order of construction (RCC -> clocks -> GPIO pins -> peripherals)
+//! - peripherals are moved (not copied) into the late resources
+//! - using the resources across a hardware and a software task
+//!
+//! > cargo run --example rtic_late_resources
+//!
+//! Connect a terminal (e.g., `moserial`, 115200 8N1) to the ST-LINK virtual COM port.
+
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+    serial::{config::Config, Serial, Tx},
+    stm32::{TIM2, USART2},
+    timer::{Event, Timer},
+};
+
+use app::{led::Led, trace::print_banner, trace_init};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources, all initialized by `init`
+        tx: Tx<USART2>,
+        led: Led<PA5<Output<PushPull>>>,
+        timer: Timer<TIM2>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        // `cx.device` is moved into `device`, each field (peripheral) can
+        // then be moved out separately
+        let device = cx.device;
+
+        // 1. the clocks, `constrain` consumes (moves) `device.RCC`
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(48.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+        // `device.RCC` can no longer be used here (use of moved value),
+        // `clocks` is `Copy`, and can be passed by value to several drivers
+
+        // 2. the pins, `split` consumes `device.GPIOA`, the pins are moved
+        // out of `gpioa` one by one
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+        let tx_pin = gpioa.pa2.into_alternate_af7();
+        let rx_pin = gpioa.pa3.into_alternate_af7();
+
+        // 3. the peripherals, each consuming the register block and its pins
+        let serial = Serial::usart2(
+            device.USART2,
+            (tx_pin, rx_pin),
+            Config::default().baudrate(115_200.bps()),
+            clocks,
+        )
+        .unwrap();
+        // the receiver half is dropped, the USART keeps receiving (unread)
+        let (tx, _rx) = serial.split();
+
+        let mut timer = Timer::tim2(device.TIM2, 1.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        // 4. move everything into the resources, the tasks own them from here on
+        init::LateResources { tx, led, timer }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = TIM2, resources = [led, timer], spawn = [report])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        cx.resources.led.toggle().ok();
+        // pass a copy of the state, `report` has no access to `led`
+        cx.spawn.report(cx.resources.led.is_on()).ok();
+    }
+
+    #[task(resources = [tx])]
+    fn report(cx: report::Context, on: bool) {
+        writeln!(cx.resources.tx, "led {}\r", if on { "on" } else { "off" }).ok();
+        rprintln!("led {}", on);
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// A late resource is initialized at run-time by `init`, while an early
+// resource (`#[init(value)]`) has a compile time initial value. HAL drivers
+// can only be late resources, as they are created from the peripherals.
+//
+// Try using `device.GPIOA` after `split`, or `gpioa.pa5` twice, the compiler
+// rejects it (use of moved value). Each pin (and peripheral) has exactly one
+// owner, which is what guarantees that no two tasks drive the same pin.
+//
+// Only resources used by tasks at different priorities need a `lock`. Here
+// each resource is used by a single task, so no locks are needed.
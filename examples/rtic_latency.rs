@@ -0,0 +1,114 @@
+//! rtic_latency.rs
+//!
+//! Interrupt latency
+//!
+//! What it covers:
+//! - pending an interrupt from software (`rtic::pend`)
+//! - measuring the cycles from pending to the first line of the handler (CYCCNT)
+//! - a trigger/response pin pair for measuring the latency on a scope
+//!
+//! > cargo run --example rtic_latency --release
+//!
+//! Connect a scope to PA6 (CN10-13, trigger) and PA7 (CN10-15, response).
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::{asm, peripheral::DWT};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpioa::{PA6, PA7},
+        Output, PushPull, Speed,
+    },
+    prelude::*,
+    stm32::Interrupt,
+};
+
+use app::{trace::print_banner, trace_init};
+
+// cycles between measurements
+const PAUSE: u32 = 84_000_000;
+
+// CYCCNT at the time of pending, written by `idle`, read by the handler
+static TRIGGERED: AtomicU32 = AtomicU32::new(0);
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        trigger: PA6<Output<PushPull>>,
+        response: PA7<Output<PushPull>>,
+        // the last measured latency in cycles
+        #[init(0)]
+        latency: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the cycle counter (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let trigger = gpioa.pa6.into_push_pull_output().set_speed(Speed::VeryHigh);
+        let response = gpioa.pa7.into_push_pull_output().set_speed(Speed::VeryHigh);
+
+        init::LateResources { trigger, response }
+    }
+
+    #[idle(resources = [trigger, latency])]
+    fn idle(mut cx: idle::Context) -> ! {
+        loop {
+            cx.resources.trigger.set_high().ok();
+            TRIGGERED.store(DWT::get_cycle_count(), Ordering::SeqCst);
+            // set the pending bit in the NVIC (ISPR), the handler runs at once
+            rtic::pend(Interrupt::EXTI1);
+
+            cx.resources.trigger.set_low().ok();
+            let latency = cx.resources.latency.lock(|latency| *latency);
+            rprintln!("latency {} cycles", latency);
+
+            asm::delay(PAUSE);
+        }
+    }
+
+    // no EXTI line is configured, the interrupt is only pended from software
+    #[task(binds = EXTI1, resources = [response, latency])]
+    fn exti1(cx: exti1::Context) {
+        let entry = DWT::get_cycle_count();
+        cx.resources.response.set_high().ok();
+
+        *cx.resources.latency = entry.wrapping_sub(TRIGGERED.load(Ordering::SeqCst));
+
+        cx.resources.response.set_low().ok();
+    }
+};
+
+// Notes:
+//
+// The Cortex-M4 takes 12 cycles from an interrupt being pended to the first
+// instruction of the handler (with zero wait state memory). Expect somewhat
+// more, due to flash wait states, the RTIC entry code (setting the BASEPRI
+// for the task priority), and the store to the pending register itself.
+//
+// With `--release` the measurement is tighter. In a debug build, the
+// function calls in the HAL `set_high` and the atomics add cycles.
+//
+// On the scope, the distance between the rising edges of PA6 and PA7 shows
+// the latency, including the GPIO write. The width of the PA7 pulse shows the
+// (short) handler execution time.
+//
+// `rtic::pend` is the same as `NVIC::pend`, the NVIC doesn't know whether the
+// pending bit was set by a peripheral or by software.
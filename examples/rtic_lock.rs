@@ -0,0 +1,106 @@
+//! rtic_lock.rs
+//!
+//! Shared resources and the priority ceiling
+//!
+//! What it covers:
+//! - a resource shared between tasks of different priorities
+//! - `lock` for the lower priority accessors
+//! - direct access for the highest priority accessor
+//!
+//! > cargo run --example rtic_lock
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{TIM2, TIM3},
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// idle print period in clock cycles, ~1 s at 16 MHz
+const PRINT: u32 = 16_000_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        fast: Timer<TIM2>,
+        slow: Timer<TIM3>,
+        // shared by `idle` (0), `tim3` (1) and `tim2` (2), ceiling 2
+        #[init(0)]
+        counter: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // TIM2 update interrupt at 1 kHz
+        let mut fast = Timer::tim2(device.TIM2, 1.khz(), clocks);
+        fast.listen(Event::TimeOut);
+
+        // TIM3 update interrupt at 10 Hz
+        let mut slow = Timer::tim3(device.TIM3, 10.hz(), clocks);
+        slow.listen(Event::TimeOut);
+
+        init::LateResources { fast, slow }
+    }
+
+    #[idle(resources = [counter])]
+    fn idle(mut cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            let counter = cx.resources.counter.lock(|counter| *counter);
+            rprintln!("counter {}", counter);
+            asm::delay(PRINT);
+        }
+    }
+
+    // low priority, adds 1000 each time
+    #[task(binds = TIM3, priority = 1, resources = [slow, counter])]
+    fn tim3(mut cx: tim3::Context) {
+        cx.resources.slow.clear_interrupt(Event::TimeOut);
+
+        // `counter` is also accessed by `tim2` (priority 2), hence lock
+        cx.resources.counter.lock(|counter| *counter += 1000);
+    }
+
+    // high priority, adds 1 each time
+    #[task(binds = TIM2, priority = 2, resources = [fast, counter])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.fast.clear_interrupt(Event::TimeOut);
+
+        // no lock, `tim2` has the highest priority of all accessors (the ceiling)
+        *cx.resources.counter += 1;
+    }
+};
+
+// Notes:
+//
+// Why is the lock mandatory only on the lower priority side?
+//
+// `*counter += 1000` is a read-modify-write (load, add, store). If `tim2`
+// preempts `tim3` between the load and the store, the increment made by
+// `tim2` is lost when `tim3` stores its (stale) value. `lock` raises the
+// system priority to the ceiling (2) for the duration of the closure, so
+// `tim2` cannot preempt in the middle.
+//
+// `tim2` runs at the ceiling priority, so no task accessing `counter` can
+// preempt it. Its access is thus already exclusive, and RTIC hands it a plain
+// `&mut u32`. The same goes for `idle` vs. `tim3`: idle must lock as both
+// tasks may preempt it.
+//
+// Try removing the `lock` in `tim3`, the code does not compile. RTIC enforces
+// the locking at compile time, at zero cost for the highest priority task.
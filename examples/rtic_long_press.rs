@@ -0,0 +1,155 @@
+//! rtic_long_press.rs
+//!
+//! Short vs. long button presses
+//!
+//! What it covers:
+//! - triggering on both edges (`Edge::RISING_FALLING`)
+//! - timestamping the press (CYCCNT) and measuring the duration on release
+//! - classifying presses against a threshold
+//!
+//! > cargo run --example rtic_long_press
+//!
+//! Press the user button (B1):
+//! - SHORT, toggles the LED
+//! - LONG (at least `LONG_MS`), starts/stops blinking
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, gpioc::PC13, Edge, ExtiPin, Floating, Input, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    delay::ms_to_cycles, led::Led, schedule::try_reschedule, trace::print_banner, trace_init,
+};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+// presses at least this long are LONG
+const LONG_MS: u32 = 800;
+// presses shorter than this are contact bounce, and ignored
+const BOUNCE_MS: u32 = 20;
+// LED toggle period when blinking
+const BLINK_MS: u32 = 100;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        led: Led<PA5<Output<PushPull>>>,
+        // time of the press, `None` while released
+        #[init(None)]
+        pressed_at: Option<Instant>,
+        #[init(false)]
+        blinking: bool,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        // setup the user button (B1) on PC13, active low
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        // both EXTI_RTSR and EXTI_FTSR set, RM0368 10.3.3 and 10.3.4
+        button.trigger_on_edge(&mut device.EXTI, Edge::RISING_FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources { button, led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle, press the button");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [button, led, pressed_at, blinking], schedule = [blink])]
+    fn exti15_10(cx: exti15_10::Context) {
+        let now = Instant::now();
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        // the edge is given by the pin level (low = pressed)
+        if cx.resources.button.is_low().unwrap() {
+            // keep the first press, if bouncing
+            if cx.resources.pressed_at.is_none() {
+                *cx.resources.pressed_at = Some(now);
+            }
+            return;
+        }
+
+        let pressed_at = match cx.resources.pressed_at.take() {
+            Some(pressed_at) => pressed_at,
+            // release without a press (e.g., pressed during reset)
+            None => return,
+        };
+        let cycles = now.duration_since(pressed_at).as_cycles();
+        let ms = cycles / (SYSCLK_HZ / 1_000);
+
+        if cycles < ms_to_cycles(BOUNCE_MS, SYSCLK_HZ) {
+            // a bounce, wait for the press to settle
+            *cx.resources.pressed_at = Some(pressed_at);
+        } else if cycles < ms_to_cycles(LONG_MS, SYSCLK_HZ) {
+            rprintln!("SHORT ({} ms)", ms);
+            cx.resources.led.toggle().ok();
+        } else {
+            rprintln!("LONG  ({} ms)", ms);
+            *cx.resources.blinking = !*cx.resources.blinking;
+            if *cx.resources.blinking {
+                try_reschedule(cx.schedule.blink(now), "blink");
+            }
+        }
+    }
+
+    #[task(resources = [led, blinking], schedule = [blink])]
+    fn blink(cx: blink::Context) {
+        if *cx.resources.blinking {
+            cx.resources.led.toggle().ok();
+            let offset = ms_to_cycles(BLINK_MS, SYSCLK_HZ);
+            try_reschedule(cx.schedule.blink(cx.scheduled + offset.cycles()), "blink");
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// A bounce on release looks like a very short press followed by a release.
+// Releases closer than BOUNCE_MS to the press are treated as bounce (the
+// press time is kept), so a bouncy button still yields one classification.
+//
+// Two LONG presses within BLINK_MS may schedule `blink` while a message is
+// still queued, `try_reschedule` then reports the (harmless) failure.
+//
+// An alternative to measuring on release, is to schedule a task LONG_MS after
+// the press, and cancel it (or ignore it) on an early release. This reports
+// LONG while the button is still held, which often feels more responsive.
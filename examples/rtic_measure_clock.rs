@@ -0,0 +1,217 @@
+//! rtic_measure_clock.rs
+//!
+//! Measure SYSCLK at run-time
+//!
+//! What it covers:
+//! - counting core cycles (CYCCNT) over a number of LSE periods, captured by
+//!   TIM5 channel 4 (remapped to the LSE), an independent time base
+//! - validating the outcome of `freeze()` without an oscilloscope
+//! - checking the AHB prescaler, CYCCNT against SysTick (both run off HCLK)
+//!
+//! > cargo run --example rtic_measure_clock --features rtt
+//!
+//! The Nucleo has a 32.768 kHz crystal (X2) connected to the LSE (PC14/PC15).
+//! Without it, the LSI (~32 kHz, +/- 50%) is used, only good for a coarse check.
+//!
+//! The raw measurements are logged at the `debug` level, add `--release`
+//! to see only the result (see `MAX_LEVEL` in `src/log.rs`).
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::{syst::SystClkSource, DWT, SYST};
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{PWR, RCC, TIM5},
+};
+
+// the panic handler matching the log backend
+#[cfg(feature = "defmt-log")]
+use panic_probe as _;
+#[cfg(feature = "rtt")]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use app::{debug, info, log_init, warn};
+
+// LSE frequency in Hz
+const LSE_HZ: u32 = 32_768;
+// LSI frequency in Hz, nominal (17..47 kHz on the F401)
+const LSI_HZ: u32 = 32_000;
+// LSE start-up timeout in clock cycles, 2 s at 16 MHz (the HSI)
+const LSE_TIMEOUT: u32 = 32_000_000;
+
+// input capture prescaler, a capture every 2^ICPSC reference periods
+const ICPSC: u8 = 3;
+// number of captures to measure over, 4096 * 8 LSE periods = 1 s
+const CAPTURES: u32 = 4096;
+
+// SysTick is a 24 bit down counter, wraps every (RELOAD + 1) counts
+const RELOAD: u32 = 1_000_000 - 1;
+// number of SysTick wraps to measure over
+const TICKS: u32 = 10;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        log_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the cycle counter (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // the reference clock, started while still running on the HSI
+        let lse = start_lse(&device.RCC, &device.PWR);
+        let ref_hz = if lse {
+            LSE_HZ
+        } else {
+            warn!("LSE failed to start (no X2 crystal?), using the LSI");
+            device.RCC.csr.modify(|_, w| w.lsion().on());
+            while device.RCC.csr.read().lsirdy().is_not_ready() {}
+            LSI_HZ
+        };
+
+        // power on TIM5, RM0368 6.3.11
+        device.RCC.apb1enr.modify(|_, w| w.tim5en().enabled());
+
+        let rcc = device.RCC.constrain();
+
+        // try out different configurations
+        // let clocks = rcc.cfgr.freeze();
+        let clocks = rcc.cfgr.sysclk(48.mhz()).pclk1(24.mhz()).freeze();
+
+        let tim = device.TIM5;
+        // TI4 remapped to the LSE (0b10) or the LSI (0b01), TIM5_OR, RM0368 13.4.21
+        tim.or
+            .write(|w| unsafe { w.it4_rmp().bits(if lse { 0b10 } else { 0b01 }) });
+        // CC4 as input mapped on TI4, a capture every 2^ICPSC edges, RM0368 13.4.8
+        tim.ccmr2_input()
+            .write(|w| w.cc4s().ti4().ic4psc().bits(ICPSC));
+        // capture on the rising edge, RM0368 13.4.9
+        tim.ccer.write(|w| w.cc4e().set_bit());
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        // core cycles over CAPTURES * 2^ICPSC reference periods
+        let cycles = measure_ref(&tim);
+        let periods = CAPTURES << ICPSC;
+        let measured_hz = (cycles as u64 * ref_hz as u64 / periods as u64) as u32;
+
+        debug!("CYCCNT cycles {}, reference periods {}", cycles, periods);
+        info!(
+            "measured SYSCLK {} Hz (against the {})",
+            measured_hz,
+            if lse { "LSE" } else { "LSI" }
+        );
+        info!("expected SYSCLK {} Hz", clocks.sysclk().0);
+
+        // SysTick driven by the external reference clock, HCLK / 8, PM0214 4.5
+        let mut syst = core.SYST;
+        syst.set_clock_source(SystClkSource::External);
+        syst.set_reload(RELOAD);
+        syst.clear_current();
+        syst.enable_counter();
+
+        // elapsed SysTick counts, (RELOAD + 1) per wrap
+        let cycles = measure_systick(&mut syst);
+        let systick_counts = TICKS * (RELOAD + 1);
+        let ratio = cycles as f32 / systick_counts as f32;
+
+        debug!(
+            "CYCCNT cycles {}, SysTick counts {}",
+            cycles, systick_counts
+        );
+        info!(
+            "CYCCNT / SysTick ratio {} (SYSCLK / HCLK * 8, expected {})",
+            ratio,
+            8 * clocks.sysclk().0 / clocks.hclk().0
+        );
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        debug!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Start the LSE, RM0368 6.3.20, `false` on timeout
+fn start_lse(rcc: &RCC, pwr: &PWR) -> bool {
+    // the LSE is in the backup domain, PWR_CR DBP, RM0368 5.4.1
+    rcc.apb1enr.modify(|_, w| w.pwren().enabled());
+    pwr.cr.modify(|_, w| w.dbp().set_bit());
+
+    rcc.bdcr.modify(|_, w| w.lseon().on());
+    let start = DWT::get_cycle_count();
+    while DWT::get_cycle_count().wrapping_sub(start) < LSE_TIMEOUT {
+        if rcc.bdcr.read().lserdy().is_ready() {
+            return true;
+        }
+    }
+    false
+}
+
+// Busy wait for `CAPTURES` captures, and return the elapsed CYCCNT cycles.
+#[inline(never)]
+fn measure_ref(tim: &TIM5) -> u32 {
+    // reading CCR4 clears CC4IF, sync to a capture
+    tim.ccr4.read();
+    while tim.sr.read().cc4if().bit_is_clear() {}
+    tim.ccr4.read();
+
+    let start = DWT::get_cycle_count();
+    for _ in 0..CAPTURES {
+        while tim.sr.read().cc4if().bit_is_clear() {}
+        tim.ccr4.read();
+    }
+    let end = DWT::get_cycle_count();
+
+    end.wrapping_sub(start)
+}
+
+// Busy wait for `TICKS` SysTick wraps, and return the elapsed CYCCNT cycles.
+#[inline(never)]
+fn measure_systick(syst: &mut SYST) -> u32 {
+    // sync to a wrap, `has_wrapped` clears the COUNTFLAG on read
+    syst.has_wrapped();
+    while !syst.has_wrapped() {}
+
+    let start = DWT::get_cycle_count();
+    for _ in 0..TICKS {
+        while !syst.has_wrapped() {}
+    }
+    let end = DWT::get_cycle_count();
+
+    end.wrapping_sub(start)
+}
+
+// Notes:
+//
+// The LSE is a crystal oscillator, independent of the HSI/HSE/PLL, so the
+// cycle count over a known number of LSE periods measures the actual SYSCLK.
+// A wrong PLL setting, e.g., a wrong PLLM for the HSE, shows up as a mismatch
+// between the measured and the expected SYSCLK. The crystal tolerance (~20 ppm)
+// is far below anything a misconfiguration causes.
+//
+// TIM5 is only used to capture the edges, the timer clock (and thus the APB1
+// prescaler) does not enter the result. Each capture sets CC4IF, which is
+// polled, and CYCCNT is read right after. The polling latency (a few cycles)
+// is the same for the first and the last capture, and cancels out.
+//
+// With ICPSC = 3, a capture comes every 8 LSE periods (~244 us), plenty of
+// time for the polling loop. The measurement spans 1 s, 48e6 cycles at 48 MHz,
+// the u32 CYCCNT difference wraps only above ~4.29 GHz.
+//
+// CYCCNT counts core (SYSCLK) cycles, while SysTick (with the external reference)
+// counts HCLK / 8. Both run off HCLK, so their ratio only tells the AHB prescaler:
+// with the default (HCLK = SYSCLK) the ratio should be exactly 8, regardless of
+// the SYSCLK frequency. It cannot tell if SYSCLK itself is right.
+//
+// With RELOAD + 1 = 1e6 and TICKS = 10 the SysTick check spans 8e7 SYSCLK cycles
+// (about 1.7 seconds at 48 MHz). Make sure TICKS * (RELOAD + 1) * 8 fits in a u32.
@@ -0,0 +1,197 @@
+//! rtic_menu.rs
+//!
+//! A tiny menu, rotary encoder with push button
+//!
+//! What it covers:
+//! - the encoder (TIM4 `Qei`, see `rtic_encoder.rs`) scrolling through the items
+//! - the encoder push button confirming the selection (EXTI, with a debounce)
+//! - the menu state in a resource, rendered by pure functions (`app::menu`)
+//!
+//! > cargo run --example rtic_menu
+//!
+//! Wiring (rotary encoder with push button, e.g., KY-040):
+//!
+//! A   -> PB6 (CN10-17, TIM4_CH1)
+//! B   -> PB7 (CN7-21, TIM4_CH2)
+//! SW  -> PA0 (CN7-28)
+//! GND -> GND
+//!
+//! The LED (LD2, PA5) blinks the number of the selected item (1, 2, 3, ...).
+
+#![no_main]
+#![no_std]
+
+use embedded_hal::Qei as _;
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpioa::{PA0, PA5},
+        gpiob::{PB6, PB7},
+        Alternate, Edge, ExtiPin, Input, Output, PullUp, PushPull, AF2,
+    },
+    prelude::*,
+    qei::Qei,
+    stm32::TIM4,
+};
+
+use app::{
+    delay::ms_to_cycles,
+    led::Led,
+    menu::{blink, Menu, TICK_MS},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+// encoder counts per detent (x4 mode)
+const COUNTS_PER_DETENT: i32 = 4;
+// presses closer than this are bounces, 50 ms in clock cycles
+const DEBOUNCE: u32 = 800_000;
+
+const ITEMS: [&str; 4] = ["start", "stop", "speed", "reset"];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        qei: Qei<TIM4, (PB6<Alternate<AF2>>, PB7<Alternate<AF2>>)>,
+        button: PA0<Input<PullUp>>,
+        led: Led<PA5<Output<PushPull>>>,
+        // last counter value
+        last: u16,
+        // counts not yet making a full detent
+        #[init(0)]
+        counts: i32,
+        menu: Menu<'static>,
+        // the blink tick, restarted on a change of selection
+        #[init(0)]
+        tick: u32,
+        #[init(None)]
+        pressed_at: Option<Instant>,
+    }
+
+    #[init(schedule = [update])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpiob = device.GPIOB.split();
+        let a = gpiob.pb6.into_pull_up_input().into_alternate_af2();
+        let b = gpiob.pb7.into_pull_up_input().into_alternate_af2();
+        let qei = Qei::tim4(device.TIM4, (a, b));
+        let last = qei.count();
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        // the push button connects to GND, interrupt on press
+        let mut button = gpioa.pa0.into_pull_up_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        rprintln!("menu {:?}", ITEMS);
+
+        cx.schedule.update(cx.start).unwrap();
+
+        init::LateResources {
+            qei,
+            button,
+            led,
+            last,
+            menu: Menu::new(&ITEMS),
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    // poll the encoder and render the menu on the LED, every tick
+    #[task(resources = [qei, led, last, counts, menu, tick], schedule = [update])]
+    fn update(cx: update::Context) {
+        let count = cx.resources.qei.count();
+        // signed difference across the 16 bit wrap, see `rtic_encoder.rs`
+        let delta = count.wrapping_sub(*cx.resources.last) as i16;
+        *cx.resources.last = count;
+
+        // whole detents, the remainder is kept for the next tick
+        *cx.resources.counts += delta as i32;
+        let steps = *cx.resources.counts / COUNTS_PER_DETENT;
+        *cx.resources.counts -= steps * COUNTS_PER_DETENT;
+
+        let menu = cx.resources.menu;
+        if steps != 0 {
+            menu.scroll(steps);
+            *cx.resources.tick = 0;
+            rprintln!("> {} ({})", menu.label(), menu.selected() + 1);
+        }
+
+        if blink(menu.selected(), *cx.resources.tick) {
+            cx.resources.led.on().ok();
+        } else {
+            cx.resources.led.off().ok();
+        }
+        *cx.resources.tick += 1;
+
+        let offset = ms_to_cycles(TICK_MS, SYSCLK_HZ);
+        if !try_reschedule(cx.schedule.update(cx.scheduled + offset.cycles()), "update") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    // confirm, at the same priority as `update`, so the menu needs no lock
+    #[task(binds = EXTI0, resources = [button, menu, pressed_at])]
+    fn exti0(cx: exti0::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        // ignore the bounces following a press
+        let now = cx.start;
+        if let Some(pressed_at) = *cx.resources.pressed_at {
+            if now.duration_since(pressed_at) < DEBOUNCE.cycles() {
+                return;
+            }
+        }
+        *cx.resources.pressed_at = Some(now);
+
+        rprintln!("confirmed {}", cx.resources.menu.label());
+    }
+
+    extern "C" {
+        fn EXTI1();
+    }
+};
+
+// Notes:
+//
+// The menu logic (wrapping the selection, the blink sequence) is in
+// `src/menu.rs` and tested on the host. The example only connects it to the
+// encoder, the button and the LED.
+//
+// The encoder is polled every TICK_MS (200 ms), which is fine for a menu,
+// the counter keeps counting in hardware between polls. Turning a detent
+// both ways within a tick cancels out.
+//
+// The blink sequence restarts on each change of selection, so the count
+// always starts from the first blink.
@@ -0,0 +1,127 @@
+//! rtic_morse.rs
+//!
+//! Blinking a message in morse code
+//!
+//! What it covers:
+//! - the pure logic in a host tested module (`app::morse`)
+//! - stepping through an iterator from a scheduled task, one run per step
+//! - converting durations to clock cycles (`ms_to_cycles`)
+//!
+//! > cargo run --example rtic_morse
+//!
+//! The LED (LD2, PA5) blinks MESSAGE, over and over.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    delay::ms_to_cycles,
+    led::Led,
+    morse::{render, Render, UNIT_MS},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+
+// letters, digits and spaces, other characters are skipped
+const MESSAGE: &str = "E7020E";
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+        // the runs left of the message
+        runs: Render<'static>,
+    }
+
+    #[init(schedule = [step])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        cx.schedule.step(cx.start).unwrap();
+
+        init::LateResources {
+            led,
+            runs: render(MESSAGE),
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // one (on, units) run per step, the LED is kept until the next step
+    #[task(resources = [led, runs], schedule = [step])]
+    fn step(cx: step::Context) {
+        let runs = cx.resources.runs;
+        let (on, units) = match runs.next() {
+            Some(run) => run,
+            None => {
+                // start over, the message ends with a word gap
+                rprintln!("{}", MESSAGE);
+                *runs = render(MESSAGE);
+                // an empty message (nothing to blink), wait a word gap
+                runs.next().unwrap_or((false, 7))
+            }
+        };
+
+        let led = cx.resources.led;
+        if on {
+            led.on().ok();
+        } else {
+            led.off().ok();
+        }
+
+        let offset = ms_to_cycles(units * UNIT_MS, SYSCLK_HZ);
+        if !try_reschedule(cx.schedule.step(cx.scheduled + offset.cycles()), "step") {
+            // the task stopped, leave the LED off
+            led.off().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The encoding and the timing are in `src/morse.rs`, tested on the host
+// (see the README), only the LED and the scheduling are left here. Compare
+// with `src/pattern.rs`, where the SOS pattern is a hand written table.
+//
+// Each step schedules the next at the end of the current run, relative to
+// the previous deadline (`cx.scheduled`), so the timing does not drift.
+//
+// UNIT_MS (120 ms) gives 10 words per minute (1200 / unit in ms), use
+// another unit for a different speed.
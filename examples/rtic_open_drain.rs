@@ -0,0 +1,148 @@
+//! rtic_open_drain.rs
+//!
+//! Open-drain output, sinking the current of an external load
+//!
+//! What it covers:
+//! - configuring a pin as open-drain output (`into_open_drain_output`)
+//! - an active low load, on when the pin is driven low
+//! - the output type register (GPIOx_OTYPER), read back
+//! - push-pull versus open-drain, and when open-drain is required (see notes)
+//!
+//! > cargo run --example rtic_open_drain
+//!
+//! Wiring (LED with series resistor, from the supply to the pin):
+//!
+//! 3V3 (CN7-16) -> 330 Ohm -> LED anode, LED cathode -> PA10 (CN9-3, D2)
+//!
+//! The LED lights when PA10 is low (sinking ~4 mA). For a relay, use a relay
+//! module with an (active low) transistor input, see notes.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA10, OpenDrain, Output},
+    prelude::*,
+    stm32::GPIOA,
+};
+
+use app::{delay::ms_to_cycles, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+// toggle period in ms
+const PERIOD_MS: u32 = 1_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        load: PA10<Output<OpenDrain>>,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        // start released (high impedance), the load off
+        let mut load = gpioa.pa10.into_open_drain_output();
+        load.set_high().ok();
+
+        // SAFETY: read only access
+        let otyper = unsafe { (*GPIOA::ptr()).otyper.read().bits() };
+        // OT10, 0: push-pull, 1: open-drain, GPIOx_OTYPER, RM0368 8.4.2
+        rprintln!("OTYPER 0x{:04x}, OT10 {}", otyper, (otyper >> 10) & 1);
+
+        cx.schedule.toggle(cx.start).unwrap();
+
+        init::LateResources { load }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [load], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        let load = cx.resources.load;
+        // active low, driving low turns the load on
+        if load.is_set_low().unwrap_or(false) {
+            load.set_high().ok();
+            rprintln!("off (released)");
+        } else {
+            load.set_low().ok();
+            rprintln!("on (sinking)");
+        }
+
+        let offset = ms_to_cycles(PERIOD_MS, SYSCLK_HZ);
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + offset.cycles()), "toggle") {
+            // the task stopped, release the pin (load off)
+            load.set_high().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// Output types (GPIOx_OTYPER, one bit per pin, RM0368 8.3 and 8.4.2):
+//
+// - push-pull (OTy = 0, the reset state), the P-MOS drives the pin high, the
+//   N-MOS drives it low. The pin both sources and sinks current, as for the
+//   on-board LED (LD2, PA5) in the other examples.
+// - open-drain (OTy = 1), only the N-MOS is used. Writing 0 pulls the pin
+//   low (sinks current), writing 1 releases it (high impedance). The high
+//   level comes from the outside, a pull-up resistor or the load itself.
+//
+// `into_open_drain_output` sets OTy (and MODER to output). The HAL also has
+// `set_open_drain` for alternate functions, as used for I2C in
+// `rtt_rtic_i2c.rs`.
+//
+// Open-drain is required (or the natural choice) for:
+//
+// - wired-AND buses, several devices on one line, any of them can pull it
+//   low, none can drive it high against another (I2C, 1-Wire, interrupt
+//   lines shared by several chips). With push-pull, one device driving high
+//   and another low short circuits the supply.
+// - level shifting, the pull-up goes to the other device's supply. On a 5 V
+//   tolerant pin (FT in the pin table of the data sheet, e.g., PA10) the
+//   load may be pulled up to 5 V, the pin never drives it above 3.3 V.
+// - active low inputs of other devices, e.g., a reset or enable line, which
+//   must be released rather than driven high.
+// - scanning a key matrix, see `rtic_keypad.rs`, two keys pressed at once
+//   cannot short two driven rows.
+//
+// Current: a pin sinks at most 25 mA, and all pins together at most 120 mA
+// (data sheet, absolute maximum ratings), 8 mA keeps the output low level
+// within spec. Anything more (a motor, a relay coil, an LED strip) needs a
+// transistor or a MOSFET in between. A bare relay coil also needs a flyback
+// diode across it, the switch off spike destroys the pin otherwise. Relay
+// modules contain both, and usually have an active low input, which an
+// open-drain output drives directly (pull-up on the module).
+//
+// With a pull-up (internal ~40 kOhm, `into_open_drain_output` followed by
+// `internal_pull_up(true)`, or external) the rising edge is slow, the pull-up
+// charges the line capacitance (RC), while the falling edge is driven. This
+// limits the speed of open-drain buses (I2C uses 1..10 kOhm).
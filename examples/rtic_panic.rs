@@ -14,6 +14,9 @@ use panic_semihosting as _;
 // Logs panic messages using the ITM (Instrumentation Trace Macrocell)
 // use panic_itm as _;
 
+// Reports panic messages and locations over RTT, requires `--features panic-rtt`
+// use app::panic as _;
+
 use stm32f4;
 
 #[rtic::app(device = stm32f4)]
@@ -0,0 +1,107 @@
+//! rtic_panic_count.rs
+//!
+//! Counting panics across resets
+//!
+//! What it covers:
+//! - the RTT panic handler (`app::panic`) with the `panic-count` feature
+//! - reading the panic count from the backup register at boot
+//! - clearing the count (user button B1)
+//!
+//! > cargo run --example rtic_panic_count --features panic-count
+//!
+//! The example panics a few seconds after boot. Press the reset button (B2) to
+//! restart, the count increases by one per panic. Hold the user button (B1)
+//! while pressing reset to clear the count.
+
+#![no_main]
+#![no_std]
+
+use app::panic as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::prelude::*;
+
+use app::{
+    backup::enable_backup_access,
+    panic::{clear_panic_count, panic_count},
+    trace::print_banner,
+    trace_init,
+};
+
+// seconds until the panic
+const COUNTDOWN: u32 = 3;
+// countdown period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    #[init(schedule = [countdown])]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // the count is in the backup domain
+        enable_backup_access(&device.RCC, &device.PWR);
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // user button B1 (PC13), active low
+        let gpioc = device.GPIOC.split();
+        let button = gpioc.pc13.into_floating_input();
+        if button.is_low().unwrap() {
+            clear_panic_count(&device.RTC);
+            rprintln!("panic count cleared");
+        }
+        rprintln!("{} panics so far", panic_count(&device.RTC));
+
+        cx.schedule
+            .countdown(cx.start + OFFSET.cycles(), COUNTDOWN)
+            .unwrap();
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(schedule = [countdown])]
+    fn countdown(cx: countdown::Context, left: u32) {
+        if left == 0 {
+            panic!("countdown reached zero");
+        }
+        rprintln!("panic in {}", left);
+        cx.schedule
+            .countdown(cx.scheduled + OFFSET.cycles(), left - 1)
+            .unwrap();
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The handler increments the count before it prints, so a panic is counted
+// even if the RTT output fails. It enables the backup domain access itself,
+// also a panic before `enable_backup_access` is counted.
+//
+// The handler halts on a breakpoint. Without a debugger attached, the
+// breakpoint escalates to a HardFault (with the CPU locked up), and the board
+// stays there until reset. For unattended testing, combine with the
+// independent watchdog (`rtic_iwdg.rs`), which then resets the board, and the
+// count tells how often that happened.
+//
+// The count lives in the backup domain, it survives system resets (reset
+// button, watchdog, software reset), but not a power cycle unless VBAT is
+// supplied. On the Nucleo VBAT is connected to VDD.
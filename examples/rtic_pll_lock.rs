@@ -0,0 +1,192 @@
+//! rtic_pll_lock.rs
+//!
+//! Measuring the PLL lock time
+//!
+//! What it covers:
+//! - enabling the PLL at register level (RCC_CR PLLON), SYSCLK staying on the HSI
+//! - timing PLLON to PLLRDY with the cycle counter (CYCCNT), at 16 MHz
+//! - the PLL fed by the HSI, and by the HSE if present (`clock::start_hse`)
+//!
+//! > cargo run --example rtic_pll_lock --release
+//!
+//! The HSE is the 8 MHz ST-LINK MCO (bypass mode), see `rtic_hse.rs`.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::stm32::RCC;
+
+use app::{
+    clock::{start_hse, HSE_TIMEOUT, HSI_MHZ},
+    trace::print_banner,
+    trace_init,
+};
+
+// HSE frequency in MHz, the ST-LINK MCO on the Nucleo
+const HSE_MHZ: u32 = 8;
+// OSC_IN driven by an external clock (true), or a crystal (false)
+const BYPASS: bool = true;
+
+// measurements per PLL source
+const RUNS: u32 = 8;
+// give up after 10 ms (at 16 MHz), the PLL locks within ~0.1 ms
+const LOCK_TIMEOUT: u32 = 160_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PllSource {
+    Hsi,
+    Hse,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // the cycle counter, counting SYSCLK (the HSI) throughout
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // SYSCLK is never switched to the PLL, the HAL `freeze` is not used
+        print_banner(HSI_MHZ * 1_000_000);
+
+        let rcc = &device.RCC;
+        measure(rcc, PllSource::Hsi);
+
+        // the HSE startup itself, for comparison
+        let start = DWT::get_cycle_count();
+        let hse = start_hse(rcc, BYPASS, HSE_TIMEOUT);
+        let cycles = DWT::get_cycle_count().wrapping_sub(start);
+        if hse {
+            rprintln!("HSE startup {} us (bypass {})", to_us(cycles), BYPASS);
+            measure(rcc, PllSource::Hse);
+            rcc.cr.modify(|_, w| w.hseon().off());
+        } else {
+            rprintln!("HSE not ready, skipping the HSE driven PLL");
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Lock the PLL from `source` RUNS times, and print the min/max lock time
+fn measure(rcc: &RCC, source: PllSource) {
+    configure(rcc, source);
+
+    let (mut min, mut max) = (u32::MAX, 0);
+    for _ in 0..RUNS {
+        match lock(rcc) {
+            Some(cycles) => {
+                min = min.min(cycles);
+                max = max.max(cycles);
+            }
+            None => {
+                rprintln!(
+                    "{:?}: PLL not locked within {} cycles",
+                    source,
+                    LOCK_TIMEOUT
+                );
+                return;
+            }
+        }
+    }
+    rprintln!(
+        "{:?}: PLL lock {}..{} us ({}..{} cycles)",
+        source,
+        to_us(min),
+        to_us(max),
+        min,
+        max
+    );
+}
+
+// The PLL configuration, 84 MHz (not used as SYSCLK) from either source
+fn configure(rcc: &RCC, source: PllSource) {
+    // PLLCFGR may only be written while the PLL is off, RM0368 6.3.2
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    // M gives 2 MHz at the PLL input for both, x 168 / 4 = 84 MHz
+    let m = match source {
+        PllSource::Hsi => HSI_MHZ / 2,
+        PllSource::Hse => HSE_MHZ / 2,
+    };
+    rcc.pllcfgr.write(|w| unsafe {
+        let w = match source {
+            PllSource::Hsi => w.pllsrc().hsi(),
+            PllSource::Hse => w.pllsrc().hse(),
+        };
+        w.pllm()
+            .bits(m as u8)
+            .plln()
+            .bits(168)
+            .pllp()
+            .div4()
+            .pllq()
+            .bits(7)
+    });
+}
+
+// Turn on the PLL and count the cycles until PLLRDY, RCC_CR, RM0368 6.3.1
+//
+// `None` on timeout. The PLL is turned off again afterwards.
+#[inline(never)]
+fn lock(rcc: &RCC) -> Option<u32> {
+    let start = DWT::get_cycle_count();
+    rcc.cr.modify(|_, w| w.pllon().on());
+    let mut cycles = 0;
+    while cycles < LOCK_TIMEOUT {
+        cycles = DWT::get_cycle_count().wrapping_sub(start);
+        if rcc.cr.read().pllrdy().is_ready() {
+            break;
+        }
+    }
+
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    if cycles < LOCK_TIMEOUT {
+        Some(cycles)
+    } else {
+        None
+    }
+}
+
+// Cycles at the HSI (SYSCLK) to microseconds
+fn to_us(cycles: u32) -> u32 {
+    cycles / HSI_MHZ
+}
+
+// Notes:
+//
+// The data sheet (DS10086, PLL characteristics, tLOCK) gives a lock time in
+// the order of 100 us, depending on the VCO frequency. The HSE startup is
+// much longer for a crystal (tSU(HSE), ~2 ms), but close to nothing for the
+// ST-LINK MCO in bypass mode (a clock is already present). The HSI itself
+// starts within a few us, which is why the MCU always boots on the HSI.
+//
+// The time is counted at 16 MHz (1 cycle = 62.5 ns). The polling loop takes
+// a few cycles per iteration, well below 1 us, so the resolution is fine for
+// this purpose.
+//
+// The PLL input is 2 MHz for both sources (M = 8 or 4). The lock time
+// depends on the input frequency and the VCO frequency, not on the source,
+// try other M/N values to see the effect.
+//
+// A startup to 84 MHz thus costs ~0.1 ms for the PLL (+ ~2 ms for a crystal).
+// Compare to the cost of a wake up from STOP mode (`rtic_stop_mode.rs`),
+// where the clocks are restarted each time, the PLL lock dominates the wake
+// up latency if the PLL is used.
@@ -0,0 +1,221 @@
+//! rtic_pll_sweep.rs
+//!
+//! Changing SYSCLK at run-time
+//!
+//! What it covers:
+//! - reconfiguring the PLL on the fly (HSI -> PLL off -> reconfigure -> relock -> PLL)
+//! - flash wait states and the APB1 prescaler for each frequency
+//! - observing SYSCLK on MCO2 (PC9)
+//!
+//! > cargo run --example rtic_pll_sweep
+//!
+//! Press the user button (B1) to step through `STEPS`, with a scope on PC9
+//! (CN10-1) the MCO2 frequency steps accordingly.
+//!
+//! The HAL computes the clock configuration once (`freeze`), so this example
+//! does not use the HAL `rcc` at all, and programs the RCC registers directly.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+    stm32::{FLASH, RCC},
+};
+
+use app::{
+    mco::{ClockOut, Mco2Prescaler},
+    trace_init,
+};
+
+// A SYSCLK setting, SYSCLK = 1 MHz x PLLN / PLLP (PLLM divides the HSI to 1 MHz)
+#[derive(Clone, Copy)]
+struct Step {
+    mhz: u32,
+    // PLLN and PLLP, `None` runs directly from the HSI
+    pll: Option<(u16, u8)>,
+    // MCO2 prescaler, keeps the MCO2 output at or below ~21 MHz
+    mco_div: u32,
+}
+
+// VCO = PLLN MHz must be within 192..=432 MHz, RM0368 6.3.2
+const STEPS: [Step; 4] = [
+    Step {
+        mhz: 16,
+        pll: None,
+        mco_div: 1,
+    },
+    Step {
+        mhz: 42,
+        pll: Some((252, 6)),
+        mco_div: 2,
+    },
+    Step {
+        mhz: 64,
+        pll: Some((256, 4)),
+        mco_div: 4,
+    },
+    Step {
+        mhz: 84,
+        pll: Some((336, 4)),
+        mco_div: 4,
+    },
+];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        rcc: RCC,
+        flash: FLASH,
+        #[init(0)]
+        step: usize,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        // SYSCLK (16 MHz HSI after reset) on MCO2 (PC9)
+        ClockOut::mco2_sysclk(&device.RCC, &device.GPIOC, Mco2Prescaler::Div1);
+
+        // prefetch and caches, compensating for the wait states, RM0368 3.4.1
+        device
+            .FLASH
+            .acr
+            .modify(|_, w| w.prften().set_bit().icen().set_bit().dcen().set_bit());
+
+        // setup the user button (B1) on PC13, `split` powers on GPIOC
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        rprintln!("sysclk {} MHz, press the button", STEPS[0].mhz);
+
+        init::LateResources {
+            button,
+            rcc: device.RCC,
+            flash: device.FLASH,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [button, rcc, flash, step])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        *cx.resources.step = (*cx.resources.step + 1) % STEPS.len();
+        let step = STEPS[*cx.resources.step];
+
+        set_sysclk(cx.resources.rcc, cx.resources.flash, &step);
+
+        rprintln!(
+            "sysclk {} MHz, MCO2 {} kHz",
+            step.mhz,
+            step.mhz * 1_000 / step.mco_div
+        );
+    }
+};
+
+// Switch SYSCLK to `step`, RM0368 6.3.1 - 6.3.3 and 3.4.1
+fn set_sysclk(rcc: &RCC, flash: &FLASH, step: &Step) {
+    // 1. run from the HSI, the PLL can only be reconfigured when off
+    rcc.cfgr.modify(|_, w| w.sw().hsi());
+    while !rcc.cfgr.read().sws().is_hsi() {}
+
+    // 2. turn off the PLL
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    // 3. the flash wait states for the new frequency, set while running at 16 MHz,
+    // so they are sufficient both before and after the switch
+    set_wait_states(flash, wait_states(step.mhz));
+
+    // 4. APB1 at most 42 MHz, RM0368 6.3.3 (PPRE1)
+    rcc.cfgr.modify(|_, w| {
+        if step.mhz > 42 {
+            w.ppre1().div2()
+        } else {
+            w.ppre1().div1()
+        }
+    });
+
+    if let Some((plln, pllp)) = step.pll {
+        // 5. reconfigure and relock the PLL
+        rcc.pllcfgr.write(|w| unsafe {
+            w.pllsrc()
+                .hsi()
+                .pllm()
+                .bits(16)
+                .plln()
+                .bits(plln)
+                // 0b00: /2, 0b01: /4, 0b10: /6, 0b11: /8
+                .pllp()
+                .bits(pllp / 2 - 1)
+                // only used for USB/SDIO, must be 2..=15
+                .pllq()
+                .bits(7)
+        });
+        rcc.cr.modify(|_, w| w.pllon().on());
+        while rcc.cr.read().pllrdy().is_not_ready() {}
+
+        // 6. switch to the PLL
+        rcc.cfgr.modify(|_, w| w.sw().pll());
+        while !rcc.cfgr.read().sws().is_pll() {}
+    }
+
+    // 7. MCO2 prescaler for the new frequency
+    rcc.cfgr.modify(|_, w| match step.mco_div {
+        1 => w.mco2pre().div1(),
+        2 => w.mco2pre().div2(),
+        _ => w.mco2pre().div4(),
+    });
+}
+
+// Flash wait states at 2.7..3.6 V, RM0368 3.4.1 Table 6
+fn wait_states(mhz: u32) -> u8 {
+    match mhz {
+        0..=30 => 0,
+        31..=60 => 1,
+        _ => 2,
+    }
+}
+
+fn set_wait_states(flash: &FLASH, ws: u8) {
+    flash.acr.modify(|_, w| match ws {
+        0 => w.latency().ws0(),
+        1 => w.latency().ws1(),
+        _ => w.latency().ws2(),
+    });
+    // the new value must be read back before the frequency is raised
+    while flash.acr.read().latency().bits() != ws {}
+}
+
+// Notes:
+//
+// Running with too few wait states makes the CPU read garbage from flash,
+// typically causing a HardFault. Try setting `wait_states` to always return 0
+// and step up to 84 MHz.
+//
+// Everything derived from the clocks (baud rates, timers, CYCCNT based delays)
+// goes wrong after a change. That is why the HAL only allows the configuration
+// to be frozen once, and drivers take `clocks` as an argument.
+//
+// The RTT (debug) link is independent of SYSCLK, so printing still works.
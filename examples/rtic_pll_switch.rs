@@ -0,0 +1,245 @@
+//! rtic_pll_switch.rs
+//!
+//! Switching the PLL input between HSI and HSE at run-time
+//!
+//! What it covers:
+//! - the PLL configuration at register level (RCC_PLLCFGR)
+//! - the safe switch sequence, SYSCLK to HSI, PLL off, new source, relock, back to PLL
+//! - falling back to the HSI if the HSE does not start
+//!
+//! > cargo run --example rtic_pll_switch
+//!
+//! Starts on the HSI driven PLL (84 MHz), each press of the user button (B1)
+//! toggles the PLL input between HSI and HSE. SYSCLK / 4 (21 MHz) is output on
+//! MCO2 (PC9, CN10-1), observe the change in accuracy and jitter on a scope.
+//!
+//! The HSE is the 8 MHz ST-LINK MCO (bypass mode), see `rtic_hse.rs`.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Edge, ExtiPin, Floating, Input},
+    prelude::*,
+    stm32::{FLASH, RCC},
+};
+
+use app::{
+    clock::{start_hse, HSE_TIMEOUT, HSI_MHZ},
+    mco::{ClockOut, Mco2Prescaler},
+    trace::print_banner,
+    trace_init,
+};
+
+// HSE frequency in MHz, the ST-LINK MCO on the Nucleo
+const HSE_MHZ: u32 = 8;
+// OSC_IN driven by an external clock (true), or a crystal (false)
+const BYPASS: bool = true;
+
+// PLL input after the M divider, 2 MHz for the lowest jitter (RM0368 6.3.2)
+const PLL_IN_MHZ: u32 = 2;
+// VCO = 2 MHz * 168 = 336 MHz
+const PLL_N: u32 = 168;
+// SYSCLK = VCO / 4 = 84 MHz
+const PLL_P: u32 = 4;
+const SYSCLK_MHZ: u32 = PLL_IN_MHZ * PLL_N / PLL_P;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PllSource {
+    Hsi,
+    Hse,
+}
+
+impl PllSource {
+    fn mhz(self) -> u32 {
+        match self {
+            PllSource::Hsi => HSI_MHZ,
+            PllSource::Hse => HSE_MHZ,
+        }
+    }
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        rcc: RCC,
+        button: PC13<Input<Floating>>,
+        source: PllSource,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut device = cx.device;
+        let rcc = device.RCC;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        rcc.apb2enr.modify(|_, w| w.syscfgen().enabled());
+
+        // The clocks are set up here, not by the HAL (`constrain`/`freeze`),
+        // which would take the RCC for good.
+        setup_bus(&rcc, &device.FLASH);
+        let source = switch_pll(&rcc, PllSource::Hsi).unwrap();
+        print_banner(SYSCLK_MHZ * 1_000_000);
+
+        // SYSCLK / 4 on MCO2, before `split` takes GPIOC
+        ClockOut::mco2_sysclk(&rcc, &device.GPIOC, Mco2Prescaler::Div4);
+
+        // the user button (B1) on PC13, interrupt on release
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::RISING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        report(&rcc);
+
+        init::LateResources {
+            rcc,
+            button,
+            source,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [rcc, button, source])]
+    fn exti15_10(cx: exti15_10::Context) {
+        cx.resources.button.clear_interrupt_pending_bit();
+
+        let rcc = cx.resources.rcc;
+        let next = match *cx.resources.source {
+            PllSource::Hsi => PllSource::Hse,
+            PllSource::Hse => PllSource::Hsi,
+        };
+
+        match switch_pll(rcc, next) {
+            Ok(source) => *cx.resources.source = source,
+            Err(()) => rprintln!("HSE not ready, staying on {:?}", cx.resources.source),
+        }
+        report(rcc);
+    }
+};
+
+// Flash wait states and bus prescalers for 84 MHz, set once
+//
+// SYSCLK is 84 MHz with either source, so no change is needed when switching.
+fn setup_bus(rcc: &RCC, flash: &FLASH) {
+    // 2 wait states for 64 < HCLK <= 84 MHz at 2.7 - 3.6 V, RM0368 3.4.1 Table 6
+    flash.acr.modify(|_, w| {
+        w.latency()
+            .ws2()
+            .prften()
+            .enabled()
+            .icen()
+            .enabled()
+            .dcen()
+            .enabled()
+    });
+    // APB1 at most 42 MHz, RCC_CFGR PPRE1, RM0368 6.3.3
+    rcc.cfgr.modify(|_, w| w.ppre1().div2());
+}
+
+// Run the PLL from `source`, returns `Err` (and leaves the clocks as they
+// are) if the HSE is requested but does not start
+fn switch_pll(rcc: &RCC, source: PllSource) -> Result<PllSource, ()> {
+    // the HSE is not in use while the PLL runs from the HSI, so it can be
+    // (re)started first, and the switch cancelled before touching SYSCLK
+    if source == PllSource::Hse && !start_hse(rcc, BYPASS, HSE_TIMEOUT) {
+        return Err(());
+    }
+
+    // 1. SYSCLK to the HSI (always on after reset), RCC_CFGR SW/SWS
+    rcc.cr.modify(|_, w| w.hsion().on());
+    while rcc.cr.read().hsirdy().is_not_ready() {}
+    rcc.cfgr.modify(|_, w| w.sw().hsi());
+    while !rcc.cfgr.read().sws().is_hsi() {}
+
+    // 2. PLL off, PLLCFGR may only be written while the PLL is off, RM0368 6.3.2
+    rcc.cr.modify(|_, w| w.pllon().off());
+    while rcc.cr.read().pllrdy().is_ready() {}
+
+    // 3. the new source, M gives the same PLL input (PLL_IN_MHZ) for both
+    let m = (source.mhz() / PLL_IN_MHZ) as u8;
+    rcc.pllcfgr.write(|w| unsafe {
+        let w = match source {
+            PllSource::Hsi => w.pllsrc().hsi(),
+            PllSource::Hse => w.pllsrc().hse(),
+        };
+        // Q = 7, 48 MHz for USB/SDIO
+        w.pllm()
+            .bits(m)
+            .plln()
+            .bits(PLL_N as u16)
+            .pllp()
+            .div4()
+            .pllq()
+            .bits(7)
+    });
+
+    // 4. relock
+    rcc.cr.modify(|_, w| w.pllon().on());
+    while rcc.cr.read().pllrdy().is_not_ready() {}
+
+    // 5. SYSCLK back to the PLL
+    rcc.cfgr.modify(|_, w| w.sw().pll());
+    while !rcc.cfgr.read().sws().is_pll() {}
+
+    // the HSE is no longer used, save the power
+    if source == PllSource::Hsi {
+        rcc.cr.modify(|_, w| w.hseon().off());
+    }
+
+    Ok(source)
+}
+
+// Print the clock configuration as read back from the RCC
+fn report(rcc: &RCC) {
+    let pllcfgr = rcc.pllcfgr.read();
+    let (name, input) = if pllcfgr.pllsrc().is_hse() {
+        ("HSE", HSE_MHZ)
+    } else {
+        ("HSI", HSI_MHZ)
+    };
+    let m = pllcfgr.pllm().bits() as u32;
+    let n = pllcfgr.plln().bits() as u32;
+    // PLLP encodes 2, 4, 6, 8 as 0..=3
+    let p = (pllcfgr.pllp().bits() as u32 + 1) * 2;
+    rprintln!(
+        "PLL from {} ({} MHz / {} * {} / {}), SYSCLK {} MHz, on PLL {}",
+        name,
+        input,
+        m,
+        n,
+        p,
+        input / m * n / p,
+        rcc.cfgr.read().sws().is_pll()
+    );
+}
+
+// Notes:
+//
+// The PLL cannot change its input while running, and SYSCLK must not run
+// from the PLL while it is off. Hence the detour over the HSI, which is
+// always available (the HSE may be missing). During the switch the core
+// runs at 16 MHz for the PLL lock time (~100 us), the flash wait states set
+// for 84 MHz are then more than needed, but never too few.
+//
+// When changing to a higher SYSCLK, the wait states must be increased
+// before the switch, when changing to a lower SYSCLK, decreased after.
+//
+// Peripherals clocked from the buses see the detour, e.g., a USART transfer
+// during the switch is garbled, and the SysTick/CYCCNT run slower for a
+// moment. Switch while the peripherals are idle.
+//
+// On the scope, the HSI driven clock has visibly more jitter and is off by
+// up to +/- 1%, while the HSE (derived from the ST-LINK crystal) is accurate.
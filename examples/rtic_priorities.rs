@@ -0,0 +1,138 @@
+//! rtic_priorities.rs
+//!
+//! Task priorities and preemption
+//!
+//! What it covers:
+//! - assigning priorities to tasks (`#[task(priority = N)]`)
+//! - a high priority (hardware) task preempting a low priority (software) task
+//! - accessing a resource shared between priorities using `lock`
+//!
+//! > cargo run --example rtic_priorities
+//!
+//! Connect a scope (or logic analyzer) to PA6 (CN10-13, low priority `work`)
+//! and PA7 (CN10-15, high priority `tim2`). The short `tim2` pulses appear
+//! while `work` is high, i.e., `work` is preempted.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpioa::{PA6, PA7},
+        Output, PushPull,
+    },
+    prelude::*,
+    stm32::TIM2,
+    timer::{Event, Timer},
+};
+
+use app::{schedule::try_reschedule, trace::print_banner, trace_init};
+
+// `work` period in clock cycles, 1 s at 16 MHz
+const PERIOD: u32 = 16_000_000;
+// `work` busy time in clock cycles, ~100 ms at 16 MHz
+const WORK: u32 = 1_600_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        timer: Timer<TIM2>,
+        work_pin: PA6<Output<PushPull>>,
+        tick_pin: PA7<Output<PushPull>>,
+        // number of `tim2` ticks, shared between priorities 1 and 2
+        #[init(0)]
+        ticks: u32,
+    }
+
+    #[init(schedule = [work])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let work_pin = gpioa.pa6.into_push_pull_output();
+        let tick_pin = gpioa.pa7.into_push_pull_output();
+
+        // TIM2 update interrupt at 100 Hz
+        let mut timer = Timer::tim2(device.TIM2, 100.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        cx.schedule.work(cx.start + PERIOD.cycles()).unwrap();
+
+        init::LateResources {
+            timer,
+            work_pin,
+            tick_pin,
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // low priority, long running
+    #[task(priority = 1, resources = [work_pin, ticks], schedule = [work])]
+    fn work(mut cx: work::Context) {
+        // `ticks` is shared with `tim2` (higher priority), hence lock
+        let before = cx.resources.ticks.lock(|ticks| *ticks);
+
+        cx.resources.work_pin.set_high().ok();
+        // a lengthy computation, `tim2` preempts us here
+        asm::delay(WORK);
+        cx.resources.work_pin.set_low().ok();
+
+        let after = cx.resources.ticks.lock(|ticks| *ticks);
+        if after != before {
+            rprintln!("work preempted {} times", after.wrapping_sub(before));
+        } else {
+            rprintln!("work not preempted");
+        }
+
+        try_reschedule(cx.schedule.work(cx.scheduled + PERIOD.cycles()), "work");
+    }
+
+    // high priority, short
+    #[task(binds = TIM2, priority = 2, resources = [timer, tick_pin, ticks])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        cx.resources.tick_pin.set_high().ok();
+        // highest priority accessing `ticks`, no lock needed
+        *cx.resources.ticks += 1;
+        cx.resources.tick_pin.set_low().ok();
+    }
+
+    // the dispatcher for `work` runs at priority 1
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// RTIC implements the Stack Resource Policy (SRP). `lock` raises the system
+// priority (BASEPRI) to the ceiling of the resource (here 2), so `tim2` is
+// blocked for the (short) duration of the critical section, never longer.
+//
+// Try setting `tim2` to priority 1. Both tasks then run at the same priority,
+// `tim2` is delayed until `work` completes, and no preemption is reported.
+// (RTIC then also grants `work` lock-free access to `ticks`.)
@@ -0,0 +1,107 @@
+//! rtic_probe.rs
+//!
+//! Measuring execution time on a scope
+//!
+//! What it covers:
+//! - bracketing code with a GPIO marker (`app::probe::Probe`)
+//! - measuring the execution time of a task on a scope
+//!
+//! > cargo run --example rtic_probe
+//!
+//! Connect a scope to PA7 (CN10-15) and PA5 (CN10-11, the LED). Trigger on the
+//! rising edge of PA7, the high time is the execution time of the work in
+//! `toggle`, and the LED edge shows where in the task the LED is toggled.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{
+        gpioa::{PA5, PA7},
+        Output, PushPull, Speed,
+    },
+    prelude::*,
+};
+
+use app::{led::Led, probe::Probe, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// toggle period in clock cycles, ~100 ms at 84 MHz
+const OFFSET: u32 = 8_400_000;
+// simulated work in clock cycles, ~10 us at 84 MHz
+const WORK: u32 = 840;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+        probe: Probe<PA7<Output<PushPull>>>,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+        // sharp edges for the measurement, see `rtic_gpio_speed.rs`
+        let probe = Probe::new(gpioa.pa7.into_push_pull_output().set_speed(Speed::VeryHigh));
+
+        cx.schedule.toggle(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { led, probe }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [led, probe], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        let led = cx.resources.led;
+        // PA7 high during the work, expect ~10 us on the scope
+        cx.resources.probe.measure(|| {
+            asm::delay(WORK);
+            led.toggle().ok();
+        });
+
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + OFFSET.cycles()), "toggle") {
+            // the task stopped, leave the LED off
+            led.off().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The measured width includes the closure, but not the task dispatch, nor
+// the rescheduling. To see the complete task, move `measure` to wrap the
+// whole task body, or `pulse` first thing in the task, and compare the
+// distance between pulses with OFFSET.
+//
+// Compared to counting cycles (`rtic_bench.rs`), the scope shows the timing
+// of every run (jitter, preemption by higher priority tasks) without
+// disturbing it by printing.
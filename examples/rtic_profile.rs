@@ -0,0 +1,174 @@
+//! rtic_profile.rs
+//!
+//! Profiling task execution times
+//!
+//! What it covers:
+//! - bracketing task bodies with CYCCNT reads (`profile::Profile::record`)
+//! - keeping the last records in a `heapless::HistoryBuffer`
+//! - dumping the records from `idle` on a button press, printing outside the lock
+//!
+//! > cargo run --example rtic_profile --release
+//!
+//! Press the user button (B1) to dump the last `Depth` (32) records.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::{asm, peripheral::DWT};
+use heapless::consts::U32;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioc::PC13, Floating, Input},
+    prelude::*,
+};
+
+use app::{
+    delay::ms_to_cycles,
+    profile::{Profile, TaskId},
+    rand::XorShift32,
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+
+// the task ids, index into TASKS
+const SAMPLE: TaskId = 0;
+const PROCESS: TaskId = 1;
+const TASKS: [&str; 2] = ["sample", "process"];
+
+// the number of records kept
+type Depth = U32;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        profile: Profile<Depth>,
+        button: PC13<Input<Floating>>,
+        rand: XorShift32,
+    }
+
+    #[init(schedule = [sample, process])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT), also used for the profiling
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // the user button (B1) on PC13, the Nucleo has an external pull-up
+        let gpioc = device.GPIOC.split();
+        let button = gpioc.pc13.into_floating_input();
+
+        cx.schedule.sample(cx.start).unwrap();
+        cx.schedule.process(cx.start).unwrap();
+
+        init::LateResources {
+            profile: Profile::new(),
+            button,
+            rand: XorShift32::new(1),
+        }
+    }
+
+    // poll the button, dump on press
+    #[idle(resources = [profile, button])]
+    fn idle(mut cx: idle::Context) -> ! {
+        rprintln!("idle, press B1 to dump");
+        let mut was_pressed = false;
+        loop {
+            // active low
+            let pressed = cx.resources.button.is_low().unwrap_or(false);
+            if pressed && !was_pressed {
+                // copy out under the lock, print afterwards (printing is slow)
+                let (count, records) = cx
+                    .resources
+                    .profile
+                    .lock(|profile| (profile.count(), profile.in_order()));
+
+                rprintln!("last {} of {} records", records.len(), count);
+                for record in records.iter() {
+                    rprintln!(
+                        "{:6} {:8} {:6} cycles",
+                        record.seq,
+                        TASKS[record.task as usize],
+                        record.cycles
+                    );
+                }
+            }
+            was_pressed = pressed;
+        }
+    }
+
+    // high priority, short and varying work every 10 ms
+    #[task(priority = 2, resources = [profile, rand], schedule = [sample])]
+    fn sample(cx: sample::Context) {
+        let start = DWT::get_cycle_count();
+
+        asm::delay(500 + cx.resources.rand.below(500));
+
+        // the highest priority using `profile`, no lock needed
+        cx.resources.profile.record(SAMPLE, start);
+
+        let offset = ms_to_cycles(10, SYSCLK_HZ);
+        try_reschedule(cx.schedule.sample(cx.scheduled + offset.cycles()), "sample");
+    }
+
+    // low priority, longer work every 100 ms, preempted by `sample`
+    #[task(priority = 1, resources = [profile], schedule = [process])]
+    fn process(mut cx: process::Context) {
+        let start = DWT::get_cycle_count();
+
+        asm::delay(50_000);
+
+        cx.resources
+            .profile
+            .lock(|profile| profile.record(PROCESS, start));
+
+        let offset = ms_to_cycles(100, SYSCLK_HZ);
+        try_reschedule(
+            cx.schedule.process(cx.scheduled + offset.cycles()),
+            "process",
+        );
+    }
+
+    extern "C" {
+        fn EXTI0();
+        fn EXTI1();
+    }
+};
+
+// Notes:
+//
+// A record is 12 bytes, `Depth` (32) records take 384 bytes of RAM. The
+// `HistoryBuffer` overwrites the oldest record, so recording never fails,
+// and the dump shows the most recent activity, ordered by the sequence
+// number.
+//
+// The measured time is wall clock time from the start of the body to the
+// record, including any preemption. A `process` run preempted by `sample`
+// shows up as ~50_000 cycles plus the `sample` runs in between. The records
+// are in order of completion, so the `sample` records right before a
+// `process` record with a higher time are the ones that preempted it.
+//
+// The overhead is two CYCCNT reads and the record (a few tens of cycles in
+// `--release`), plus the lock for tasks below the highest priority using
+// `profile`. The task dispatch (from the interrupt to the body) is not
+// included, see `rtic_latency.rs`.
+//
+// `idle` holds the lock only while copying the records, so the tasks are
+// blocked for the copy (a few hundred cycles), not for the printing. A
+// press while dumping is missed, the dump is short enough in practice.
+//
+// The button is not debounced, a bouncing press may dump twice.
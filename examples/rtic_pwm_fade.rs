@@ -0,0 +1,125 @@
+//! rtic_pwm_fade.rs
+//!
+//! PWM LED fade
+//!
+//! What it covers:
+//! - setting up a timer channel in PWM mode using the HAL
+//! - changing the duty cycle from a scheduled task
+//!
+//! > cargo run --example rtic_pwm_fade
+//!
+//! Connect an LED (with a series resistor) between PA6 (CN10-13) and GND.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    pwm::{self, PwmChannels, C1},
+    stm32::TIM3,
+};
+
+use app::{schedule::try_reschedule, trace::print_banner, trace_init};
+
+// number of steps from off to fully on
+const STEPS: u16 = 100;
+// time between steps, in clock cycles
+const OFFSET: u32 = 100_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        pwm: PwmChannels<TIM3, C1>,
+    }
+
+    #[init(schedule = [fade])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // TIM3 channel 1 on PA6, alternate function AF2
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa6.into_alternate_af2();
+
+        // 1 kHz PWM frequency, see notes below
+        let mut pwm = pwm::tim3(device.TIM3, pin, clocks, 1.khz());
+        rprintln!("max_duty {}", pwm.get_max_duty());
+        pwm.set_duty(0);
+        pwm.enable();
+
+        cx.schedule.fade(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { pwm }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [pwm], schedule = [fade])]
+    fn fade(cx: fade::Context) {
+        static mut LEVEL: u16 = 0;
+        static mut UP: bool = true;
+
+        // reverse direction at the endpoints
+        if *LEVEL == STEPS {
+            *UP = false;
+        } else if *LEVEL == 0 {
+            *UP = true;
+        }
+
+        if *UP {
+            *LEVEL += 1;
+        } else {
+            *LEVEL -= 1;
+        }
+
+        let pwm = cx.resources.pwm;
+        let max_duty = pwm.get_max_duty() as u32;
+        pwm.set_duty((max_duty * *LEVEL as u32 / STEPS as u32) as u16);
+
+        if !try_reschedule(cx.schedule.fade(cx.scheduled + OFFSET.cycles()), "fade") {
+            // the task stopped, leave the LED off
+            pwm.set_duty(0);
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// TIM3 is on APB1, and is clocked by PCLK1 (x2 if the APB1 prescaler is not 1).
+// The counter is clocked by TIM_CLK / (PSC + 1), and counts from 0 to ARR,
+// thus the PWM frequency is:
+//
+//   f_pwm = TIM_CLK / ((PSC + 1) * (ARR + 1))
+//
+// The HAL solves PSC/ARR for the requested frequency. At 16 MHz and 1 kHz,
+// 16_000 timer ticks per period fits in 16 bits, so PSC = 0 and ARR ~ 16_000.
+// The duty is set by the compare register (CCR1) in the range 0..=ARR,
+// which is what `get_max_duty` reports. A lower PWM frequency gives a finer
+// duty resolution (but may cause visible flicker below ~100 Hz).
+//
+// A full fade (up and down) takes 2 * STEPS * OFFSET cycles, 1.25 s at 16 MHz.
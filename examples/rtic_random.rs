@@ -0,0 +1,85 @@
+//! rtic_random.rs
+//!
+//! Pseudo random numbers seeded from ADC noise
+//!
+//! What it covers:
+//! - gathering a (weak) seed from the noise of a floating ADC input
+//! - a xorshift pseudo random number generator (`app::rand`)
+//!
+//! > cargo run --example rtic_random
+//!
+//! Leave PA1 (CN8-2, A1) unconnected.
+//!
+//! IMPORTANT: This is NOT cryptographically secure. The seed has (much) less
+//! than 32 bits of entropy, and the generator is predictable from its output.
+//! Fine for randomized blink patterns and demos, never for security.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc,
+    },
+    gpio::{gpioa::PA1, Analog},
+    prelude::*,
+    stm32::ADC1,
+};
+
+use app::{
+    rand::{seed_from_lsbs, XorShift32},
+    trace::print_banner,
+    trace_init,
+};
+
+// samples folded into the seed, some LSBs are correlated, so take plenty
+const SEED_SAMPLES: usize = 128;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa1.into_analog();
+
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let mut adc = Adc::adc1(device.ADC1, true, config);
+
+        let seed = weak_seed(&mut adc, &pin);
+        rprintln!("seed 0x{:08x}", seed);
+
+        let mut rng = XorShift32::new(seed);
+        for _ in 0..5 {
+            rprintln!("random {:>10}, dice {}", rng.next_u32(), 1 + rng.below(6));
+        }
+    }
+};
+
+/// A seed from the LSBs of repeated conversions of a floating input
+fn weak_seed(adc: &mut Adc<ADC1>, pin: &PA1<Analog>) -> u32 {
+    // the shortest sample time, the least filtering of the noise
+    seed_from_lsbs((0..SEED_SAMPLES).map(|_| adc.convert(pin, SampleTime::Cycles_3)))
+}
+
+// Notes:
+//
+// Reset a few times, the seed should differ between runs. If it doesn't (or
+// only a few bits change), the input is not floating (or is shielded), and the
+// LSBs carry little noise.
+//
+// The internal temperature sensor or VREFINT channels can be used instead of
+// a pin, with a similar (low) quality of the noise.
+//
+// The F401/F411 have no true random number generator (RNG peripheral),
+// it is found on, e.g., the F405/F407/F446.
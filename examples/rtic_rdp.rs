@@ -0,0 +1,89 @@
+//! rtic_rdp.rs
+//!
+//! Read-out protection (RDP) level
+//!
+//! What it covers:
+//! - reading the RDP level from the option bytes (`rdp::read_protection_level`)
+//! - what the levels mean, before protecting a deployed firmware
+//!
+//! > cargo run --example rtic_rdp
+//!
+//! Read only, the example never changes the option bytes.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::prelude::*;
+
+use app::{
+    rdp::{read_protection_level, RdpLevel},
+    trace::print_banner,
+    trace_init,
+};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let device = cx.device;
+
+        // the RDP option byte, FLASH_OPTCR, RM0368 3.8.6
+        let level = read_protection_level(&device.FLASH);
+        rprintln!(
+            "RDP {:?} (0x{:02x})",
+            level,
+            (device.FLASH.optcr.read().bits() >> 8) as u8
+        );
+
+        match level {
+            RdpLevel::Level0 => {
+                rprintln!("no protection, the flash can be read by the debugger")
+            }
+            RdpLevel::Level1 => {
+                rprintln!("flash read protected, going back to Level 0 mass erases the flash")
+            }
+            RdpLevel::Level2 => rprintln!("permanently protected"),
+        }
+
+        rprintln!("WARNING: Level 2 is irreversible, the debug port is disabled for good,");
+        rprintln!("WARNING: the board can never be re-flashed or debugged again");
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+    }
+};
+
+// Notes:
+//
+// The RDP option byte (RM0368 3.6.3):
+//
+// - 0xAA, Level 0, no protection (as shipped)
+// - 0xCC, Level 2, chip protection
+// - any other value, Level 1, read protection
+//
+// Level 1 blocks reading the flash while a debugger is connected, or after
+// booting from RAM or system memory (the bootloader). The firmware itself
+// runs as usual. Going back to Level 0 is allowed, but mass erases the flash
+// (the firmware is gone, not read out). Level 1 is the usual choice for a
+// deployed product, and it can be undone during development, e.g., with
+// STM32CubeProgrammer (option bytes, RDP 0xAA).
+//
+// Level 2 also disables the debug port (SWD) and the boot from RAM or
+// system memory, and locks the option bytes. There is no way back, not
+// even a mass erase. A firmware update is then only possible through a
+// bootloader of your own, in flash.
+//
+// The level is written like the BOR level (see `rtic_brownout.rs`): unlock
+// FLASH_OPTCR with the OPTKEYR sequence, write RDP, set OPTSTRT and wait for
+// BSY to clear. This is left out here on purpose, a typo (e.g., 0xCC) makes
+// the board unusable. Use the vendor tools, where setting Level 2 requires
+// an explicit confirmation.
+//
+// Under Level 1 with the debugger connected, flash reads from the debugger
+// give a bus error, `cargo run` (probe-run/openocd) then fails to flash or
+// verify, revert to Level 0 first.
@@ -0,0 +1,154 @@
+//! rtic_round_robin.rs
+//!
+//! Cooperative round-robin, software tasks at equal priority
+//!
+//! What it covers:
+//! - three "threads", each a software task advancing its own state machine
+//! - yielding by re-spawning, the ready queue gives round-robin order
+//! - run-to-completion, equal priority tasks never preempt each other
+//! - sharing a resource at equal priority without `lock`
+//!
+//! > cargo run --example rtic_round_robin
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+
+use app::trace_init;
+
+// number of ticks (thread steps) before stopping
+const TICKS: u32 = 24;
+// work per step, in clock cycles
+const WORK: u32 = 100_000;
+
+// the state machine of thread `b`
+#[derive(Clone, Copy, Debug)]
+enum Phase {
+    Ping,
+    Pong,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // the global tick, one per thread step
+        #[init(0)]
+        tick: u32,
+        // set while a thread is running, to check that no thread interrupts another
+        #[init(false)]
+        running: bool,
+    }
+
+    #[init(spawn = [a, b, c])]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        // the threads run in spawn order, then round-robin
+        cx.spawn.a().unwrap();
+        cx.spawn.b().unwrap();
+        cx.spawn.c().unwrap();
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        // only reached when no thread is ready, i.e., all have stopped
+        rprintln!("idle, all threads done");
+        loop {
+            continue;
+        }
+    }
+
+    // counts up
+    #[task(resources = [tick, running], spawn = [a])]
+    fn a(cx: a::Context) {
+        static mut COUNT: u32 = 0;
+
+        if let Some(tick) = enter(cx.resources.tick, cx.resources.running) {
+            *COUNT += 1;
+            rprintln!("tick {:2}: a, count {}", tick, COUNT);
+            leave(cx.resources.running);
+            // yield, go to the back of the ready queue
+            cx.spawn.a().unwrap();
+        }
+    }
+
+    // alternates between two states
+    #[task(resources = [tick, running], spawn = [b])]
+    fn b(cx: b::Context) {
+        static mut PHASE: Phase = Phase::Ping;
+
+        if let Some(tick) = enter(cx.resources.tick, cx.resources.running) {
+            rprintln!("tick {:2}: b, {:?}", tick, PHASE);
+            *PHASE = match *PHASE {
+                Phase::Ping => Phase::Pong,
+                Phase::Pong => Phase::Ping,
+            };
+            leave(cx.resources.running);
+            cx.spawn.b().unwrap();
+        }
+    }
+
+    // a countdown, stops on its own after 3 steps
+    #[task(resources = [tick, running], spawn = [c])]
+    fn c(cx: c::Context) {
+        static mut LEFT: u32 = 3;
+
+        if let Some(tick) = enter(cx.resources.tick, cx.resources.running) {
+            *LEFT -= 1;
+            rprintln!("tick {:2}: c, {} left", tick, LEFT);
+            leave(cx.resources.running);
+            if *LEFT > 0 {
+                cx.spawn.c().unwrap();
+            }
+        }
+    }
+
+    // all threads at the same priority, one dispatcher
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Start a thread step, `None` when the run is over (the thread stops).
+//
+// The resources are accessed directly, tasks at the same priority cannot
+// preempt each other, so there is no race (and RTIC requires no `lock`).
+fn enter(tick: &mut u32, running: &mut bool) -> Option<u32> {
+    if *tick >= TICKS {
+        return None;
+    }
+    assert!(!*running, "a thread was preempted");
+    *running = true;
+    *tick += 1;
+    // the step takes a while, time for an interrupt to preempt (it doesn't)
+    asm::delay(WORK);
+    Some(*tick)
+}
+
+fn leave(running: &mut bool) {
+    *running = false;
+}
+
+// Notes:
+//
+// The dispatcher (EXTI0) runs the ready tasks of a priority level one at a
+// time, in FIFO order, each to completion. A task that re-spawns itself goes
+// to the back of the queue, behind the other ready threads, so they take turns:
+//
+//   a b c a b c a b c a b a b a b ...
+//
+// until `c` stops spawning itself (then `a b a b`), and all stop at TICKS.
+// Each task needs capacity for one message (the default), it is only ever
+// spawned by itself, after its own message has been taken out of the queue.
+//
+// Unlike threads in an RTOS, a "thread" here must return to yield, its
+// state is kept in task local `static mut` variables (or resources) between
+// steps. A thread that loops forever blocks the others (at its priority),
+// there is no time slicing. Compare with `rtic_priorities.rs`, where higher
+// priority tasks preempt lower priority ones.
+//
+// `idle` runs only once all threads have stopped, as the dispatcher keeps
+// the CPU busy as long as any thread is ready.
@@ -0,0 +1,203 @@
+//! rtic_rtc.rs
+//!
+//! Real Time Clock
+//!
+//! What it covers:
+//! - enabling the LSE (32.768 kHz) oscillator, with fall back to the LSI
+//! - keeping an already selected RTC clock source (RTCSEL is written once)
+//! - unlocking the backup domain (PWR DBP) and the RTC write protection
+//! - setting and reading the RTC time (BCD encoded) registers
+//!
+//! > cargo run --example rtic_rtc
+//!
+//! The Nucleo has a 32.768 kHz crystal (X2) connected to the LSE (PC14/PC15).
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::{self, rcc::bdcr::RTCSEL_A, RCC},
+};
+
+use app::{schedule::try_reschedule, trace_init};
+
+// print period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+// LSE start-up timeout in clock cycles, 2 s at 16 MHz
+const LSE_TIMEOUT: u32 = 32_000_000;
+
+// initial time, 12:34:56
+const HOURS: u8 = 12;
+const MINUTES: u8 = 34;
+const SECONDS: u8 = 56;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        RTC: stm32::RTC,
+    }
+
+    #[init(schedule = [tick])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = &device.RCC;
+
+        // power on PWR, RM0368 6.3.13
+        rcc.apb1enr.modify(|_, w| w.pwren().enabled());
+        // disable backup domain write protection, PWR_CR DBP, RM0368 5.4.1
+        device.PWR.cr.modify(|_, w| w.dbp().set_bit());
+
+        // RTCSEL is written once, and kept until a backup domain reset,
+        // RM0368 6.3.20. After a system reset (or `backup::enable_rtc_clock`
+        // in another app) the source is already selected, keep it.
+        let source = match rcc.bdcr.read().rtcsel().variant() {
+            RTCSEL_A::NOCLOCK => {
+                let source = if start_lse(rcc) {
+                    RTCSEL_A::LSE
+                } else {
+                    rprintln!("LSE failed to start");
+                    rcc.bdcr.modify(|_, w| w.lseon().off());
+                    RTCSEL_A::LSI
+                };
+                rcc.bdcr.modify(|_, w| w.rtcsel().variant(source));
+                source
+            }
+            selected => {
+                rprintln!("RTC clock already selected (power cycle to change)");
+                selected
+            }
+        };
+        if source == RTCSEL_A::LSI {
+            // LSION is cleared by a system reset, RM0368 6.3.21
+            rcc.csr.modify(|_, w| w.lsion().on());
+            while rcc.csr.read().lsirdy().is_not_ready() {}
+        }
+        rcc.bdcr.modify(|_, w| w.rtcen().enabled());
+
+        // ck_spre = RTCCLK / ((PREDIV_A + 1) * (PREDIV_S + 1)) = 1 Hz
+        // LSE 32768 Hz: 128 * 256, LSI ~32000 Hz: 128 * 250
+        let prediv_s = match source {
+            RTCSEL_A::LSE => {
+                rprintln!("RTC clock LSE");
+                255
+            }
+            RTCSEL_A::LSI => {
+                rprintln!("RTC clock LSI (reduced accuracy, +/- 5%)");
+                249
+            }
+            _ => panic!("RTC clock HSE not supported, power cycle to reset the backup domain"),
+        };
+
+        let rtc = device.RTC;
+
+        // disable RTC write protection, RM0368 17.3.5
+        rtc.wpr.write(|w| w.key().bits(0xCA));
+        rtc.wpr.write(|w| w.key().bits(0x53));
+
+        // enter initialization mode, RM0368 17.3.5
+        rtc.isr.modify(|_, w| w.init().set_bit());
+        while rtc.isr.read().initf().bit_is_clear() {}
+
+        // two separate writes, PREDIV_S first, then PREDIV_A, RM0368 17.3.5
+        rtc.prer.write(|w| w.prediv_s().bits(prediv_s));
+        rtc.prer.modify(|_, w| w.prediv_a().bits(127));
+
+        // set the time (24 hour format, BCD), RM0368 17.6.1
+        rtc.tr.write(|w| {
+            w.pm()
+                .clear_bit()
+                .ht()
+                .bits(HOURS / 10)
+                .hu()
+                .bits(HOURS % 10)
+                .mnt()
+                .bits(MINUTES / 10)
+                .mnu()
+                .bits(MINUTES % 10)
+                .st()
+                .bits(SECONDS / 10)
+                .su()
+                .bits(SECONDS % 10)
+        });
+
+        // exit initialization mode, the counter starts
+        rtc.isr.modify(|_, w| w.init().clear_bit());
+
+        // enable RTC write protection
+        rtc.wpr.write(|w| w.key().bits(0xFF));
+
+        cx.schedule.tick(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { RTC: rtc }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [RTC], schedule = [tick])]
+    fn tick(cx: tick::Context) {
+        let rtc = cx.resources.RTC;
+
+        // wait for the shadow registers to be synchronized, RM0368 17.3.6
+        while rtc.isr.read().rsf().bit_is_clear() {}
+
+        // reading TR locks the shadow DR until DR is read
+        let tr = rtc.tr.read();
+        let _dr = rtc.dr.read();
+
+        rprintln!(
+            "{}{}:{}{}:{}{}",
+            tr.ht().bits(),
+            tr.hu().bits(),
+            tr.mnt().bits(),
+            tr.mnu().bits(),
+            tr.st().bits(),
+            tr.su().bits()
+        );
+
+        try_reschedule(cx.schedule.tick(cx.scheduled + OFFSET.cycles()), "tick");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Start the LSE, RM0368 6.3.20, `false` on timeout
+fn start_lse(rcc: &RCC) -> bool {
+    rcc.bdcr.modify(|_, w| w.lseon().on());
+    let start = DWT::get_cycle_count();
+    while DWT::get_cycle_count().wrapping_sub(start) < LSE_TIMEOUT {
+        if rcc.bdcr.read().lserdy().is_ready() {
+            return true;
+        }
+    }
+    false
+}
+
+// Notes:
+//
+// The backup domain (LSE, RTC, backup registers) survives a system reset.
+// A power cycle (or setting BDRST) is needed to change the RTC clock source.
+//
+// The LSI is an RC oscillator with a wide tolerance (17..47 kHz on the F401),
+// so the RTC may drift by minutes per hour when driven by the LSI.
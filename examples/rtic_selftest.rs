@@ -0,0 +1,84 @@
+//! rtic_selftest.rs
+//!
+//! Power on self test
+//!
+//! What it covers:
+//! - validating the clock setup and RCC state at startup (`app::selftest`)
+//! - halting with an error message if the test fails
+//! - a visual POST (the LED blinks three times)
+//!
+//! > cargo run --example rtic_selftest
+//!
+//! Change the `sysclk`/`pclk1` below (e.g., `pclk1(84)`) to see a failing test.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    clock::SysClkConfig,
+    led::Led,
+    selftest::{blink, self_test},
+    trace::print_banner,
+    trace_init,
+};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        // setup LED, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+        let mut led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        let clocks = SysClkConfig::new().sysclk(84).pclk1(42).apply(device.RCC);
+        print_banner(clocks.sysclk().0);
+
+        if let Err(err) = self_test(&clocks) {
+            rprintln!("self test failed: {}", err);
+            // stop here, with the LED on, the debugger halts at the breakpoint
+            led.on().ok();
+            loop {
+                asm::bkpt();
+            }
+        }
+        rprintln!("self test passed");
+        blink(&mut led, &clocks, 3).ok();
+
+        init::LateResources { led }
+    }
+
+    #[idle(resources = [led])]
+    fn idle(cx: idle::Context) -> ! {
+        rprintln!("idle");
+        // steady on, the board is alive
+        cx.resources.led.on().ok();
+        loop {
+            continue;
+        }
+    }
+};
+
+// Notes:
+//
+// The self test runs in `init` with interrupts disabled, so no task can
+// run before it has passed.
+//
+// `asm::bkpt` without a debugger attached causes a HardFault, which is fine
+// here, as the application is stopped either way.
@@ -0,0 +1,121 @@
+//! rtic_serial.rs
+//!
+//! Serial output
+//!
+//! What it covers:
+//! - setting up USART2 using the HAL serial abstraction
+//! - formatted output using `core::fmt::Write`
+//!
+//! > cargo run --example rtic_serial
+//!
+//! On the Nucleo, USART2 (PA2/PA3) is connected to the stlink virtual COM port,
+//! e.g., use `moserial` or `screen /dev/ttyACM0 115200` on the host.
+
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    serial::{config::Config, Serial, Tx},
+    stm32::{self, USART2},
+};
+
+use app::{
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+    usart::{baud_rate, usart_div},
+};
+
+const OFFSET: u32 = 10_000_000;
+const BAUD: u32 = 115_200;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        TX: Tx<USART2>,
+    }
+
+    #[init(schedule = [hello])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+
+        // 16 MHz (default, all clocks)
+        //
+        // The baud rate divider (USART_BRR) is computed by the HAL from the
+        // PCLK1 frequency given by `clocks`, USART2 is on APB1.
+        // Always pass the `clocks` returned by `freeze()` to `Serial`,
+        // then the baud rate stays correct when SYSCLK/PCLK1 is changed.
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let tx = gpioa.pa2.into_alternate_af7();
+        let rx = gpioa.pa3.into_alternate_af7();
+
+        let serial = Serial::usart2(
+            device.USART2,
+            (tx, rx),
+            Config::default().baudrate(BAUD.bps()),
+            clocks,
+        )
+        .unwrap();
+
+        // check the BRR computed by the HAL against the active PCLK1
+        // SAFETY: read only access
+        let brr = unsafe { (*stm32::USART2::ptr()).brr.read().bits() } as u16;
+        let expected = usart_div(clocks.pclk1().0, BAUD);
+        rprintln!(
+            "pclk1 {} Hz, BRR 0x{:04x} (expected 0x{:04x}), {} baud",
+            clocks.pclk1().0,
+            brr,
+            expected,
+            baud_rate(clocks.pclk1().0, brr)
+        );
+        assert_eq!(brr, expected, "USART2 BRR does not match PCLK1");
+
+        // we only transmit in this example
+        let (tx, _rx) = serial.split();
+
+        cx.schedule.hello(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { TX: tx }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [TX], schedule = [hello])]
+    fn hello(cx: hello::Context) {
+        static mut COUNTER: u32 = 0;
+
+        writeln!(cx.resources.TX, "hello {}\r", COUNTER).unwrap();
+        rprintln!("hello {}", COUNTER);
+
+        *COUNTER += 1;
+        try_reschedule(cx.schedule.hello(cx.scheduled + OFFSET.cycles()), "hello");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
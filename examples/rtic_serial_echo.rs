@@ -0,0 +1,135 @@
+//! rtic_serial_echo.rs
+//!
+//! Interrupt driven serial echo
+//!
+//! What it covers:
+//! - receiving on the RXNE (receive buffer not empty) interrupt
+//! - transmitting on the TXE (transmit buffer empty) interrupt
+//! - buffering outgoing data in a ring buffer (`heapless::spsc::Queue`)
+//! - handling receive errors without panicking
+//!
+//! > cargo run --example rtic_serial_echo
+//!
+//! Connect a terminal to the stlink virtual COM port at 115200 baud.
+
+#![no_main]
+#![no_std]
+
+use heapless::{consts::U16, spsc::Queue};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    nb,
+    prelude::*,
+    serial::{config::Config, Error, Event, Rx, Serial, Tx},
+    stm32::USART2,
+};
+
+use app::{trace::print_banner, trace_init};
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        TX: Tx<USART2>,
+        RX: Rx<USART2>,
+        BUFFER: Queue<u8, U16>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+
+        // 16 MHz (default, all clocks)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let tx = gpioa.pa2.into_alternate_af7();
+        let rx = gpioa.pa3.into_alternate_af7();
+
+        let mut serial = Serial::usart2(
+            device.USART2,
+            (tx, rx),
+            Config::default().baudrate(115_200.bps()),
+            clocks,
+        )
+        .unwrap();
+
+        // generate interrupt on Rxne
+        // (Txe is enabled on demand, when there is data to send)
+        serial.listen(Event::Rxne);
+
+        // Separate out the sender and receiver of the serial port
+        let (tx, rx) = serial.split();
+
+        init::LateResources {
+            TX: tx,
+            RX: rx,
+            BUFFER: Queue::new(),
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // RXNE and TXE share the USART2 interrupt vector
+    #[task(binds = USART2, resources = [TX, RX, BUFFER])]
+    fn usart2(cx: usart2::Context) {
+        let buffer = cx.resources.BUFFER;
+
+        // receive side
+        match cx.resources.RX.read() {
+            Ok(byte) => {
+                rprintln!("received {}", byte);
+                match buffer.enqueue(byte) {
+                    Ok(_) => txe_interrupt(true),
+                    Err(_) => rprintln!("warning: buffer full, dropped {}", byte),
+                }
+            }
+            // `read` has already cleared the error flag (by reading SR followed by DR)
+            Err(nb::Error::Other(Error::Overrun)) => rprintln!("warning: overrun"),
+            Err(nb::Error::Other(err)) => rprintln!("warning: {:?}", err),
+            // not a receive interrupt
+            Err(nb::Error::WouldBlock) => {}
+        }
+
+        // transmit side
+        if let Some(&byte) = buffer.peek() {
+            // only dequeue if the byte was accepted (TXE set)
+            if cx.resources.TX.write(byte).is_ok() {
+                buffer.dequeue();
+            }
+        }
+        if buffer.is_empty() {
+            // nothing more to send, stop the TXE interrupt from firing
+            txe_interrupt(false);
+        }
+    }
+};
+
+// The `Tx` half does not provide `listen`/`unlisten` in this HAL version,
+// so we set/clear TXEIE directly, RM0368 19.6.4.
+fn txe_interrupt(enable: bool) {
+    // NOTE(unsafe) read-modify-write of CR1 only from the USART2 task
+    unsafe { (*USART2::ptr()).cr1.modify(|_, w| w.txeie().bit(enable)) };
+}
+
+// Notes:
+//
+// The TXE flag is set whenever the transmit data register is empty, thus
+// the TXE interrupt must be disabled when there is nothing to send, else
+// the handler would be re-entered immediately (and forever).
+//
+// An overrun occurs if a new byte arrives before the previous was read, i.e.,
+// if the USART2 interrupt is blocked for more than a character time
+// (~87 us at 115200 baud). The previous byte is kept, the new one is lost.
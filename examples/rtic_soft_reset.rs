@@ -0,0 +1,111 @@
+//! rtic_soft_reset.rs
+//!
+//! Software reset, and counting resets in a backup register
+//!
+//! What it covers:
+//! - resetting the MCU from code (`system_reset`, AIRCR SYSRESETREQ)
+//! - reading (and clearing) the reset cause in RCC_CSR
+//! - a boot counter in a backup register (RTC_BKP0R), surviving the reset
+//!
+//! > cargo run --example rtic_soft_reset
+//!
+//! Prints a countdown, then resets. Each boot prints the cause and the count.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::prelude::*;
+
+use app::{
+    backup::{bkp_read, bkp_write, enable_backup_access},
+    reset::{system_reset, take_reset_cause, ResetCause},
+    schedule::try_reschedule,
+    trace::print_banner,
+    trace_init,
+};
+
+// countdown steps before the reset
+const COUNTDOWN: u32 = 5;
+// countdown period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    #[init(schedule = [countdown])]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // read and clear the reset flags, RCC_CSR, RM0368 6.3.21
+        let cause = take_reset_cause(&device.RCC);
+
+        // RTC_BKP0R holds the count
+        enable_backup_access(&device.RCC, &device.PWR);
+        let boots = match cause {
+            // the backup domain is reset with VDD (VBAT is tied to VDD on the Nucleo)
+            ResetCause::PowerOn => 0,
+            _ => bkp_read(&device.RTC, 0),
+        } + 1;
+        bkp_write(&device.RTC, 0, boots);
+
+        rprintln!("reset cause {:?}, boot {}", cause, boots);
+
+        let clocks = device.RCC.constrain().cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        cx.schedule
+            .countdown(cx.start + OFFSET.cycles(), COUNTDOWN)
+            .unwrap();
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(schedule = [countdown])]
+    fn countdown(cx: countdown::Context, left: u32) {
+        if left == 0 {
+            rprintln!("reset");
+            system_reset();
+        }
+        rprintln!("reset in {}", left);
+        try_reschedule(
+            cx.schedule
+                .countdown(cx.scheduled + OFFSET.cycles(), left - 1),
+            "countdown",
+        );
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// The reset clears all RAM based state, so the count must be kept in memory
+// that survives: the backup registers (20 words, see `rtic_backup.rs`), backup
+// SRAM (not on the F401/F411), or flash (see `rtic_flash_store.rs`, wears out).
+//
+// The RTT buffer is in RAM, and re-initialized on boot. The host side keeps
+// reading from the same address, so the output continues after the reset.
+// Some probe tools lose the connection, and need to be restarted.
+//
+// With a debugger attached, a system reset does not reset the debug logic,
+// breakpoints and watchpoints stay in place.
+//
+// `SCB::sys_reset` keeps the PRIGROUP setting in AIRCR. Setting VECTRESET
+// instead of SYSRESETREQ (core only reset) is not supported on ARMv7-M
+// outside of debug.
@@ -0,0 +1,104 @@
+//! rtic_spawn_args.rs
+//!
+//! Message passing by spawn arguments
+//!
+//! What it covers:
+//! - passing a payload struct from a producer to a consumer task
+//! - ownership of the payload is moved, no shared resource (nor `lock`) is needed
+//! - tasks at different priorities (one dispatcher per priority)
+//!
+//! > cargo run --example rtic_spawn_args
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+
+use app::{schedule::try_reschedule, trace_init};
+
+// producer period in clock cycles, ~1 s at the default 16 MHz
+const PERIOD: u32 = 16_000_000;
+
+// The payload, `Copy` as it is plain data (the message queue stores it by value)
+#[derive(Clone, Copy, Debug)]
+struct Measurement {
+    seq: u32,
+    // time of production, to show the latency in `consume`
+    produced: Instant,
+    // some "computed" data, the first `seq` odd numbers sum to seq^2
+    sum: u32,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    #[init(schedule = [produce])]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        cx.schedule.produce(cx.start + PERIOD.cycles()).unwrap();
+    }
+
+    // the state of the producer is task local, no other task can see it
+    #[task(priority = 2, schedule = [produce], spawn = [consume])]
+    fn produce(cx: produce::Context) {
+        static mut SEQ: u32 = 0;
+
+        *SEQ += 1;
+        let sum = (0..*SEQ).map(|i| 2 * i + 1).sum();
+
+        let m = Measurement {
+            seq: *SEQ,
+            produced: Instant::now(),
+            sum,
+        };
+
+        rprintln!("produce {}", m.seq);
+        // `m` is copied into the message queue of `consume`
+        if let Err(m) = cx.spawn.consume(m) {
+            rprintln!("consume queue full, dropped {:?}", m);
+        }
+
+        try_reschedule(
+            cx.schedule.produce(cx.scheduled + PERIOD.cycles()),
+            "produce",
+        );
+    }
+
+    // lower priority, runs when `produce` has finished
+    #[task(priority = 1, capacity = 4)]
+    fn consume(_cx: consume::Context, m: Measurement) {
+        let latency = Instant::now().duration_since(m.produced);
+        rprintln!(
+            "consume {}, sum {}, latency {} cycles",
+            m.seq,
+            m.sum,
+            latency.as_cycles()
+        );
+    }
+
+    // one dispatcher per software task priority
+    extern "C" {
+        fn EXTI0();
+        fn EXTI1();
+    }
+};
+
+// Notes:
+//
+// Compared to a shared resource (`rtic_lock.rs`) or a queue split into
+// producer/consumer halves (`rtic_spsc.rs`), spawn arguments:
+//
+// - need no resource declarations, the message queue is managed by RTIC
+// - run the consumer once per message (no polling in `idle`)
+// - copy the payload, keep it small (or pass a `&'static mut` buffer)
+//
+// The payload need not be `Copy`, any type that is `Send` works, it is moved
+// into the queue (and handed back in the `Err` of a failed spawn).
@@ -0,0 +1,92 @@
+//! rtic_spi_loopback.rs
+//!
+//! SPI loopback self-test
+//!
+//! What it covers:
+//! - setting up SPI1 in master mode using the HAL
+//! - SPI mode (clock polarity and phase) and bit rate (prescaler)
+//! - a full duplex transfer (`Transfer`), checking the received data
+//!
+//! > cargo run --example rtic_spi_loopback
+//!
+//! Wire MOSI PA7 (CN10-15) to MISO PA6 (CN10-13) with a jumper.
+//! Every byte sent is then received back. SCK on PA5 (CN10-11) can be
+//! monitored with a scope, (it also drives the on-board LED LD2).
+
+#![no_main]
+#![no_std]
+
+use embedded_hal::spi::{Mode, Phase, Polarity};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, spi::Spi};
+
+use app::{trace::print_banner, trace_init};
+
+// SPI mode 0, try the other combinations
+const MODE: Mode = Mode {
+    polarity: Polarity::IdleLow,
+    phase: Phase::CaptureOnFirstTransition,
+};
+// SCK = PCLK2 / PRESCALER, one of 2, 4, 8, .. 256, SPI_CR1 BR, RM0368 20.5.1
+const PRESCALER: u32 = 16;
+
+// test pattern, covering all zeros/ones and alternating bits
+const PATTERN: [u8; 8] = [0x00, 0xff, 0x55, 0xaa, 0x01, 0x80, 0x12, 0xed];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let sck = gpioa.pa5.into_alternate_af5();
+        let miso = gpioa.pa6.into_alternate_af5();
+        let mosi = gpioa.pa7.into_alternate_af5();
+
+        // the HAL picks the prescaler from the requested frequency
+        let freq = (clocks.pclk2().0 / PRESCALER).hz();
+        let mut spi = Spi::spi1(device.SPI1, (sck, miso, mosi), MODE, freq, clocks);
+        rprintln!(
+            "SPI1 CPOL {}, CPHA {}, SCK {} Hz",
+            (MODE.polarity == Polarity::IdleHigh) as u8,
+            (MODE.phase == Phase::CaptureOnSecondTransition) as u8,
+            freq.0
+        );
+
+        // the transfer overwrites the buffer with the received bytes
+        let mut buffer = PATTERN;
+        match spi.transfer(&mut buffer) {
+            Ok(received) if *received == PATTERN[..] => rprintln!("PASS"),
+            Ok(received) => {
+                rprintln!("FAIL, sent {:02x?}", PATTERN);
+                rprintln!("  received {:02x?}", received);
+            }
+            Err(err) => rprintln!("FAIL, error {:?}", err),
+        }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+};
+
+// Notes:
+//
+// Without the jumper MISO floats, and the received bytes are typically
+// all 0x00 or 0xff (or random), so the test fails.
+//
+// In a loopback, master samples its own output, so any MODE works. With a
+// real slave, MODE must match the slave (see its data sheet), and the bit rate
+// must not exceed what the slave (and the wiring) supports.
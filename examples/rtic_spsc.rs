@@ -0,0 +1,118 @@
+//! rtic_spsc.rs
+//!
+//! Lock-free communication between tasks
+//!
+//! What it covers:
+//! - a single producer single consumer queue (`heapless::spsc::Queue`)
+//! - splitting the queue into `Producer`/`Consumer` halves owned by different tasks
+//! - handling a full queue without blocking the (higher priority) producer
+//!
+//! > cargo run --example rtic_spsc
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use heapless::{
+    consts::*,
+    i,
+    spsc::{Consumer, Producer, Queue},
+};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    prelude::*,
+    stm32::TIM2,
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// Timestamped sample passed from `tim2` to `idle`
+#[derive(Debug)]
+struct Sample {
+    seq: u32,
+    timestamp: u32,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        timer: Timer<TIM2>,
+        p: Producer<'static, Sample, U8>,
+        c: Consumer<'static, Sample, U8>,
+        // number of samples dropped due to a full queue
+        #[init(0)]
+        dropped: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        // the queue must outlive the `Producer`/`Consumer` halves, hence `static`
+        static mut Q: Queue<Sample, U8> = Queue(i::Queue::new());
+
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the cycle counter (CYCCNT) used for time stamps
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // TIM2 update interrupt at 100 Hz
+        let mut timer = Timer::tim2(device.TIM2, 100.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        let (p, c) = Q.split();
+
+        init::LateResources { timer, p, c }
+    }
+
+    // idle has the lowest priority (0), it runs whenever no task is running
+    #[idle(resources = [c, dropped])]
+    fn idle(mut cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            // no lock needed, idle is the only consumer
+            if let Some(sample) = cx.resources.c.dequeue() {
+                // `dropped` is shared with `tim2` (higher priority), hence lock
+                let dropped = cx.resources.dropped.lock(|dropped| *dropped);
+                rprintln!("{:?}, dropped {}", sample, dropped);
+            }
+        }
+    }
+
+    #[task(binds = TIM2, resources = [timer, p, dropped])]
+    fn tim2(cx: tim2::Context) {
+        static mut SEQ: u32 = 0;
+
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        let sample = Sample {
+            seq: *SEQ,
+            timestamp: DWT::get_cycle_count(),
+        };
+        *SEQ = SEQ.wrapping_add(1);
+
+        // never block in the producer, drop the sample if the consumer lags behind
+        if cx.resources.p.enqueue(sample).is_err() {
+            *cx.resources.dropped += 1;
+        }
+    }
+};
+
+// Notes:
+//
+// The `Producer` and `Consumer` are each owned by a single task, so RTIC
+// grants access without locks. The queue itself is lock-free: the producer only
+// writes the tail index and the consumer only writes the head index.
+//
+// Printing over RTT is slow compared to the sample rate, so expect the queue
+// to fill up. Try increasing the sample rate, or the queue size (U8), and
+// observe the `dropped` counter.
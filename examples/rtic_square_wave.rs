@@ -0,0 +1,158 @@
+//! rtic_square_wave.rs
+//!
+//! Square wave generator, timer output compare in toggle mode
+//!
+//! What it covers:
+//! - TIM3 channel 1 toggling its output pin in hardware, no code per edge
+//! - solving the prescaler (PSC) and auto-reload (ARR) for a frequency (`app::timer`)
+//! - the timer clock, twice PCLK1 when APB1 is divided (`clock::timer_clock`)
+//! - the achieved frequency, which may differ from the requested one
+//!
+//! > cargo run --example rtic_square_wave
+//!
+//! Connect a scope (or a frequency counter) to PA6 (CN10-13, TIM3_CH1). Every
+//! STEP_MS a new frequency from FREQS is set.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{prelude::*, rcc::Clocks, stm32::TIM3};
+
+use app::{
+    clock::timer_clock, delay::ms_to_cycles, schedule::try_reschedule, timer::divider,
+    trace::print_banner, trace_init,
+};
+
+// SYSCLK in Hz, set in `init`
+const SYSCLK_HZ: u32 = 84_000_000;
+// time between frequency changes in ms
+const STEP_MS: u32 = 2_000;
+
+// requested frequencies in Hz, the last two are out of range
+const FREQS: [u32; 7] = [1, 440, 1_000, 12_345, 1_000_000, 0, 50_000_000];
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        tim: TIM3,
+        clocks: Clocks,
+        #[init(0)]
+        index: usize,
+    }
+
+    #[init(schedule = [step])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // power on TIM3, RM0368 6.3.11
+        device.RCC.apb1enr.modify(|_, w| w.tim3en().enabled());
+
+        // APB1 divided by 2, so TIM3 counts at 2 x 42 MHz
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).pclk1(42.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        // TIM3_CH1 on PA6, alternate function 2
+        let gpioa = device.GPIOA.split();
+        let _ch1 = gpioa.pa6.into_alternate_af2();
+
+        let tim = device.TIM3;
+        // toggle OC1REF when the counter matches CCR1, TIMx_CCMR1, RM0368 13.4.7
+        tim.ccmr1_output().modify(|_, w| w.oc1m().toggle());
+        tim.ccr1.write(|w| w.ccr().bits(0));
+        // output enable, TIMx_CCER, RM0368 13.4.9
+        tim.ccer.modify(|_, w| w.cc1e().set_bit());
+        tim.cr1.modify(|_, w| w.cen().enabled());
+
+        cx.schedule.step(cx.start).unwrap();
+
+        init::LateResources { tim, clocks }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    // set the next frequency from FREQS
+    #[task(resources = [tim, clocks, index], schedule = [step])]
+    fn step(cx: step::Context) {
+        let hz = FREQS[*cx.resources.index];
+        *cx.resources.index = (*cx.resources.index + 1) % FREQS.len();
+
+        match set_freq(cx.resources.tim, cx.resources.clocks, hz) {
+            Some(achieved) => rprintln!("requested {} Hz, achieved {} Hz", hz, achieved),
+            None => rprintln!("requested {} Hz, out of range, unchanged", hz),
+        }
+
+        let offset = ms_to_cycles(STEP_MS, SYSCLK_HZ);
+        try_reschedule(cx.schedule.step(cx.scheduled + offset.cycles()), "step");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Set the square wave frequency, returns the achieved frequency in Hz
+//
+// `None` (the timer left as is) if `hz` cannot be produced, see `timer::divider`.
+fn set_freq(tim: &TIM3, clocks: &Clocks, hz: u32) -> Option<f32> {
+    let timer_clk = timer_clock(clocks.pclk1().0, clocks.ppre1());
+    // the output toggles once per update, two updates per period
+    let d = divider(timer_clk, hz.checked_mul(2)?, 0xFFFF)?;
+
+    tim.psc.write(|w| w.psc().bits(d.psc));
+    // TIM3 is a 16 bit timer, `divider` keeps ARR <= 0xFFFF
+    tim.arr.write(|w| w.arr().bits(d.arr as u16));
+    // load PSC and ARR now (not at the next update) and restart from 0,
+    // TIMx_EGR, RM0368 13.4.6
+    tim.egr.write(|w| w.ug().set_bit());
+
+    Some(d.update_hz(timer_clk) / 2.0)
+}
+
+// Notes:
+//
+// The square wave frequency is
+//
+//   f = timer_clk / ((PSC + 1) x (ARR + 1)) / 2
+//
+// the counter matches CCR1 once per period (ARR + 1 counts), and the output
+// toggles on each match. With 84 MHz at the timer, 440 Hz needs 95_454.5
+// timer cycles per half period. That is above 0xFFFF, so PSC is 1 (divide by
+// 2), and ARR + 1 = 47_727, giving 440.002 Hz. The difference to the request
+// is the integer division, print it rather than assume the exact value.
+//
+// The timer clock is 84 MHz, although PCLK1 is 42 MHz. Timers on a divided
+// APB run at twice PCLK (RM0368 6.2, and `clock::dump_clocks`). Using PCLK1
+// here would give half the requested frequency.
+//
+// The edges are made by the timer hardware, so there is no jitter from
+// interrupt latency, compared with toggling a pin from a (CYCCNT) scheduled
+// task as in `rtic_bare6.rs`. The CPU is not involved at all, also at 1 MHz.
+// The accuracy is that of the clock source (HSI, or HSE). The MCO outputs
+// (`rtic_bare6.rs`, `rtic_heartbeat_mco.rs`) are as clean, but divide a clock
+// by 1..5 only.
+//
+// Changing the frequency restarts the count (UG), cutting the current half
+// period short. For glitch free changes, set ARPE in TIMx_CR1 (ARR preload)
+// and let the new values take effect at the next update instead.
+//
+// Other pins: any pin with a timer channel works the same way, see the
+// alternate function table in the data sheet (table 9), e.g., TIM3_CH1 is
+// also on PB4 and PC6. Set the matching CCMRx/CCER bits for channels 2..4.
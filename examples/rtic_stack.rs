@@ -0,0 +1,94 @@
+//! rtic_stack.rs
+//!
+//! Measuring the peak stack usage
+//!
+//! What it covers:
+//! - painting the free stack at startup (`app::stack`)
+//! - the stack bounds from the linker symbols (`_stack_start`, `__sheap`)
+//! - the high-water mark after calls of increasing depth
+//!
+//! > cargo run --example rtic_stack --release
+
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+
+use app::{stack, trace::print_banner, trace_init};
+
+// bytes of locals per call level
+const FRAME: usize = 64;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    #[init]
+    fn init(_cx: init::Context) {
+        // first, before the stack grows any deeper (interrupts are disabled in `init`)
+        stack::paint();
+
+        trace_init!();
+        print_banner(16_000_000);
+
+        let (bottom, top) = stack::bounds();
+        rprintln!(
+            "stack 0x{:08x}..0x{:08x}, {} bytes",
+            bottom,
+            top,
+            stack::size()
+        );
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("after init: {} bytes", stack::high_water_mark());
+
+        // the high-water mark only grows, shallow calls after deep ones do not lower it
+        for depth in [1, 2, 4, 8, 16, 4].iter() {
+            nested(*depth);
+            rprintln!(
+                "after depth {:2}: {} bytes",
+                depth,
+                stack::high_water_mark()
+            );
+        }
+
+        loop {
+            continue;
+        }
+    }
+};
+
+// Recurse `depth` levels, each with FRAME bytes of locals
+#[inline(never)]
+fn nested(depth: u32) -> u32 {
+    let mut locals = [0u8; FRAME];
+    // volatile (the whole array), so it is kept on the stack, not optimized out
+    unsafe { ptr::write_volatile(&mut locals, [depth as u8; FRAME]) };
+    let sum = if depth > 1 { nested(depth - 1) } else { 0 };
+    sum + unsafe { ptr::read_volatile(&locals) }[FRAME - 1] as u32
+}
+
+// Notes:
+//
+// The high-water mark covers everything using the main stack (MSP), `init`,
+// `idle`, all tasks and exception handlers (RTIC tasks share the one stack).
+// A task preempting another stacks its frame on top, so the worst case is
+// the sum of the deepest paths at each priority level.
+//
+// The measurement shows the peak of what actually ran. Paths not exercised
+// (e.g., error handling) are not included. Leave a margin, or analyze the
+// worst case statically (e.g., `cargo call-stack`).
+//
+// Each level above takes FRAME bytes plus the saved registers and the return
+// address, compare the growth per level with FRAME. In a debug build the
+// frames are larger.
+//
+// A stack overflowing into the statics silently corrupts them, there is no
+// MPU guard by default. `flip-link` (a linker wrapper) places the stack below
+// the statics instead, so an overflow hits the start of RAM and faults.
+//
+// Painting costs a write per word of free stack (~15 k words with the 64 kB
+// RAM in `memory.x`), once at startup. Scanning reads from the bottom up to
+// the first overwritten word.
@@ -0,0 +1,147 @@
+//! rtic_stop_mode.rs
+//!
+//! STOP mode
+//!
+//! What it covers:
+//! - entering STOP mode from `idle` (PWR_CR and SLEEPDEEP)
+//! - waking up on an EXTI line (the user button)
+//! - restoring the system clock after wakeup
+//!
+//! > cargo run --example rtic_stop_mode
+//!
+//! IMPORTANT: On wakeup from STOP, the HSI is selected as system clock and
+//! the PLL is OFF. Any clock configuration (here 84 MHz from the PLL) must
+//! be restored by software, otherwise the MCU silently continues at 16 MHz,
+//! and all timing derived from `clocks` (baud rates, timers, delays) is wrong.
+//!
+//! Measure the current through JP5 (IDD) on the Nucleo to compare RUN and STOP.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, gpioc::PC13, Edge, ExtiPin, Floating, Input, Output, PushPull},
+    prelude::*,
+    stm32,
+};
+
+use app::{trace::print_banner, trace_init};
+
+// LED on time in clock cycles, ~100 ms at 84 MHz
+const BLINK: u32 = 8_400_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        button: PC13<Input<Floating>>,
+        led: PA5<Output<PushPull>>,
+        #[init(0)]
+        wakeups: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let mut device = cx.device;
+
+        // power on SYSCFG (needed for EXTI routing), RM0368 6.3.12
+        device.RCC.apb2enr.modify(|_, w| w.syscfgen().enabled());
+        // power on PWR, RM0368 6.3.11
+        device.RCC.apb1enr.modify(|_, w| w.pwren().enabled());
+
+        // STOP (not STANDBY) with the regulator in low-power mode, PWR_CR, RM0368 5.4.1
+        device
+            .PWR
+            .cr
+            .modify(|_, w| w.pdds().clear_bit().lpds().set_bit());
+
+        // keep the debugger (and RTT) connected in STOP mode, DBGMCU_CR, RM0368 chapter 23
+        // clear DBG_STOP when measuring the current, as it keeps the clocks running
+        device.DBGMCU.cr.modify(|_, w| w.dbg_stop().set_bit());
+
+        // deep sleep, `wfi` enters STOP instead of SLEEP, SCB_SCR, PM0214 4.4.6
+        core.SCB.set_sleepdeep();
+
+        let rcc = device.RCC.constrain();
+        // 84 MHz from the PLL (HSI source)
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+        rprintln!("sysclk {}", clocks.sysclk().0);
+
+        // setup LED, `split` powers on GPIOA
+        let gpioa = device.GPIOA.split();
+        let led = gpioa.pa5.into_push_pull_output();
+
+        // setup the user button (B1) on PC13, the Nucleo has an external pull-up
+        let gpioc = device.GPIOC.split();
+        let mut button = gpioc.pc13.into_floating_input();
+
+        // EXTI lines are active in STOP mode, and can thus wake the MCU
+        button.make_interrupt_source(&mut device.SYSCFG);
+        button.trigger_on_edge(&mut device.EXTI, Edge::FALLING);
+        button.enable_interrupt(&mut device.EXTI);
+
+        init::LateResources { button, led }
+    }
+
+    #[idle(resources = [wakeups])]
+    fn idle(mut cx: idle::Context) -> ! {
+        loop {
+            rprintln!("enter STOP");
+            // STOP mode, until an EXTI event occurs
+            asm::wfi();
+            // the `exti15_10` task has already run (and restored the clocks) here
+            let wakeups = cx.resources.wakeups.lock(|wakeups| *wakeups);
+            rprintln!("wakeup {}", wakeups);
+        }
+    }
+
+    #[task(binds = EXTI15_10, resources = [button, led, wakeups])]
+    fn exti15_10(cx: exti15_10::Context) {
+        // first thing after wakeup, get back to 84 MHz
+        restore_clocks();
+
+        cx.resources.button.clear_interrupt_pending_bit();
+        *cx.resources.wakeups += 1;
+
+        // blink, to show that we are back
+        cx.resources.led.set_high().ok();
+        asm::delay(BLINK);
+        cx.resources.led.set_low().ok();
+    }
+};
+
+// Re-enable the PLL and select it as system clock, RM0368 6.3.1 and 6.3.3
+//
+// The PLL configuration (PLLCFGR), bus prescalers and flash wait states are
+// retained in STOP mode, only PLLON and SW are reset by hardware.
+fn restore_clocks() {
+    // SAFETY: RCC is owned by the HAL after `constrain`, we only touch PLLON and SW,
+    // which the HAL does not use after `freeze`.
+    let rcc = unsafe { &*stm32::RCC::ptr() };
+
+    rcc.cr.modify(|_, w| w.pllon().on());
+    while rcc.cr.read().pllrdy().is_not_ready() {}
+
+    rcc.cfgr.modify(|_, w| w.sw().pll());
+    while !rcc.cfgr.read().sws().is_pll() {}
+}
+
+// Notes:
+//
+// In STOP mode all clocks in the 1.2V domain are stopped, and thus also
+// SysTick and CYCCNT. That is why this example does not use the monotonic
+// timer (scheduled tasks would be delayed until the next wakeup).
+//
+// With DBG_STOP set, the current consumption is far from the data sheet values.
+// For a real measurement, clear DBG_STOP, flash the application, detach the
+// debugger and power cycle the board. (RTT output is then not available.)
+//
+// Wakeup from STOP with the regulator in low-power mode takes longer than
+// with the main regulator on (LPDS = 0), see the data sheet (tWUSTOP).
@@ -0,0 +1,119 @@
+//! rtic_systick_pin.rs
+//!
+//! SysTick vs. CYCCNT
+//!
+//! What it covers:
+//! - configuring SysTick (reload value, clock source) and binding its exception
+//! - toggling a pin from the SysTick handler
+//! - cross-checking the SysTick rate against CYCCNT and MCO2
+//!
+//! > cargo run --example rtic_systick_pin
+//!
+//! Scope channel 1 on PA6 (CN10-13), SysTick rate / 2 (a toggle per tick).
+//! Scope channel 2 on PC9 (CN10-1), MCO2 = SYSCLK / 4.
+
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::{syst::SystClkSource, DWT};
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA6, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    mco::{ClockOut, Mco2Prescaler},
+    trace::print_banner,
+    trace_init,
+};
+
+// SysTick interrupt rate in Hz
+const TICK_HZ: u32 = 1_000;
+
+// This app does not use a `monotonic` (RTIC would then own SysTick for its timer queue).
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        pin: PA6<Output<PushPull>>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // SYSCLK / 4 on MCO2 (PC9)
+        ClockOut::mco2_sysclk(&device.RCC, &device.GPIOC, Mco2Prescaler::Div4);
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(48.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+        let sysclk = clocks.sysclk().0;
+
+        let gpioa = device.GPIOA.split();
+        let pin = gpioa.pa6.into_push_pull_output();
+
+        // SysTick counts down from RELOAD to 0, i.e., RELOAD + 1 cycles per tick,
+        // clocked by the core clock (HCLK = SYSCLK here), PM0214 4.5
+        let reload = sysclk / TICK_HZ - 1;
+        let mut syst = core.SYST;
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(reload);
+        syst.clear_current();
+        syst.enable_interrupt();
+        syst.enable_counter();
+
+        rprintln!("SYSCLK {} Hz, MCO2 (PC9) {} Hz", sysclk, sysclk / 4);
+        rprintln!(
+            "SysTick reload {}, {} Hz, PA6 {} Hz",
+            reload,
+            sysclk / (reload + 1),
+            sysclk / (reload + 1) / 2
+        );
+
+        init::LateResources { pin }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = SysTick, resources = [pin])]
+    fn systick(cx: systick::Context) {
+        static mut TICKS: u32 = 0;
+        static mut LAST: u32 = 0;
+
+        cx.resources.pin.toggle().ok();
+
+        // once per second, print the CYCCNT delta over TICK_HZ ticks (= SYSCLK)
+        *TICKS += 1;
+        if *TICKS == TICK_HZ {
+            *TICKS = 0;
+            let now = DWT::get_cycle_count();
+            rprintln!("1 s of SysTick = {} CYCCNT cycles", now.wrapping_sub(*LAST));
+            *LAST = now;
+        }
+    }
+};
+
+// Notes:
+//
+// SysTick and CYCCNT both count core clock cycles here, so the printed
+// delta equals SYSCLK (after the first, partial, second). With the clock
+// source set to `External` (HCLK / 8), the reload must be 8 times smaller.
+//
+// SysTick is a 24 bit down counter, so RELOAD must be below 2^24, i.e.,
+// the slowest tick at 48 MHz is ~2.9 Hz. CYCCNT is a 32 bit up counter
+// without reload or interrupt, used by RTIC as a monotonic time base.
@@ -0,0 +1,138 @@
+//! rtic_temp_blink.rs
+//!
+//! A blink rate following the chip temperature
+//!
+//! What it covers:
+//! - sampling the internal temperature sensor (see `rtic_temp_sensor.rs`)
+//! - mapping the temperature to a blink interval, clamped to sane bounds
+//! - rescheduling with an offset recomputed on each cycle
+//!
+//! > cargo run --example rtic_temp_blink
+//!
+//! The hotter the chip, the faster the LED (LD2, PA5) blinks. Warm the chip
+//! with a finger (or a hair dryer) and watch the interval drop.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc, Temperature,
+    },
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+    signature::{VtempCal110, VtempCal30},
+    stm32::ADC1,
+};
+
+use app::{
+    adc::to_celsius, delay::ms_to_cycles, led::Led, schedule::try_reschedule, trace::print_banner,
+    trace_init,
+};
+
+// SYSCLK in Hz, the HAL default (HSI)
+const SYSCLK_HZ: u32 = 16_000_000;
+
+// the interval at the reference temperature, in ms
+const BASE_MS: u32 = 1_000;
+const REF_C: f32 = 30.0;
+// interval change per degree above (or below) the reference, in ms
+const MS_PER_C: f32 = 50.0;
+// the bounds of the interval, in ms
+const MIN_MS: u32 = 100;
+const MAX_MS: u32 = 2_000;
+
+// at least 10 us for the sensor, see `rtic_temp_sensor.rs`
+const SAMPLE_TIME: SampleTime = SampleTime::Cycles_480;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: Adc<ADC1>,
+        led: Led<PA5<Output<PushPull>>>,
+    }
+
+    #[init(schedule = [blink])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // the calibration values are given for 12 bit samples
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let mut adc = Adc::adc1(device.ADC1, true, config);
+        adc.enable_temperature_and_vref();
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        cx.schedule.blink(cx.start + SYSCLK_HZ.cycles()).unwrap();
+
+        init::LateResources { adc, led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [adc, led], schedule = [blink])]
+    fn blink(cx: blink::Context) {
+        cx.resources.led.toggle().ok();
+
+        let raw = cx.resources.adc.convert(&Temperature, SAMPLE_TIME);
+        let celsius = to_celsius(raw, VtempCal30::get().read(), VtempCal110::get().read());
+        let ms = interval_ms(celsius);
+        rprintln!("{:.1} C, interval {} ms", celsius, ms);
+
+        // the next deadline relative to this one, with the new interval
+        let offset = ms_to_cycles(ms, SYSCLK_HZ);
+        if !try_reschedule(cx.schedule.blink(cx.scheduled + offset.cycles()), "blink") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// The blink interval at `celsius`, shorter when hotter, clamped to MIN_MS..=MAX_MS
+fn interval_ms(celsius: f32) -> u32 {
+    let ms = BASE_MS as f32 - (celsius - REF_C) * MS_PER_C;
+    // also maps NaN (a broken reading) to MIN_MS, the float to int `as` saturates
+    (ms as u32).max(MIN_MS).min(MAX_MS)
+}
+
+// Notes:
+//
+// The interval is linear in the temperature, 1000 ms at 30 C, 50 ms shorter
+// per degree. The die runs a few degrees above ambient, expect about 1000 -
+// 1300 ms at room temperature, and the minimum (100 ms) at 48 C.
+//
+// Sampling once per toggle ties the control rate to the blink rate, slow
+// when cold and fast when hot. The sensor is noisy (a few tenths of a degree),
+// which shows as a jitter of some ms in the interval. Average a few samples
+// (e.g., a moving average) for a steadier rate.
+//
+// The interval is changed from one toggle to the next, each deadline is the
+// previous one plus the new offset (`cx.scheduled`, not `now`), so the task
+// overhead does not accumulate.
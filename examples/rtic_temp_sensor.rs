@@ -0,0 +1,109 @@
+//! rtic_temp_sensor.rs
+//!
+//! Internal temperature sensor
+//!
+//! What it covers:
+//! - enabling the internal temperature sensor (TSVREFE)
+//! - sampling an internal ADC channel
+//! - converting to degrees Celsius using the factory calibration values
+//!
+//! > cargo run --example rtic_temp_sensor
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    adc::{
+        config::{AdcConfig, Resolution, SampleTime},
+        Adc, Temperature,
+    },
+    prelude::*,
+    signature::{VtempCal110, VtempCal30},
+    stm32::ADC1,
+};
+
+use app::{adc::to_celsius, schedule::try_reschedule, trace::print_banner, trace_init};
+
+// sample period in clock cycles, 1 s at 16 MHz
+const OFFSET: u32 = 16_000_000;
+
+// The sensor requires a minimum sampling time of 10 us (STM32F401xD/E data sheet,
+// table 72, TS_temp). With ADCCLK = PCLK2 / 2 = 8 MHz, 480 cycles gives 60 us.
+// Shorter sample times give garbage readings.
+const SAMPLE_TIME: SampleTime = SampleTime::Cycles_480;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        adc: Adc<ADC1>,
+    }
+
+    #[init(schedule = [sample])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks)
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        // the calibration values are given for 12 bit samples
+        let config = AdcConfig::default().resolution(Resolution::Twelve);
+        let mut adc = Adc::adc1(device.ADC1, true, config);
+
+        // set TSVREFE in ADC_CCR, RM0368 11.12.4
+        adc.enable_temperature_and_vref();
+
+        rprintln!(
+            "TS_CAL1 (30 C) {}, TS_CAL2 (110 C) {}",
+            VtempCal30::get().read(),
+            VtempCal110::get().read()
+        );
+
+        cx.schedule.sample(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { adc }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [adc], schedule = [sample])]
+    fn sample(cx: sample::Context) {
+        let raw = cx.resources.adc.convert(&Temperature, SAMPLE_TIME);
+        rprintln!("raw {}, {} C", raw, adc_to_celsius(raw));
+
+        try_reschedule(cx.schedule.sample(cx.scheduled + OFFSET.cycles()), "sample");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Linear interpolation between the factory calibration points, see `to_celsius`
+fn adc_to_celsius(raw: u16) -> f32 {
+    to_celsius(raw, VtempCal30::get().read(), VtempCal110::get().read())
+}
+
+// Notes:
+//
+// The sensor measures the die temperature, which is typically a few degrees
+// above ambient. It is suitable for detecting temperature changes, the absolute
+// accuracy is limited (+/- 1.5 C after calibration).
@@ -0,0 +1,106 @@
+//! rtic_tim2_monotonic.rs
+//!
+//! TIM2 as the monotonic timer, scheduling that survives sleep
+//!
+//! What it covers:
+//! - a custom `rtic::Monotonic` implementation (`app::monotonic::Tim2Monotonic`)
+//! - scheduling with a hardware timer as time base, instead of CYCCNT
+//! - sleeping in `idle` (`wfi`) without the DBG_SLEEP workaround of `rtic_wfi.rs`
+//!
+//! > cargo run --example rtic_tim2_monotonic
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{
+    led::Led, monotonic::Tim2Monotonic, schedule::try_reschedule, trace::print_banner, trace_init,
+};
+
+// toggle period in TIM2 ticks (core clock cycles), 0.5 s at 16 MHz
+const PERIOD: u32 = 8_000_000;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = app::monotonic::Tim2Monotonic, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        // Notice, no DCB/DWT setup, the CYCCNT is not used.
+        // The monotonic timer must be running before `init` returns.
+        Tim2Monotonic::start(&device.RCC, device.TIM2);
+
+        let rcc = device.RCC.constrain();
+        // 16 MHz (default, all clocks), TIM2 ticks at 16 MHz
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        // `cx.start` is a `Tim2Instant`, `+ u32` adds timer ticks
+        cx.schedule.toggle(cx.start + PERIOD).unwrap();
+
+        init::LateResources { led }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle, sleeping");
+        loop {
+            // TIM2 keeps counting in sleep mode, the wake up time is exact
+            asm::wfi();
+        }
+    }
+
+    #[task(resources = [led], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        cx.resources.led.toggle().ok();
+        rprintln!("toggle @ {:?}", cx.scheduled);
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + PERIOD), "toggle") {
+            // the task stopped, leave the LED off
+            cx.resources.led.off().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// In RTIC 0.5 the monotonic timer only tells the time (`now`), the timer queue
+// still uses SysTick to wake up at the next deadline. SysTick keeps running in
+// sleep mode, but the CYCCNT stops with the core clock (unless DBG_SLEEP is
+// set, see `rtic_wfi.rs`). With CYCCNT, `now` is then too early after each
+// sleep, and the queue re-arms SysTick for the (wrongly) remaining time, so
+// tasks run late. TIM2 counts in sleep mode, and the deadlines hold.
+//
+// Tradeoffs vs. CYCCNT:
+//
+// - TIM2 is a peripheral, it is taken by the monotonic and not available
+//   for PWM, input capture, etc. (the CYCCNT costs no peripheral)
+// - reading TIM2_CNT is a bus access to APB1, a few cycles slower than
+//   reading CYCCNT on the private peripheral bus
+// - `ratio` is 1/1 only while the timer clock equals the core clock (APB1
+//   prescaler 1 or 2), with a prescaler (e.g., PSC for 1 MHz ticks) the ratio
+//   must match, or the timer queue over or under shoots
+// - in STOP mode both TIM2 and SysTick stop (the LSE driven RTC keeps
+//   running, see `rtic_stop_mode.rs`)
+// - TIM2 can be frozen when the core is halted by the debugger (DBGMCU_APB1_FZ
+//   DBG_TIM2_STOP), the CYCCNT always stops on halt
@@ -0,0 +1,105 @@
+//! rtic_tim2_periodic.rs
+//!
+//! Periodic task driven by a hardware timer
+//!
+//! What it covers:
+//! - configuring TIM2 as a periodic count down timer (HAL `Timer`)
+//! - binding a hardware task to the TIM2 update interrupt
+//! - clearing the update interrupt flag
+//!
+//! > cargo run --example rtic_tim2_periodic
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+    stm32::TIM2,
+    timer::{Event, Timer},
+};
+
+use app::{trace::print_banner, trace_init};
+
+// frequency of the TIM2 update interrupt (LED toggles), blinks at TIM2_HZ / 2
+const TIM2_HZ: u32 = 2;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: PA5<Output<PushPull>>,
+        timer: Timer<TIM2>,
+        #[init(0)]
+        ticks: u32,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = gpioa.pa5.into_push_pull_output();
+
+        // `Timer::tim2` powers on TIM2, computes the prescaler (PSC) and
+        // auto-reload (ARR) values from the timer clock, and starts counting
+        let mut timer = Timer::tim2(device.TIM2, TIM2_HZ.hz(), clocks);
+        // update interrupt enable, TIM2_DIER UIE, RM0368 13.4.4
+        timer.listen(Event::TimeOut);
+
+        // The TIM2 interrupt is enabled in the NVIC by RTIC,
+        // as it is bound to the `tim2` hardware task.
+
+        init::LateResources { led, timer }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = TIM2, resources = [led, timer, ticks])]
+    fn tim2(cx: tim2::Context) {
+        // clear the update interrupt flag, TIM2_SR UIF, RM0368 13.4.5
+        // otherwise the handler is re-entered as soon as it returns
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        *cx.resources.ticks += 1;
+        rprintln!("tim2 {}", cx.resources.ticks);
+        cx.resources.led.toggle().ok();
+    }
+};
+
+// Notes:
+//
+// Compared to scheduling on CYCCNT (e.g., `rtic_bare6.rs`):
+//
+// - The period is kept by the timer hardware, there is no `cx.scheduled + offset`
+//   to compute in software, and no accumulating error from late rescheduling.
+// - The CYCCNT stops in (deep) sleep modes, while a timer keeps running in
+//   SLEEP (see `rtic_wfi.rs`).
+// - The timer interrupt is a hardware task bound to a fixed vector, while
+//   scheduled software tasks are dispatched through the timer queue (SysTick)
+//   and a free interrupt (`extern "C" { fn EXTI0(); }`), and can be given
+//   arbitrary arguments and deadlines.
+// - Each periodic activity needs a timer of its own, while any number of
+//   tasks can be scheduled on the CYCCNT.
+//
+// The HAL `Timer` implements `embedded_hal::timer::CountDown` (there is no
+// separate count down type in this HAL version), bring the trait into scope
+// and use `start` to change the period at run-time, e.g., `timer.start(4.hz())`.
+//
+// TIM2 is a 32 bit timer, clocked at 2 x PCLK1 (84 MHz with the above setup).
+// The HAL computes a 16 bit prescaler and a 16 bit ARR, so the longest period
+// is 2^32 timer clock cycles (~51 s at 84 MHz).
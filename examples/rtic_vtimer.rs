@@ -0,0 +1,112 @@
+//! rtic_vtimer.rs
+//!
+//! Virtual timers on one hardware tick
+//!
+//! What it covers:
+//! - a single periodic hardware tick (TIM2)
+//! - several software timers with different periods (`app::vtimer`)
+//! - dispatching the due timers from the tick handler
+//!
+//! > cargo run --example rtic_vtimer
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+    stm32::TIM2,
+    timer::{Event, Timer},
+};
+
+use app::{led::Led, trace::print_banner, trace_init, vtimer::VTimers};
+
+// the hardware tick, all periods are multiples of it
+const TICK_HZ: u32 = 100;
+
+// the actions of the virtual timers
+#[derive(Clone, Copy, Debug)]
+enum Action {
+    Blink,
+    Status,
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: Led<PA5<Output<PushPull>>>,
+        timer: Timer<TIM2>,
+        timers: VTimers<Action>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let device = cx.device;
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        print_banner(clocks.sysclk().0);
+
+        let gpioa = device.GPIOA.split();
+        let led = Led::new(gpioa.pa5.into_push_pull_output());
+
+        // `add` fails if all slots are taken, a configuration error
+        let mut timers = VTimers::new();
+        // fast blink, toggle every 100 ms
+        timers
+            .add(TICK_HZ / 10, Action::Blink)
+            .expect("no free slot");
+        // slow status print, every 2 s
+        timers
+            .add(2 * TICK_HZ, Action::Status)
+            .expect("no free slot");
+
+        let mut timer = Timer::tim2(device.TIM2, TICK_HZ.hz(), clocks);
+        timer.listen(Event::TimeOut);
+
+        init::LateResources { led, timer, timers }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        loop {
+            continue;
+        }
+    }
+
+    #[task(binds = TIM2, resources = [led, timer, timers])]
+    fn tim2(cx: tim2::Context) {
+        cx.resources.timer.clear_interrupt(Event::TimeOut);
+
+        let led = cx.resources.led;
+        let timers = cx.resources.timers;
+        let now = timers.now();
+        timers.tick(|action| match action {
+            Action::Blink => {
+                led.toggle().ok();
+            }
+            Action::Status => {
+                rprintln!("status @ tick {}, led {}", now + 1, led.is_on());
+            }
+        });
+    }
+};
+
+// Notes:
+//
+// The tick handler runs at TICK_HZ regardless of how many timers are
+// registered, and the timers are checked in a loop over the slots (O(SLOTS)
+// per tick). Scheduled tasks (`cx.schedule`, see `rtic_bare6.rs`) instead
+// program the SysTick for the next deadline only, with no periodic overhead,
+// but each needs a task (and a queue slot) of its own.
+//
+// All actions run in the tick handler (at its priority). For longer work,
+// spawn a software task from the action instead of doing it inline.
+//
+// Periods are quantized to the tick, 10 ms here. A timer with period 1 fires
+// on every tick.
@@ -0,0 +1,139 @@
+//! rtic_watchpoint.rs
+//!
+//! Data watchpoint using a DWT comparator
+//!
+//! What it covers:
+//! - setting up a DWT comparator (COMP, MASK, FUNCTION) to match writes to a variable
+//! - the DebugMonitor exception, raised on a match (debug monitor mode)
+//! - telling reads and writes apart (only writes match)
+//!
+//! > cargo run --example rtic_watchpoint
+//!
+//! ARMv7-M Architecture Reference Manual (DDI0403E), C1.8 (DWT) and C1.6 (debug events)
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::peripheral::{DCB, DWT, SCB};
+use cortex_m_rt::exception;
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+
+use app::{schedule::try_reschedule, trace_init};
+
+const OFFSET: u32 = 16_000_000;
+
+// the watched variable
+static WATCHED: AtomicU32 = AtomicU32::new(0);
+// number of watchpoint hits
+static HITS: AtomicU32 = AtomicU32::new(0);
+
+// DEMCR MON_EN, enables the DebugMonitor exception, DDI0403E C1.6.5
+const DEMCR_MON_EN: u32 = 1 << 16;
+// DWT_FUNCTION FUNCTION field, watchpoint on data write, DDI0403E C1.8.17
+const FUNCTION_WRITE: u32 = 0b0110;
+// DWT_FUNCTION MATCHED, set on a match, cleared on read
+const FUNCTION_MATCHED: u32 = 1 << 24;
+// DFSR DWTTRAP, the debug event was caused by the DWT, DDI0403E C1.6.1
+const DFSR_DWTTRAP: u32 = 1 << 2;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    #[init(schedule = [write, read])]
+    fn init(cx: init::Context) {
+        trace_init!();
+
+        let mut core = cx.core;
+
+        // Initialize (enable) the monotonic timer (CYCCNT), also enables the DWT (TRCENA)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        watch_writes(&mut core.DCB, &mut core.DWT, &WATCHED as *const _ as u32);
+
+        cx.schedule.write(cx.start + OFFSET.cycles()).unwrap();
+        cx.schedule.read(cx.start + (OFFSET / 2).cycles()).unwrap();
+    }
+
+    #[task(schedule = [write])]
+    fn write(cx: write::Context) {
+        let value = WATCHED.load(Ordering::Relaxed) + 1;
+        rprintln!("write {}", value);
+        // this store raises DebugMonitor
+        WATCHED.store(value, Ordering::Relaxed);
+        rprintln!("hits {}", HITS.load(Ordering::Relaxed));
+
+        try_reschedule(cx.schedule.write(cx.scheduled + OFFSET.cycles()), "write");
+    }
+
+    #[task(schedule = [read])]
+    fn read(cx: read::Context) {
+        // reads do not match the comparator
+        rprintln!("read {}", WATCHED.load(Ordering::Relaxed));
+
+        try_reschedule(cx.schedule.read(cx.scheduled + OFFSET.cycles()), "read");
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Setup comparator 0 to match word writes to `address`
+fn watch_writes(dcb: &mut DCB, dwt: &mut DWT, address: u32) {
+    unsafe {
+        // debug events raise DebugMonitor (if not halted by a debugger)
+        dcb.demcr.modify(|r| r | DEMCR_MON_EN);
+
+        // the address to compare with
+        dwt.c[0].comp.write(address);
+        // ignore the 2 low address bits, i.e., match any byte in the word
+        dwt.c[0].mask.write(2);
+        // match data writes (data address comparison, no linked comparator)
+        dwt.c[0].function.write(FUNCTION_WRITE);
+    }
+}
+
+// Raised on a debug event in monitor mode, here a DWT match
+//
+// The data watchpoint is imprecise, the exception is taken after the write
+// has completed (and possibly a few instructions more).
+#[exception]
+fn DebugMonitor() {
+    // SAFETY: read/clear of debug status only
+    let (scb, dwt) = unsafe { (&*SCB::ptr(), &*DWT::ptr()) };
+
+    let dfsr = scb.dfsr.read();
+    if dfsr & DFSR_DWTTRAP != 0 {
+        // read (and thereby clear) MATCHED
+        let function = dwt.c[0].function.read();
+        if function & FUNCTION_MATCHED != 0 {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            rprintln!(
+                "  watchpoint, WATCHED = {}",
+                WATCHED.load(Ordering::Relaxed)
+            );
+        }
+    }
+    // clear the DFSR flags (write 1 to clear)
+    unsafe { scb.dfsr.write(dfsr) };
+}
+
+// Notes:
+//
+// Debug events are handled in one of two ways:
+//
+// - Halting debug, if a debugger has set C_DEBUGEN (DHCSR), the core halts.
+//   `gdb` reports this as a watchpoint hit (`watch WATCHED` in gdb uses the
+//   very same comparator).
+// - Debug monitor, if C_DEBUGEN is clear and MON_EN is set, the DebugMonitor
+//   exception is raised, as in this example.
+//
+// C_DEBUGEN can only be changed by the debugger. If the core halts at the write
+// instead of printing, the debugger has enabled halting debug. With `probe-run`
+// (or openocd) resume the core, or run the program detached.
+//
+// The MASK allows watching a power of two sized, aligned range, e.g., MASK = 10
+// watches a 1 KB buffer. The F401 has 4 comparators (DWT_CTRL NUMCOMP).
@@ -0,0 +1,110 @@
+//! rtic_wfi.rs
+//!
+//! Low power idle
+//!
+//! What it covers:
+//! - putting the core to sleep in `idle` using `wfi` (Wait For Interrupt)
+//! - the interaction between sleep mode and the CYCCNT monotonic timer
+//!
+//! > cargo run --example rtic_wfi
+
+#![no_main]
+#![no_std]
+
+use cortex_m::asm;
+use panic_rtt_target as _;
+use rtic::cyccnt::{Instant, U32Ext as _};
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA5, Output, PushPull},
+    prelude::*,
+};
+
+use app::{schedule::try_reschedule, trace_init};
+
+const OFFSET: u32 = 8_000_000;
+
+// set to false to compare with a busy (spinning) idle
+const SLEEP: bool = true;
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        led: PA5<Output<PushPull>>,
+        // number of idle loop iterations since the last toggle
+        #[init(0)]
+        wakeups: u32,
+    }
+
+    #[init(schedule = [toggle])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT)
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        // Keep the core clock running in sleep mode, DBGMCU_CR, RM0368 chapter 23
+        // CYCCNT counts core clock cycles, and would otherwise stop during `wfi`.
+        device.DBGMCU.cr.modify(|_, w| w.dbg_sleep().set_bit());
+
+        let gpioa = device.GPIOA.split();
+        let led = gpioa.pa5.into_push_pull_output();
+
+        cx.schedule.toggle(cx.start + OFFSET.cycles()).unwrap();
+
+        init::LateResources { led }
+    }
+
+    #[idle(resources = [wakeups])]
+    fn idle(mut cx: idle::Context) -> ! {
+        rprintln!("idle, sleep {}", SLEEP);
+        loop {
+            if SLEEP {
+                // sleep until the next interrupt (e.g., SysTick for the timer queue)
+                asm::wfi();
+            }
+            cx.resources.wakeups.lock(|wakeups| *wakeups += 1);
+        }
+    }
+
+    #[task(resources = [led, wakeups], schedule = [toggle])]
+    fn toggle(cx: toggle::Context) {
+        rprintln!(
+            "toggle  @ {:?}, idle iterations {}",
+            Instant::now(),
+            cx.resources.wakeups
+        );
+        *cx.resources.wakeups = 0;
+
+        cx.resources.led.toggle().ok();
+        if !try_reschedule(cx.schedule.toggle(cx.scheduled + OFFSET.cycles()), "toggle") {
+            // the task stopped, leave the LED off
+            cx.resources.led.set_low().ok();
+        }
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Notes:
+//
+// With SLEEP = true, idle iterates only once per interrupt (a handful per toggle),
+// while with SLEEP = false it spins millions of times, burning power for nothing.
+//
+// RTIC uses SysTick to trigger the timer queue, and SysTick keeps running
+// in sleep mode. The CYCCNT however counts core clock cycles, and the core clock
+// is gated in sleep mode. Without DBG_SLEEP, CYCCNT only advances while the core
+// is awake, so scheduled tasks are delayed (time "stands still" while sleeping).
+//
+// `probe-run` (and openocd) typically set DBG_SLEEP when attached, which hides
+// the problem during development. Setting it explicitly keeps the behavior
+// the same with and without a debugger, at the cost of a higher sleep current.
+// If the lowest power is required, use a monotonic timer based on a peripheral
+// timer instead of CYCCNT (see `rtic_tim2_monotonic.rs`).
@@ -0,0 +1,191 @@
+//! rtic_ws2812.rs
+//!
+//! Bit-banged WS2812 ("NeoPixel") LED strip
+//!
+//! What it covers:
+//! - generating sub-microsecond pulses by busy waiting on CYCCNT
+//! - deriving the timing from the active SYSCLK
+//! - running timing critical code in a critical section
+//!
+//! > cargo run --example rtic_ws2812 --release
+//!
+//! Connect the strip DIN to PA7 (CN10-15), and supply the strip with 5V and GND
+//! (shared with the Nucleo). Many strips accept the 3.3V data level, otherwise
+//! use a level shifter (e.g., 74AHCT125).
+
+#![no_main]
+#![no_std]
+
+use cortex_m::{interrupt, peripheral::DWT};
+use panic_rtt_target as _;
+use rtic::cyccnt::U32Ext as _;
+use rtt_target::rprintln;
+use stm32f4xx_hal::{
+    gpio::{gpioa::PA7, Output, PushPull, Speed},
+    prelude::*,
+};
+
+use app::{delay::ms_to_cycles, trace::print_banner, trace_init};
+
+// number of LEDs on the strip
+const LEDS: usize = 8;
+// frame period in milliseconds
+const FRAME_MS: u32 = 50;
+
+// WS2812B timing in nanoseconds (data sheet, tolerance +/- 150 ns)
+const T0H_NS: u32 = 400;
+const T1H_NS: u32 = 800;
+const BIT_NS: u32 = 1_250;
+
+// Pulse timing in clock cycles, computed from SYSCLK
+#[derive(Clone, Copy)]
+struct Timing {
+    t0h: u32,
+    t1h: u32,
+    bit: u32,
+}
+
+impl Timing {
+    fn new(sysclk_hz: u32) -> Self {
+        let cycles = |ns: u32| (ns as u64 * sysclk_hz as u64 / 1_000_000_000) as u32;
+        Timing {
+            t0h: cycles(T0H_NS),
+            t1h: cycles(T1H_NS),
+            bit: cycles(BIT_NS),
+        }
+    }
+}
+
+#[rtic::app(device = stm32f4xx_hal::stm32, monotonic = rtic::cyccnt::CYCCNT, peripherals = true)]
+const APP: () = {
+    struct Resources {
+        // late resources
+        pin: PA7<Output<PushPull>>,
+        timing: Timing,
+        frame: u32,
+    }
+
+    #[init(schedule = [render])]
+    fn init(cx: init::Context) -> init::LateResources {
+        trace_init!();
+
+        let mut core = cx.core;
+        let device = cx.device;
+
+        // Initialize (enable) the monotonic timer (CYCCNT), also used for the pulses
+        core.DCB.enable_trace();
+        core.DWT.enable_cycle_counter();
+
+        let rcc = device.RCC.constrain();
+        let clocks = rcc.cfgr.sysclk(84.mhz()).freeze();
+        print_banner(clocks.sysclk().0);
+        let sysclk = clocks.sysclk().0;
+
+        let gpioa = device.GPIOA.split();
+        let mut pin = gpioa.pa7.into_push_pull_output().set_speed(Speed::VeryHigh);
+        pin.set_low().ok();
+
+        let timing = Timing::new(sysclk);
+        rprintln!(
+            "sysclk {} Hz, T0H {}, T1H {}, bit {} cycles",
+            sysclk,
+            timing.t0h,
+            timing.t1h,
+            timing.bit
+        );
+
+        let frame = ms_to_cycles(FRAME_MS, sysclk);
+        cx.schedule.render(cx.start + frame.cycles()).unwrap();
+
+        init::LateResources { pin, timing, frame }
+    }
+
+    #[idle]
+    fn idle(_cx: idle::Context) -> ! {
+        rprintln!("idle");
+        loop {
+            continue;
+        }
+    }
+
+    #[task(resources = [pin, timing, frame], schedule = [render])]
+    fn render(cx: render::Context) {
+        static mut STEP: u8 = 0;
+
+        // a rainbow, moving one step per frame
+        let mut colors = [[0u8; 3]; LEDS];
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = wheel(STEP.wrapping_add((i * 256 / LEDS) as u8));
+        }
+        *STEP = STEP.wrapping_add(4);
+
+        let pin = cx.resources.pin;
+        let timing = *cx.resources.timing;
+
+        // An interrupt in the middle of a bit stretches the pulse, which the
+        // LEDs either misread, or interpret as a reset (latch) after > 50 us.
+        interrupt::free(|_| {
+            for color in colors.iter() {
+                for byte in color.iter() {
+                    write_byte(pin, timing, *byte);
+                }
+            }
+        });
+        // the line is now kept low until the next frame, latching the colors
+
+        cx.schedule
+            .render(cx.scheduled + cx.resources.frame.cycles())
+            .unwrap();
+    }
+
+    extern "C" {
+        fn EXTI0();
+    }
+};
+
+// Send one byte, MSB first
+fn write_byte(pin: &mut PA7<Output<PushPull>>, timing: Timing, byte: u8) {
+    for bit in (0..8).rev() {
+        let high = if byte & (1 << bit) != 0 {
+            timing.t1h
+        } else {
+            timing.t0h
+        };
+
+        let start = DWT::get_cycle_count();
+        pin.set_high().ok();
+        while DWT::get_cycle_count().wrapping_sub(start) < high {}
+        pin.set_low().ok();
+        while DWT::get_cycle_count().wrapping_sub(start) < timing.bit {}
+    }
+}
+
+// Color wheel, 0..=255 -> GRB (the WS2812 byte order)
+fn wheel(pos: u8) -> [u8; 3] {
+    // reduced brightness, to save your eyes (and the power supply)
+    let scale = |v: u8| v / 8;
+    match pos {
+        0..=84 => [scale(pos * 3), scale(255 - pos * 3), 0],
+        85..=169 => {
+            let p = pos - 85;
+            [scale(255 - p * 3), 0, scale(p * 3)]
+        }
+        _ => {
+            let p = pos - 170;
+            [0, scale(p * 3), scale(255 - p * 3)]
+        }
+    }
+}
+
+// Notes:
+//
+// At 84 MHz a bit is 105 cycles, T0H 33 and T1H 67 cycles. The +/- 150 ns
+// tolerance is only ~12 cycles, so build with `--release` (debug builds are
+// too slow to hit the timing), and keep the loop body minimal.
+//
+// At 16 MHz a bit is only 20 cycles, too few for this loop, run at 48 MHz
+// or above. For a robust implementation, use SPI (3 or 4 SPI bits per WS2812
+// bit) or PWM with DMA, which frees the CPU and is immune to interrupts.
+//
+// A frame of 8 LEDs takes 8 x 24 x 1.25 us = 240 us, interrupts are thus
+// delayed by up to that amount, which may affect other tasks.
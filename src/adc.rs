@@ -0,0 +1,72 @@
+//! ADC sample conversions
+
+use crate::fixed::scale;
+
+/// Full scale of a 12 bit sample
+pub const FULL_SCALE_12BIT: u32 = 4095;
+
+/// Convert a 12 bit sample to millivolts, with `vref_mv` at full scale
+pub fn to_millivolts(raw: u16, vref_mv: u32) -> u16 {
+    scale(raw as u32, FULL_SCALE_12BIT, vref_mv) as u16
+}
+
+/// Scale a 12 bit sample to a PWM duty in `0..=max_duty`
+///
+/// Full scale maps to `max_duty` (fully on), values above full scale saturate.
+pub fn to_duty(raw: u16, max_duty: u16) -> u16 {
+    let raw = (raw as u32).min(FULL_SCALE_12BIT);
+    scale(raw, FULL_SCALE_12BIT, max_duty as u32) as u16
+}
+
+/// Convert a 12 bit temperature sensor sample to degrees Celsius
+///
+/// Linear interpolation between the factory calibration points `cal30`
+/// (TS_CAL1, 30 C) and `cal110` (TS_CAL2, 110 C), RM0368 11.9:
+///
+///   T = (110 - 30) / (TS_CAL2 - TS_CAL1) * (raw - TS_CAL1) + 30
+///
+/// The calibration values are measured at VDDA = 3.3V, as on the Nucleo.
+pub fn to_celsius(raw: u16, cal30: u16, cal110: u16) -> f32 {
+    let cal30 = cal30 as f32;
+    let cal110 = cal110 as f32;
+    (110.0 - 30.0) / (cal110 - cal30) * (raw as f32 - cal30) + 30.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn millivolts_at_the_ends() {
+        assert_eq!(to_millivolts(0, 3_300), 0);
+        assert_eq!(to_millivolts(4095, 3_300), 3_300);
+        assert_eq!(to_millivolts(4095, 3_000), 3_000);
+    }
+
+    #[test]
+    fn millivolts_mid_scale() {
+        assert_eq!(to_millivolts(2048, 3_000), 1_500);
+        assert_eq!(to_millivolts(1365, 3_000), 1_000);
+        // 1649.6, rounded to nearest
+        assert_eq!(to_millivolts(2047, 3_300), 1_650);
+    }
+
+    #[test]
+    fn duty_covers_the_full_range() {
+        assert_eq!(to_duty(0, 16_000), 0);
+        assert_eq!(to_duty(4095, 16_000), 16_000);
+        assert_eq!(to_duty(4095, u16::MAX), u16::MAX);
+        assert_eq!(to_duty(2048, 1_000), 500);
+        // saturates
+        assert_eq!(to_duty(u16::MAX, 1_000), 1_000);
+    }
+
+    #[test]
+    fn celsius_interpolates_the_calibration_points() {
+        assert_eq!(to_celsius(940, 940, 1200), 30.0);
+        assert_eq!(to_celsius(1200, 940, 1200), 110.0);
+        assert_eq!(to_celsius(1070, 940, 1200), 70.0);
+        // extrapolates below 30 C
+        assert_eq!(to_celsius(875, 940, 1200), 10.0);
+    }
+}
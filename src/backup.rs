@@ -0,0 +1,68 @@
+//! RTC backup registers, 20 words surviving a system reset
+//!
+//! The backup registers are in the backup domain, which is only reset by a
+//! power-on reset (if VBAT is not supplied) or a backup domain reset (BDRST).
+//! Writes are ignored until backup domain access is enabled.
+//!
+//! ```ignore
+//! enable_backup_access(&device.RCC, &device.PWR);
+//! let boots = bkp_read(&device.RTC, 0) + 1;
+//! bkp_write(&device.RTC, 0, boots);
+//! ```
+
+use stm32f2xx_hal::stm32::{PWR, RCC, RTC};
+
+/// RTC clock source, RCC_BDCR RTCSEL (RM0368 6.3.20)
+pub use stm32f2xx_hal::stm32::rcc::bdcr::RTCSEL_A as RtcClock;
+
+/// Number of backup registers, RTC_BKP0R..RTC_BKP19R
+pub const BKP_REGISTERS: usize = 20;
+
+/// Enable write access to the backup domain (backup registers, RTC, LSE)
+///
+/// - powers on PWR, RCC_APB1ENR PWREN (RM0368 6.3.13)
+/// - disables the write protection, PWR_CR DBP (RM0368 5.4.1)
+///
+/// The RTC clock is left as is, the backup registers are accessed through
+/// the APB interface and work without it.
+pub fn enable_backup_access(rcc: &RCC, pwr: &PWR) {
+    rcc.apb1enr.modify(|_, w| w.pwren().enabled());
+    pwr.cr.modify(|_, w| w.dbp().set_bit());
+}
+
+/// Select `source` as the RTC clock, unless one is already selected, and
+/// enable it (RCC_BDCR RTCSEL/RTCEN, RM0368 6.3.20)
+///
+/// RTCSEL is written once, it keeps its value until a backup domain reset
+/// (BDRST, or a power-on reset without VBAT). An already selected source,
+/// e.g., the LSE selected by `rtic_rtc.rs`, is kept. Returns the source in
+/// use, set the RTC prescalers accordingly.
+///
+/// The LSI is started if used (a system reset turns it off), the LSE and the
+/// HSE must already be running. Requires `enable_backup_access`.
+pub fn enable_rtc_clock(rcc: &RCC, source: RtcClock) -> RtcClock {
+    let selected = match rcc.bdcr.read().rtcsel().variant() {
+        RtcClock::NOCLOCK => source,
+        selected => selected,
+    };
+    if selected == RtcClock::LSI {
+        rcc.csr.modify(|_, w| w.lsion().on());
+        while rcc.csr.read().lsirdy().is_not_ready() {}
+    }
+    rcc.bdcr
+        .modify(|_, w| w.rtcsel().variant(selected).rtcen().enabled());
+    selected
+}
+
+/// Read backup register `idx`, panics if `idx >= BKP_REGISTERS`
+pub fn bkp_read(rtc: &RTC, idx: usize) -> u32 {
+    rtc.bkpr[idx].read().bkp().bits()
+}
+
+/// Write `value` to backup register `idx`, panics if `idx >= BKP_REGISTERS`
+///
+/// Silently ignored unless `enable_backup_access` has been called.
+pub fn bkp_write(rtc: &RTC, idx: usize, value: u32) {
+    // any value is valid
+    rtc.bkpr[idx].write(|w| unsafe { w.bkp().bits(value) });
+}
@@ -0,0 +1,58 @@
+//! Cycle accurate benchmarking using the DWT cycle counter (CYCCNT)
+//!
+//! The cycle counter must be enabled first:
+//!
+//! ```ignore
+//! core.DCB.enable_trace();
+//! core.DWT.enable_cycle_counter();
+//!
+//! calibrate();
+//! let cycles = cycle_count(|| { /* code to measure */ });
+//! ```
+//!
+//! Measure in `--release`, debug builds are dominated by the (unoptimized)
+//! overhead of the abstractions. Interrupts occurring during the measurement
+//! are included in the count, run within a critical section if that matters.
+//!
+//! The overhead (the CYCCNT reads) depends on the opt-level, the inlining and
+//! the flash wait states. `calibrate` measures it with an empty closure, and
+//! `cycle_count` subtracts it from then on. Uncalibrated, the raw count
+//! (including the overhead) is returned.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::peripheral::DWT;
+
+// Cycles spent reading CYCCNT, 0 until calibrated
+static OVERHEAD: AtomicU32 = AtomicU32::new(0);
+
+/// Measure the overhead with an empty closure, subtracted by `cycle_count`
+/// from then on, returns the overhead in cycles
+///
+/// Takes the minimum of a few runs, to skip runs hit by an interrupt.
+/// Calibrate again after changing the clock or the flash wait states.
+pub fn calibrate() -> u32 {
+    let overhead = (0..8).map(|_| raw_cycle_count(|| {})).min().unwrap_or(0);
+    OVERHEAD.store(overhead, Ordering::Relaxed);
+    overhead
+}
+
+/// The overhead subtracted by `cycle_count`, 0 until calibrated
+pub fn overhead() -> u32 {
+    OVERHEAD.load(Ordering::Relaxed)
+}
+
+/// Runs `f` and returns the number of clock cycles it took, excluding the
+/// calibrated overhead
+#[inline(always)]
+pub fn cycle_count<F: FnOnce()>(f: F) -> u32 {
+    raw_cycle_count(f).saturating_sub(overhead())
+}
+
+// Runs `f` and returns the number of clock cycles, including the overhead
+#[inline(always)]
+fn raw_cycle_count<F: FnOnce()>(f: F) -> u32 {
+    let start = DWT::get_cycle_count();
+    f();
+    let end = DWT::get_cycle_count();
+    end.wrapping_sub(start)
+}
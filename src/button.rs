@@ -0,0 +1,89 @@
+//! Button input configuration, internal pull resistors and the matching EXTI edge
+//!
+//! The pull resistor defines the level of an open (released) button, and thereby
+//! which edge a press gives:
+//!
+//! | pull     | wiring          | released  | pressed | press edge |
+//! |----------|-----------------|-----------|---------|------------|
+//! | up       | button to GND   | high      | low     | falling    |
+//! | down     | button to 3V3   | low       | high    | rising     |
+//! | floating | external pull   | undefined | -       | -          |
+
+/// Internal pull resistor of an input pin, GPIOx_PUPDR (RM0368 8.4.4)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pull {
+    Floating,
+    Up,
+    Down,
+}
+
+/// The EXTI edge of a button press
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PressEdge {
+    Rising,
+    Falling,
+}
+
+impl Pull {
+    /// The 2 bit PUPDR field value
+    pub fn pupdr_bits(self) -> u32 {
+        match self {
+            Pull::Floating => 0b00,
+            Pull::Up => 0b01,
+            Pull::Down => 0b10,
+        }
+    }
+
+    /// The PUPDR register value with the field of `pin` (0..=15) set to `self`
+    pub fn pupdr(self, pupdr: u32, pin: u8) -> u32 {
+        let offset = 2 * pin as u32;
+        (pupdr & !(0b11 << offset)) | (self.pupdr_bits() << offset)
+    }
+
+    /// Level of a released button, `None` if given by the external circuit
+    pub fn idle_high(self) -> Option<bool> {
+        match self {
+            Pull::Floating => None,
+            Pull::Up => Some(true),
+            Pull::Down => Some(false),
+        }
+    }
+
+    /// The edge to trigger on for a press, `None` if given by the external circuit
+    pub fn press_edge(self) -> Option<PressEdge> {
+        match self.idle_high()? {
+            true => Some(PressEdge::Falling),
+            false => Some(PressEdge::Rising),
+        }
+    }
+
+    /// Name for printing
+    pub fn name(self) -> &'static str {
+        match self {
+            Pull::Floating => "floating",
+            Pull::Up => "pull-up",
+            Pull::Down => "pull-down",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_edge_opposes_idle_level() {
+        assert_eq!(Pull::Up.press_edge(), Some(PressEdge::Falling));
+        assert_eq!(Pull::Down.press_edge(), Some(PressEdge::Rising));
+        assert_eq!(Pull::Floating.press_edge(), None);
+    }
+
+    #[test]
+    fn pupdr_sets_only_the_pin_field() {
+        assert_eq!(Pull::Up.pupdr(0, 0), 0b01);
+        assert_eq!(Pull::Down.pupdr(0, 13), 0b10 << 26);
+        // clears the previous setting, keeps the other pins
+        assert_eq!(Pull::Up.pupdr(0xFFFF_FFFF, 1), 0xFFFF_FFF7);
+        assert_eq!(Pull::Floating.pupdr(0b10_01, 1), 0b00_01);
+    }
+}
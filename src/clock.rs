@@ -0,0 +1,260 @@
+//! System clock configuration
+//!
+//! One place to set up the clocks, wrapping the HAL `cfgr` builder.
+//!
+//! ```ignore
+//! let clocks = SysClkConfig::new().sysclk(48).pclk1(24).apply(device.RCC);
+//! ```
+
+use core::fmt;
+use rtt_target::rprintln;
+use stm32f2xx_hal::{prelude::*, rcc::Clocks, stm32::RCC};
+
+/// HSI (internal RC oscillator) frequency in MHz, SYSCLK without the PLL
+pub const HSI_MHZ: u32 = 16;
+/// Maximum SYSCLK in MHz (STM32F401)
+pub const SYSCLK_MAX_MHZ: u32 = 84;
+/// Minimum PLL VCO output frequency in MHz, RM0368 6.3.2
+pub const VCO_MIN_MHZ: u32 = 192;
+/// Maximum PLL VCO output frequency in MHz
+pub const VCO_MAX_MHZ: u32 = 432;
+/// PLL output division factors (PLLP)
+const PLLP: [u32; 4] = [2, 4, 6, 8];
+
+/// Reason why a SYSCLK frequency cannot be produced
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockError {
+    /// Above the maximum SYSCLK
+    TooHigh { requested_mhz: u32 },
+    /// No PLLP gives a VCO frequency within range (below 24 MHz)
+    NoPllSolution { requested_mhz: u32 },
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClockError::TooHigh { requested_mhz } => write!(
+                f,
+                "sysclk {} MHz exceeds the maximum {} MHz",
+                requested_mhz, SYSCLK_MAX_MHZ
+            ),
+            ClockError::NoPllSolution { requested_mhz } => write!(
+                f,
+                "sysclk {} MHz x PLLP (2, 4, 6, 8) is outside the VCO range {}..{} MHz",
+                requested_mhz, VCO_MIN_MHZ, VCO_MAX_MHZ
+            ),
+        }
+    }
+}
+
+/// Check that `requested_mhz` can be produced (exactly) from the HSI
+///
+/// Either the HSI is used directly (16 MHz), or the PLL produces
+/// SYSCLK = VCO / PLLP, with VCO = 1 MHz x PLLN (PLLM divides the HSI to 1 MHz).
+/// The HAL `freeze` silently picks the nearest frequency otherwise.
+pub fn validate_sysclk(requested_mhz: u32) -> Result<(), ClockError> {
+    if requested_mhz == HSI_MHZ {
+        return Ok(());
+    }
+    if requested_mhz > SYSCLK_MAX_MHZ {
+        return Err(ClockError::TooHigh { requested_mhz });
+    }
+    let vco_in_range = PLLP.iter().any(|p| {
+        let vco = requested_mhz * p;
+        vco >= VCO_MIN_MHZ && vco <= VCO_MAX_MHZ
+    });
+    if vco_in_range {
+        Ok(())
+    } else {
+        Err(ClockError::NoPllSolution { requested_mhz })
+    }
+}
+
+/// HSE startup timeout in polling iterations (a few ms at 16 MHz)
+///
+/// A crystal typically starts within 2 ms, see the data sheet (tSU(HSE)).
+pub const HSE_TIMEOUT: u32 = 100_000;
+
+/// Start the HSE and wait (at most `timeout` iterations) for HSERDY
+///
+/// Returns `true` if the HSE is running. Otherwise the HSE is turned off
+/// again, and the caller should stay on the HSI. Call before `constrain`.
+///
+/// The HAL `use_hse` waits for HSERDY forever, so on a board without a
+/// clock at OSC_IN it hangs in `freeze`. Use this check first:
+///
+/// ```ignore
+/// let hse = start_hse(&device.RCC, true, HSE_TIMEOUT);
+/// let cfgr = device.RCC.constrain().cfgr;
+/// let cfgr = if hse { cfgr.use_hse(8.mhz()) } else { cfgr };
+/// ```
+///
+/// With `bypass`, OSC_IN is driven by an external clock (not a crystal). On the
+/// Nucleo, this is the 8 MHz MCO output of the ST-LINK (see UM1724 6.7.1).
+/// HSEBYP is left set, which the HAL does not touch.
+pub fn start_hse(rcc: &RCC, bypass: bool, timeout: u32) -> bool {
+    // HSEBYP can only be written while the HSE is off, RCC_CR, RM0368 6.3.1
+    rcc.cr.modify(|_, w| w.hseon().off());
+    rcc.cr.modify(|_, w| w.hsebyp().bit(bypass));
+    rcc.cr.modify(|_, w| w.hseon().on());
+
+    for _ in 0..timeout {
+        if rcc.cr.read().hserdy().is_ready() {
+            return true;
+        }
+    }
+
+    rcc.cr.modify(|_, w| w.hseon().off());
+    false
+}
+
+/// Requested clock frequencies in MHz, `None` leaves the HAL default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SysClkConfig {
+    sysclk: Option<u32>,
+    pclk1: Option<u32>,
+    pclk2: Option<u32>,
+}
+
+impl SysClkConfig {
+    /// Default configuration, all clocks driven by the 16 MHz HSI
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Core clock (SYSCLK) in MHz
+    pub fn sysclk(mut self, mhz: u32) -> Self {
+        self.sysclk = Some(mhz);
+        self
+    }
+
+    /// APB1 (low speed bus) clock in MHz
+    pub fn pclk1(mut self, mhz: u32) -> Self {
+        self.pclk1 = Some(mhz);
+        self
+    }
+
+    /// APB2 (high speed bus) clock in MHz
+    pub fn pclk2(mut self, mhz: u32) -> Self {
+        self.pclk2 = Some(mhz);
+        self
+    }
+
+    /// Freeze the configuration, and log the achieved frequencies over RTT
+    ///
+    /// Warns if the requested SYSCLK is invalid, or differs from the achieved one.
+    pub fn apply(self, rcc: RCC) -> Clocks {
+        let mut cfgr = rcc.constrain().cfgr;
+
+        if let Some(mhz) = self.sysclk {
+            if let Err(err) = validate_sysclk(mhz) {
+                rprintln!("warning: {}", err);
+            }
+            cfgr = cfgr.sysclk(mhz.mhz());
+        }
+        if let Some(mhz) = self.pclk1 {
+            cfgr = cfgr.pclk1(mhz.mhz());
+        }
+        if let Some(mhz) = self.pclk2 {
+            cfgr = cfgr.pclk2(mhz.mhz());
+        }
+
+        let clocks = cfgr.freeze();
+
+        rprintln!(
+            "sysclk {} Hz, pclk1 {} Hz, pclk2 {} Hz",
+            clocks.sysclk().0,
+            clocks.pclk1().0,
+            clocks.pclk2().0
+        );
+
+        if let Some(mhz) = self.sysclk {
+            if clocks.sysclk().0 != mhz * 1_000_000 {
+                rprintln!(
+                    "warning: requested sysclk {} MHz, achieved {} Hz",
+                    mhz,
+                    clocks.sysclk().0
+                );
+            }
+        }
+
+        clocks
+    }
+}
+
+/// The timer kernel clock of an APB bus in Hz, RM0368 6.2
+///
+/// The timers run at PCLK if the APB prescaler (`ppre`) is 1, and at twice
+/// PCLK otherwise. E.g., with SYSCLK 84 MHz and PCLK1 42 MHz (APB1 divided
+/// by 2), TIM2..TIM5 count at 84 MHz, not at 42 MHz.
+pub fn timer_clock(pclk_hz: u32, ppre: u8) -> u32 {
+    if ppre == 1 {
+        pclk_hz
+    } else {
+        2 * pclk_hz
+    }
+}
+
+/// Print all the derived clocks (after `freeze`) as a table over RTT
+///
+/// The timer clocks follow from the APB prescalers, see `timer_clock`.
+pub fn dump_clocks(clocks: &Clocks) {
+    let pclk1 = clocks.pclk1().0;
+    let pclk2 = clocks.pclk2().0;
+    rprintln!("clock        Hz          prescaler");
+    rprintln!("SYSCLK  {:>12}", clocks.sysclk().0);
+    rprintln!("HCLK    {:>12}", clocks.hclk().0);
+    rprintln!("PCLK1   {:>12}   APB1 /{}", pclk1, clocks.ppre1());
+    rprintln!("PCLK2   {:>12}   APB2 /{}", pclk2, clocks.ppre2());
+    rprintln!(
+        "TIMCLK1 {:>12}   TIM2..TIM5 (x2 if APB1 /{} > 1)",
+        timer_clock(pclk1, clocks.ppre1()),
+        clocks.ppre1()
+    );
+    rprintln!(
+        "TIMCLK2 {:>12}   TIM1, TIM9..TIM11 (x2 if APB2 /{} > 1)",
+        timer_clock(pclk2, clocks.ppre2()),
+        clocks.ppre2()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_sysclk() {
+        // HSI directly
+        assert_eq!(validate_sysclk(16), Ok(()));
+        // PLL, VCO = 336 MHz / 4, 192 MHz / 4, 192 MHz / 8
+        assert_eq!(validate_sysclk(84), Ok(()));
+        assert_eq!(validate_sysclk(48), Ok(()));
+        assert_eq!(validate_sysclk(24), Ok(()));
+    }
+
+    #[test]
+    fn sysclk_too_high() {
+        assert_eq!(
+            validate_sysclk(100),
+            Err(ClockError::TooHigh { requested_mhz: 100 })
+        );
+    }
+
+    #[test]
+    fn sysclk_without_pll_solution() {
+        // 20 MHz x 8 = 160 MHz, below the VCO range
+        assert_eq!(
+            validate_sysclk(20),
+            Err(ClockError::NoPllSolution { requested_mhz: 20 })
+        );
+    }
+
+    #[test]
+    fn timer_clock_doubles_if_divided() {
+        // APB not divided, the timers run at PCLK
+        assert_eq!(timer_clock(16_000_000, 1), 16_000_000);
+        // SYSCLK 84 MHz, APB1 / 2
+        assert_eq!(timer_clock(42_000_000, 2), 84_000_000);
+        assert_eq!(timer_clock(21_000_000, 4), 42_000_000);
+    }
+}
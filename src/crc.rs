@@ -0,0 +1,48 @@
+//! Software CRC32, matching the STM32 CRC peripheral
+//!
+//! The CRC unit (RM0368 chapter 4) uses a fixed configuration:
+//!
+//! - polynomial 0x04C1_1DB7 (as Ethernet CRC32)
+//! - initial value 0xFFFF_FFFF (after CR RESET)
+//! - 32 bit words as input, MSB first, no bit reflection, no final XOR
+//!
+//! This is CRC-32/MPEG-2 over the words in big endian byte order, which is
+//! *not* the "standard" CRC32 (zip, Ethernet) that reflects bits and inverts
+//! the result.
+
+/// CRC polynomial of the STM32 CRC unit
+pub const POLY: u32 = 0x04C1_1DB7;
+
+/// CRC32 of `data`, the same as the CRC peripheral computes
+pub fn crc32(data: &[u32]) -> u32 {
+    let mut crc = 0xFFFF_FFFF;
+    for word in data {
+        crc ^= word;
+        for _ in 0..32 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ POLY
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_reference_values() {
+        // a single word, the commonly quoted STM32 reference value
+        assert_eq!(crc32(&[0x1234_5678]), 0xDF8A_8A2B);
+        // "12345678" as big endian words
+        assert_eq!(crc32(&[0x3132_3334, 0x3536_3738]), 0x49E3_C2FB);
+    }
+
+    #[test]
+    fn crc32_empty() {
+        assert_eq!(crc32(&[]), 0xFFFF_FFFF);
+    }
+}
@@ -0,0 +1,100 @@
+//! Time to clock cycle conversions
+//!
+//! Express delays in real time, and compute the cycle count (e.g., for
+//! `cx.schedule` using CYCCNT) from the active SYSCLK frequency.
+//!
+//! ```ignore
+//! let offset = ms_to_cycles(500, clocks.sysclk().0);
+//! cx.schedule.toggle(cx.start + offset.cycles()).unwrap();
+//! ```
+
+/// Deadline `offset` cycles after `scheduled`, on the wrapping 32 bit CYCCNT
+///
+/// At 84 MHz the CYCCNT wraps every ~51 s, so a periodic task computing
+/// `scheduled + offset` will sooner or later cross the wrap point. This is
+/// what `Instant + Duration` does in RTIC (a wrapping add), it is correct as
+/// long as deadlines are compared using `is_due` (not `>=` on the raw counts).
+pub fn next_deadline(scheduled: u32, offset: u32) -> u32 {
+    scheduled.wrapping_add(offset)
+}
+
+/// True if `deadline` has been reached at `now`
+///
+/// The difference is interpreted as signed, as RTIC does for `Instant`, which
+/// is correct for deadlines less than 2^31 cycles (~25 s at 84 MHz) away.
+pub fn is_due(now: u32, deadline: u32) -> bool {
+    now.wrapping_sub(deadline) as i32 >= 0
+}
+
+/// Number of clock cycles for `ms` milliseconds at `sysclk_hz`
+///
+/// The result must fit in 32 bits, e.g., at most ~51 s at 84 MHz.
+pub fn ms_to_cycles(ms: u32, sysclk_hz: u32) -> u32 {
+    (ms as u64 * sysclk_hz as u64 / 1_000) as u32
+}
+
+/// Number of clock cycles for `us` microseconds at `sysclk_hz`
+pub fn us_to_cycles(us: u32, sysclk_hz: u32) -> u32 {
+    (us as u64 * sysclk_hz as u64 / 1_000_000) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ms_to_cycles_exact() {
+        assert_eq!(ms_to_cycles(1_000, 48_000_000), 48_000_000);
+        assert_eq!(ms_to_cycles(500, 84_000_000), 42_000_000);
+        assert_eq!(ms_to_cycles(1, 16_000_000), 16_000);
+        assert_eq!(ms_to_cycles(0, 84_000_000), 0);
+    }
+
+    #[test]
+    fn ms_to_cycles_no_intermediate_overflow() {
+        // ms * sysclk_hz does not fit in 32 bits, the result does
+        assert_eq!(ms_to_cycles(50_000, 84_000_000), 4_200_000_000);
+    }
+
+    #[test]
+    fn us_to_cycles_exact() {
+        assert_eq!(us_to_cycles(1, 16_000_000), 16);
+        assert_eq!(us_to_cycles(10, 84_000_000), 840);
+        assert_eq!(us_to_cycles(1_000_000, 48_000_000), 48_000_000);
+    }
+
+    #[test]
+    fn next_deadline_wraps() {
+        assert_eq!(next_deadline(100, 50), 150);
+        assert_eq!(next_deadline(u32::MAX - 9, 10), 0);
+        assert_eq!(next_deadline(u32::MAX - 9, 20), 10);
+        assert_eq!(next_deadline(u32::MAX, u32::MAX), u32::MAX - 1);
+    }
+
+    #[test]
+    fn is_due_across_wrap() {
+        let scheduled = u32::MAX - 100;
+        let deadline = next_deadline(scheduled, 1_000);
+        // a raw `now >= deadline` would claim the deadline is reached already
+        assert!(scheduled >= deadline);
+        assert!(!is_due(scheduled, deadline));
+        assert!(!is_due(u32::MAX, deadline));
+        assert!(!is_due(deadline - 1, deadline));
+        assert!(is_due(deadline, deadline));
+        assert!(is_due(deadline + 1, deadline));
+    }
+
+    #[test]
+    fn periodic_deadlines_keep_the_period() {
+        // a 500 ms period at 84 MHz, for more than one wrap (~51 s)
+        let offset = ms_to_cycles(500, 84_000_000);
+        let mut scheduled = 0u32;
+        for _ in 0..250 {
+            let deadline = next_deadline(scheduled, offset);
+            assert_eq!(deadline.wrapping_sub(scheduled), offset);
+            assert!(!is_due(scheduled, deadline));
+            assert!(is_due(deadline, deadline));
+            scheduled = deadline;
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Integer scaling, for sensor conversions without floating point
+//!
+//! Maps `0..=in_max` linearly onto `0..=out_max`, e.g., ADC counts to
+//! millivolts, or encoder counts to degrees:
+//!
+//! ```ignore
+//! let mv = scale(raw as u32, 4095, 3_300);
+//! let degrees = scale_i32(position, COUNTS_PER_REV, 360);
+//! ```
+//!
+//! The product `value x out_max` is computed in 64 bits, so it cannot
+//! overflow for any 32 bit inputs, and the result is rounded to the nearest
+//! integer (instead of truncated, which is biased towards zero).
+
+/// Scale `value` from `0..=in_max` to `0..=out_max`, rounded to nearest
+///
+/// Values above `in_max` extrapolate, saturating at `u32::MAX`.
+/// Panics if `in_max` is 0.
+pub fn scale(value: u32, in_max: u32, out_max: u32) -> u32 {
+    let (value, in_max, out_max) = (value as u64, in_max as u64, out_max as u64);
+    let scaled = (value * out_max + in_max / 2) / in_max;
+    scaled.min(u32::MAX as u64) as u32
+}
+
+/// Scale a signed `value` by `out_max / in_max`, rounded to nearest
+///
+/// Halves round away from zero, so that `-x` scales to the negation of `x`.
+/// Saturates at the `i32` range. Panics if `in_max` is 0.
+pub fn scale_i32(value: i32, in_max: u32, out_max: u32) -> i32 {
+    let (value, in_max, out_max) = (value as i64, in_max as i64, out_max as i64);
+    let product = value * out_max;
+    let half = in_max / 2;
+    let scaled = if product < 0 {
+        (product - half) / in_max
+    } else {
+        (product + half) / in_max
+    };
+    scaled.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_ends() {
+        assert_eq!(scale(0, 4095, 3_300), 0);
+        assert_eq!(scale(4095, 4095, 3_300), 3_300);
+        assert_eq!(scale(4095, 4095, 0), 0);
+    }
+
+    #[test]
+    fn scale_rounds_to_nearest() {
+        // 2/3 = 0.67
+        assert_eq!(scale(1, 3, 2), 1);
+        // 2/4 = 0.5, halves round up
+        assert_eq!(scale(1, 4, 2), 1);
+        // 2/5 = 0.4
+        assert_eq!(scale(1, 5, 2), 0);
+        // 2048 x 3300 / 4095 = 1650.4, truncation would give the same,
+        // 2047 x 3300 / 4095 = 1649.6 would truncate to 1649
+        assert_eq!(scale(2048, 4095, 3_300), 1_650);
+        assert_eq!(scale(2047, 4095, 3_300), 1_650);
+    }
+
+    #[test]
+    fn scale_does_not_overflow() {
+        // the product is close to 2^64
+        assert_eq!(scale(u32::MAX, u32::MAX, u32::MAX), u32::MAX);
+        assert_eq!(scale(u32::MAX - 1, u32::MAX, u32::MAX), u32::MAX - 1);
+        assert_eq!(scale(u32::MAX, u32::MAX, 1), 1);
+        // 32 bit arithmetic would overflow (4e9 x 1000)
+        assert_eq!(scale(4_000_000_000, 4_000_000_000, 1_000), 1_000);
+        assert_eq!(scale(1, u32::MAX, u32::MAX), 1);
+    }
+
+    #[test]
+    fn scale_saturates() {
+        // extrapolates above in_max
+        assert_eq!(scale(8, 4, 10), 20);
+        assert_eq!(scale(u32::MAX, 1, 2), u32::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn scale_zero_range() {
+        scale(1, 0, 1);
+    }
+
+    #[test]
+    fn scale_i32_is_symmetric() {
+        // 80 counts per revolution to degrees
+        assert_eq!(scale_i32(20, 80, 360), 90);
+        assert_eq!(scale_i32(-20, 80, 360), -90);
+        // 1 count is 4.5 degrees, halves round away from zero
+        assert_eq!(scale_i32(1, 80, 360), 5);
+        assert_eq!(scale_i32(-1, 80, 360), -5);
+        // multiple turns
+        assert_eq!(scale_i32(-200, 80, 360), -900);
+        assert_eq!(scale_i32(0, 80, 360), 0);
+    }
+
+    #[test]
+    fn scale_i32_extremes() {
+        assert_eq!(scale_i32(i32::MAX, u32::MAX, u32::MAX), i32::MAX);
+        assert_eq!(scale_i32(i32::MIN, u32::MAX, u32::MAX), i32::MIN);
+        // saturates
+        assert_eq!(scale_i32(i32::MAX, 1, 2), i32::MAX);
+        assert_eq!(scale_i32(i32::MIN, 1, 2), i32::MIN);
+    }
+}
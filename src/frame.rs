@@ -0,0 +1,70 @@
+//! Framing of 16 bit samples for streaming to a host
+//!
+//! Each sample is sent as a 4 byte frame:
+//!
+//! | byte | content                           |
+//! |------|-----------------------------------|
+//! | 0    | `SYNC` (0xA5)                     |
+//! | 1    | sample, low byte                  |
+//! | 2    | sample, high byte                 |
+//! | 3    | checksum, low byte XOR high byte  |
+//!
+//! A receiver scans for `SYNC`, reads the next 3 bytes, and accepts the frame
+//! if the checksum matches, otherwise it drops the first byte and rescans.
+//! The sample itself may contain 0xA5, the checksum rejects such false syncs
+//! (most of the time). In Python:
+//!
+//! ```text
+//! sync, lo, hi, chk = buf[i:i + 4]
+//! if sync == 0xA5 and lo ^ hi == chk:
+//!     value = lo | hi << 8
+//! ```
+
+/// Start of frame marker
+pub const SYNC: u8 = 0xA5;
+
+/// Length of a frame in bytes
+pub const FRAME_LEN: usize = 4;
+
+/// The frame for sample `v`
+pub fn frame_sample(v: u16) -> [u8; FRAME_LEN] {
+    let [lo, hi] = v.to_le_bytes();
+    [SYNC, lo, hi, lo ^ hi]
+}
+
+/// The sample in `frame`, `None` if the sync byte or the checksum is wrong
+pub fn parse_frame(frame: &[u8; FRAME_LEN]) -> Option<u16> {
+    let [sync, lo, hi, checksum] = *frame;
+    if sync == SYNC && lo ^ hi == checksum {
+        Some(u16::from_le_bytes([lo, hi]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_layout() {
+        assert_eq!(frame_sample(0x0000), [0xA5, 0x00, 0x00, 0x00]);
+        assert_eq!(frame_sample(0x0FFF), [0xA5, 0xFF, 0x0F, 0xF0]);
+        assert_eq!(frame_sample(0x1234), [0xA5, 0x34, 0x12, 0x26]);
+    }
+
+    #[test]
+    fn round_trip() {
+        for v in [0, 1, 0x00A5, 0x0800, 0x0FFF, u16::MAX].iter() {
+            assert_eq!(parse_frame(&frame_sample(*v)), Some(*v));
+        }
+    }
+
+    #[test]
+    fn corrupted_frames_are_rejected() {
+        let mut frame = frame_sample(0x0123);
+        frame[1] ^= 0x01;
+        assert_eq!(parse_frame(&frame), None);
+        assert_eq!(parse_frame(&[0x00, 0x23, 0x01, 0x22]), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! LED wrapper, generic over the output pin
+//!
+//! Keeps track of the LED state, so tasks don't need a separate `static mut` flag.
+//!
+//! ```ignore
+//! let mut led = Led::new(gpioa.pa5.into_push_pull_output());
+//! led.toggle().ok();
+//! ```
+
+use embedded_hal::digital::v2::OutputPin;
+
+/// An LED (active high) connected to `PIN`
+pub struct Led<PIN> {
+    pin: PIN,
+    on: bool,
+}
+
+impl<PIN> Led<PIN>
+where
+    PIN: OutputPin,
+{
+    /// Wraps `pin`, the LED is initially turned off
+    pub fn new(mut pin: PIN) -> Self {
+        pin.set_low().ok();
+        Self { pin, on: false }
+    }
+
+    /// Turns the LED on
+    pub fn on(&mut self) -> Result<(), PIN::Error> {
+        self.pin.set_high()?;
+        self.on = true;
+        Ok(())
+    }
+
+    /// Turns the LED off
+    pub fn off(&mut self) -> Result<(), PIN::Error> {
+        self.pin.set_low()?;
+        self.on = false;
+        Ok(())
+    }
+
+    /// Toggles the LED, based on the tracked state
+    pub fn toggle(&mut self) -> Result<(), PIN::Error> {
+        if self.on {
+            self.off()
+        } else {
+            self.on()
+        }
+    }
+
+    /// Returns `true` if the LED is on
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Releases the pin
+    pub fn free(self) -> PIN {
+        self.pin
+    }
+}
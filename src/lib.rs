@@ -1,7 +1,74 @@
-#![no_std]
+//! Reusable, `no_std` building blocks for the examples
+//!
+//! The library is named after the package (`app`), import in examples by:
+//!
+//! ```ignore
+//! use app::{clock::SysClkConfig, delay::ms_to_cycles, led::Led, trace_init};
+//! ```
+//!
+//! - `backup`, RTC backup registers, persisting across system resets
+//! - `button`, pull resistor configuration and the matching EXTI edge
+//! - `clock`, system clock setup and validation
+//! - `crc`, software CRC32 matching the CRC peripheral
+//! - `delay`, time to clock cycle conversions (and `DwtDelay` below)
+//! - `fixed`, integer scaling (64 bit intermediates, rounded) for sensor conversions
+//! - `frame`, framing of 16 bit samples (sync byte, checksum) for streaming
+//! - `led`, LED wrapper generic over the output pin
+//! - `log`, logging macro with RTT/semihosting/defmt backend (feature `rtt`/`semihosting`/`defmt-log`)
+//! - `mco`, routing internal clocks to the MCO1/MCO2 pins
+//! - `menu`, menu state and its rendering as LED blinks
+//! - `monotonic`, TIM2 as RTIC monotonic timer (counts in sleep mode)
+//! - `morse`, morse code encoding and on/off timing
+//! - `probe`, GPIO scope marker bracketing code under measurement
+//! - `profile`, task execution times in a history buffer (on-target profiler)
+//! - `rand`, xorshift pseudo random numbers for demos (not secure)
+//! - `rdp`, the read-out protection level from the option bytes (read only)
+//! - `reset`, software reset and decoding the reset cause
+//! - `schedule`, `try_reschedule`, logging instead of panicking on a full task queue
+//! - `selftest`, startup check of the clocks and a visual POST
+//! - `stack`, stack painting and the high-water mark (peak stack usage)
+//! - `timer`, timer prescaler (PSC) and auto-reload (ARR) for an update rate
+//! - `trace`, RTT setup (`trace_init!`)
+//! - `usart`, baud rate register (BRR) computation
+//! - `vtimer`, virtual (software) timers on one periodic tick
+//!
+//! The hardware independent modules have host tests, see the README.
 
+#![cfg_attr(not(test), no_std)]
+
+pub mod adc;
+pub mod backup;
+pub mod bench;
+pub mod button;
+pub mod clock;
+pub mod crc;
+pub mod delay;
+pub mod fixed;
+pub mod frame;
+pub mod led;
+#[cfg(feature = "log")]
+pub mod log;
+pub mod mco;
+pub mod menu;
+pub mod monotonic;
+pub mod morse;
+#[cfg(feature = "panic-rtt")]
+pub mod panic;
+pub mod pattern;
 pub mod pmw3389;
 pub mod pmw3389e;
+pub mod probe;
+pub mod profile;
+pub mod rand;
+pub mod rdp;
+pub mod reset;
+pub mod schedule;
+pub mod selftest;
+pub mod stack;
+pub mod timer;
+pub mod trace;
+pub mod usart;
+pub mod vtimer;
 
 use stm32f2xx_hal::{prelude::*, rcc::Clocks, stm32};
 
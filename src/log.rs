@@ -0,0 +1,221 @@
+//! Logging with a build time selected backend
+//!
+//! `log!` has the same syntax as `rprintln!`/`hprintln!`, the output goes
+//! to RTT or semihosting depending on the enabled Cargo feature:
+//!
+//! > cargo run --example rtic_hello --features rtt
+//!
+//! > cargo run --example rtic_hello --features semihosting
+//!
+//! > cargo run --example rtic_hello --features defmt-log
+//!
+//! Each backend feature implies the `log` feature, list the examples using
+//! `log!` under `[[example]]` with `required-features = ["log"]` in
+//! `Cargo.toml`, so that a plain `cargo build --examples` skips them.
+//!
+//! The feature selects the output only, not the panic handler. An example
+//! using `log!` picks the handler matching the backend:
+//!
+//! ```ignore
+//! #[cfg(feature = "defmt-log")]
+//! use panic_probe as _;
+//! #[cfg(feature = "rtt")]
+//! use panic_rtt_target as _;
+//! #[cfg(feature = "semihosting")]
+//! use panic_semihosting as _;
+//! ```
+//!
+//! With `defmt-log`, `log!` expands to `defmt::info!`. The format string is
+//! stored in the ELF (`.defmt` section) and only the arguments are sent over
+//! RTT (by `defmt-rtt`), which is both faster and smaller than `rprintln!`.
+//! The host (`probe-run`) decodes the messages using the ELF. Notice:
+//!
+//! - the format syntax is defmt's, `{}` works for primitives and types
+//!   implementing `defmt::Format` (not `core::fmt::Debug`/`Display`)
+//! - the `.defmt` section is placed by the `defmt.x` linker script, uncomment
+//!   the `-Tdefmt.x` line in `.cargo/config` (or pass it in `RUSTFLAGS`)
+//! - the log level is selected by the `defmt-default`/`defmt-trace`/...
+//!   features in `Cargo.toml`
+//! - (the feature cannot be named `defmt`, as the dependency is)
+//!
+//! > RUSTFLAGS="-C link-arg=-Tdefmt.x" cargo run --example rtic_hello --features defmt-log
+//!
+//! ```ignore
+//! use app::{log, log_init};
+//!
+//! #[init]
+//! fn init(_cx: init::Context) {
+//!     log_init!();
+//!     log!("hello {}", 42);
+//! }
+//! ```
+//!
+//! Leveled logging, `error!`, `warn!`, `info!` and `debug!`, prefixes the
+//! message with the level, and drops messages below `MAX_LEVEL`. The check
+//! is on constants, so in a release build the disabled calls (including their
+//! format strings) are optimized out entirely.
+//!
+//! ```ignore
+//! use app::{debug, info};
+//!
+//! info!("sysclk {} Hz", clocks.sysclk().0);
+//! debug!("cfgr 0x{:08x}", cfgr);
+//! ```
+
+#[cfg(any(
+    all(feature = "rtt", feature = "semihosting"),
+    all(feature = "rtt", feature = "defmt-log"),
+    all(feature = "semihosting", feature = "defmt-log"),
+))]
+compile_error!("enable only one of the `rtt`, `semihosting` and `defmt-log` features");
+
+#[cfg(not(any(feature = "rtt", feature = "semihosting", feature = "defmt-log")))]
+compile_error!("enable one of the `rtt`, `semihosting` and `defmt-log` features");
+
+// re-exported for use in the macros
+#[cfg(feature = "semihosting")]
+#[doc(hidden)]
+pub use cortex_m_semihosting;
+#[cfg(feature = "rtt")]
+#[doc(hidden)]
+pub use rtt_target;
+#[cfg(feature = "defmt-log")]
+#[doc(hidden)]
+pub use defmt;
+
+// the defmt transport (owns the RTT control block)
+#[cfg(feature = "defmt-log")]
+use defmt_rtt as _;
+
+// defmt timestamp, the CYCCNT (0 until the cycle counter is enabled)
+#[cfg(feature = "defmt-log")]
+defmt::timestamp!("{=u32}", cortex_m::peripheral::DWT::get_cycle_count());
+
+/// Log level, in order of decreasing severity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// The least severe level logged, everything in dev builds, no `debug!` in release
+///
+/// Change to, e.g., `Level::Warn` to silence an example.
+pub const MAX_LEVEL: Level = if cfg!(debug_assertions) {
+    Level::Debug
+} else {
+    Level::Info
+};
+
+/// Initialize the logging backend, call first thing in `init`
+#[cfg(feature = "rtt")]
+#[macro_export]
+macro_rules! log_init {
+    () => {
+        $crate::log::rtt_target::rtt_init_print!();
+    };
+}
+
+/// Initialize the logging backend, call first thing in `init`
+#[cfg(feature = "semihosting")]
+#[macro_export]
+macro_rules! log_init {
+    () => {};
+}
+
+/// Initialize the logging backend, call first thing in `init`
+///
+/// `defmt-rtt` sets up its RTT channel on first use.
+#[cfg(feature = "defmt-log")]
+#[macro_export]
+macro_rules! log_init {
+    () => {};
+}
+
+/// Print a line to the logging backend
+#[cfg(feature = "rtt")]
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::log::rtt_target::rprintln!($($arg)*)
+    };
+}
+
+/// Print a line to the logging backend
+///
+/// Errors are ignored. Notice, without a debugger attached, the semihosting
+/// `bkpt` raises a HardFault, use the `rtt` backend for standalone runs.
+#[cfg(feature = "semihosting")]
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::log::cortex_m_semihosting::hprintln!($($arg)*).ok()
+    };
+}
+
+/// Print a line to the logging backend, at the `info` level
+#[cfg(feature = "defmt-log")]
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::log::defmt::info!($($arg)*)
+    };
+}
+
+// Log at `$level` if enabled by `MAX_LEVEL`, `$prefix` marks the level in the output
+#[cfg(not(feature = "defmt-log"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at {
+    ($level:ident, $defmt:ident, $prefix:literal, $fmt:literal $($arg:tt)*) => {
+        if $crate::log::Level::$level <= $crate::log::MAX_LEVEL {
+            $crate::log!(concat!($prefix, $fmt) $($arg)*);
+        }
+    };
+}
+
+// defmt marks the level itself, use the corresponding `defmt` macro
+#[cfg(feature = "defmt-log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at {
+    ($level:ident, $defmt:ident, $prefix:literal, $fmt:literal $($arg:tt)*) => {
+        if $crate::log::Level::$level <= $crate::log::MAX_LEVEL {
+            $crate::log::defmt::$defmt!($fmt $($arg)*);
+        }
+    };
+}
+
+/// Log an error, always enabled
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::__log_at!(Error, error, "ERROR ", $($arg)+)
+    };
+}
+
+/// Log a warning
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::__log_at!(Warn, warn, "WARN  ", $($arg)+)
+    };
+}
+
+/// Log an informational message
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::__log_at!(Info, info, "INFO  ", $($arg)+)
+    };
+}
+
+/// Log a debug message, compiled out in release builds (see `MAX_LEVEL`)
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::__log_at!(Debug, debug, "DEBUG ", $($arg)+)
+    };
+}
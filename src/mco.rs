@@ -0,0 +1,157 @@
+//! Microcontroller clock output (MCO)
+//!
+//! Routes internal clocks to a pin so they can be monitored by an oscilloscope.
+//! See the Reference Manual RM0368 (www.st.com/resource/en/reference_manual/dm00096844.pdf)
+//! rcc,     chapter 6
+//! gpio,    chapter 8
+//!
+//! Usage from an RTIC `init`:
+//!
+//! ```ignore
+//! #[init]
+//! fn init(cx: init::Context) {
+//!     let device = cx.device;
+//!
+//!     // SYSCLK / 4 on PC9
+//!     ClockOut::mco2_sysclk(&device.RCC, &device.GPIOC, Mco2Prescaler::Div4);
+//!
+//!     // PLL / 4 on PA8, compare with PC9 on a second scope channel
+//!     ClockOut::mco1(&device.RCC, &device.GPIOA, Mco1Source::Pll, Mco1Prescaler::Div4);
+//!
+//!     let rcc = device.RCC.constrain();
+//!     let _clocks = rcc.cfgr.sysclk(48.mhz()).freeze();
+//! }
+//! ```
+//!
+//! See `examples/rtic_bare6.rs` for a complete application.
+
+use stm32f2xx_hal::stm32::{
+    rcc::cfgr::{MCO1PRE_A, MCO1_A},
+    GPIOA, GPIOC, RCC,
+};
+
+/// MCO1 clock source, RM0368 6.3.3 (MCO1)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mco1Source {
+    Hsi,
+    Lse,
+    Hse,
+    Pll,
+}
+
+impl From<Mco1Source> for MCO1_A {
+    fn from(source: Mco1Source) -> Self {
+        match source {
+            Mco1Source::Hsi => MCO1_A::HSI,
+            Mco1Source::Lse => MCO1_A::LSE,
+            Mco1Source::Hse => MCO1_A::HSE,
+            Mco1Source::Pll => MCO1_A::PLL,
+        }
+    }
+}
+
+/// MCO1/MCO2 prescaler, RM0368 6.3.3 (MCO1PRE/MCO2PRE, same encoding)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum McoPrescaler {
+    Div1,
+    Div2,
+    Div3,
+    Div4,
+    Div5,
+}
+
+impl From<McoPrescaler> for MCO1PRE_A {
+    fn from(prescaler: McoPrescaler) -> Self {
+        match prescaler {
+            McoPrescaler::Div1 => MCO1PRE_A::DIV1,
+            McoPrescaler::Div2 => MCO1PRE_A::DIV2,
+            McoPrescaler::Div3 => MCO1PRE_A::DIV3,
+            McoPrescaler::Div4 => MCO1PRE_A::DIV4,
+            McoPrescaler::Div5 => MCO1PRE_A::DIV5,
+        }
+    }
+}
+
+impl McoPrescaler {
+    /// The division factor, e.g. `Div4` -> 4
+    pub fn divisor(self) -> u32 {
+        match self {
+            McoPrescaler::Div1 => 1,
+            McoPrescaler::Div2 => 2,
+            McoPrescaler::Div3 => 3,
+            McoPrescaler::Div4 => 4,
+            McoPrescaler::Div5 => 5,
+        }
+    }
+}
+
+/// MCO1 prescaler (MCO1PRE)
+pub type Mco1Prescaler = McoPrescaler;
+/// MCO2 prescaler (MCO2PRE)
+pub type Mco2Prescaler = McoPrescaler;
+
+/// Clock output routing
+pub struct ClockOut;
+
+impl ClockOut {
+    /// Output SYSCLK / `prescaler` on MCO2 (PC9)
+    ///
+    /// Powers on GPIOC and configures PC9 as alternate function AF0
+    /// (STM32F401xD STM32F401xE data sheet, table 9) at low speed, as in
+    /// `rtic_bare6.rs` (assignment 6 compares the signal at other speeds).
+    /// The frequency reads correctly, for a clean waveform above a few MHz
+    /// raise GPIOC_OSPEEDR (OSPEEDR9) afterwards.
+    pub fn mco2_sysclk(rcc: &RCC, gpioc: &GPIOC, prescaler: Mco2Prescaler) {
+        // mco2     : SYSCLK
+        // mco2pre  : prescaler
+        rcc.cfgr
+            .modify(|_, w| w.mco2().sysclk().mco2pre().variant(prescaler.into()));
+
+        // power on GPIOC, RM0368 6.3.11
+        rcc.ahb1enr.modify(|_, w| w.gpiocen().enabled());
+
+        // AF0, gpioc reset value = AF0
+        gpioc.afrh.modify(|_, w| w.afrh9().af0());
+
+        // configure PC9 as alternate function, RM0368 8.4.1
+        gpioc.moder.modify(|_, w| w.moder9().alternate());
+
+        // otyper reset state push/pull, in reset state (don't need to change)
+
+        // low speed, RM0368 8.4.3
+        gpioc.ospeedr.modify(|_, w| w.ospeedr9().low_speed());
+    }
+
+    /// Output `source` / `prescaler` on MCO1 (PA8)
+    ///
+    /// Useful to observe the raw HSI/HSE or the PLL output, e.g. when
+    /// debugging PLL lock issues. Powers on GPIOA and configures PA8 as
+    /// alternate function AF0 at very high speed.
+    pub fn mco1(rcc: &RCC, gpioa: &GPIOA, source: Mco1Source, prescaler: Mco1Prescaler) {
+        // mco1     : source
+        // mco1pre  : prescaler
+        rcc.cfgr.modify(|_, w| {
+            w.mco1()
+                .variant(source.into())
+                .mco1pre()
+                .variant(prescaler.into())
+        });
+
+        // power on GPIOA, RM0368 6.3.11
+        rcc.ahb1enr.modify(|_, w| w.gpioaen().enabled());
+
+        // AF0, gpioa reset value = AF0
+        gpioa.afrh.modify(|_, w| w.afrh8().af0());
+
+        // configure PA8 as alternate function, RM0368 8.4.1
+        gpioa.moder.modify(|_, w| w.moder8().alternate());
+
+        // very high speed, RM0368 8.4.3
+        gpioa.ospeedr.modify(|_, w| w.ospeedr8().very_high_speed());
+    }
+}
+
+/// Output `source` / `prescaler` on MCO1 (PA8), see `ClockOut::mco1`
+pub fn clock_out_mco1(rcc: &RCC, gpioa: &GPIOA, source: Mco1Source, prescaler: Mco1Prescaler) {
+    ClockOut::mco1(rcc, gpioa, source, prescaler);
+}
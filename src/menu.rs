@@ -0,0 +1,108 @@
+//! A tiny menu, selecting one of a few items, hardware independent
+//!
+//! The state (`Menu`) is moved by an input, e.g., the detents of a rotary
+//! encoder, and rendered by pure functions: `blink` for an LED showing the
+//! selected item as a number of blinks, `Menu::label` for text.
+//!
+//! ```ignore
+//! let mut menu = Menu::new(&["red", "green", "blue"]);
+//! menu.scroll(clicks);
+//! led_on = blink(menu.selected(), tick);
+//! tick += 1; // every TICK_MS
+//! ```
+
+/// Duration of a blink tick in milliseconds
+pub const TICK_MS: u32 = 200;
+
+/// Ticks off after the blinks, before the count repeats
+pub const PAUSE_TICKS: u32 = 5;
+
+/// The menu state, the items and the selected one
+#[derive(Clone, Copy, Debug)]
+pub struct Menu<'a> {
+    items: &'a [&'a str],
+    selected: usize,
+}
+
+impl<'a> Menu<'a> {
+    /// A menu of `items`, the first selected, panics if `items` is empty
+    pub fn new(items: &'a [&'a str]) -> Self {
+        assert!(!items.is_empty(), "a menu needs at least one item");
+        Menu { items, selected: 0 }
+    }
+
+    /// Move the selection `steps` items forward (negative, back), wrapping around
+    pub fn scroll(&mut self, steps: i32) {
+        let len = self.items.len() as i32;
+        self.selected = (self.selected as i32 + steps).rem_euclid(len) as usize;
+    }
+
+    /// The index of the selected item
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The text of the selected item
+    pub fn label(&self) -> &'a str {
+        self.items[self.selected]
+    }
+}
+
+/// Returns `true` if the LED should be on at `tick`, showing item `selected`
+///
+/// Item n blinks n + 1 times (one tick on, one tick off), followed by
+/// PAUSE_TICKS off, repeating.
+pub fn blink(selected: usize, tick: u32) -> bool {
+    let blinks = selected as u32 + 1;
+    let t = tick % (2 * blinks + PAUSE_TICKS);
+    t < 2 * blinks && t % 2 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: [&str; 3] = ["red", "green", "blue"];
+
+    #[test]
+    fn scroll_wraps_both_ways() {
+        let mut menu = Menu::new(&ITEMS);
+        assert_eq!(menu.label(), "red");
+        menu.scroll(1);
+        assert_eq!(menu.label(), "green");
+        menu.scroll(2);
+        assert_eq!(menu.label(), "red");
+        menu.scroll(-1);
+        assert_eq!(menu.label(), "blue");
+        menu.scroll(-7);
+        assert_eq!(menu.selected(), 1);
+        menu.scroll(0);
+        assert_eq!(menu.selected(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty() {
+        Menu::new(&[]);
+    }
+
+    // the LED states over one repetition
+    fn sequence(selected: usize) -> Vec<bool> {
+        let period = 2 * (selected as u32 + 1) + PAUSE_TICKS;
+        (0..period).map(|tick| blink(selected, tick)).collect()
+    }
+
+    #[test]
+    fn blink_count() {
+        for selected in 0..4 {
+            let on = sequence(selected).iter().filter(|on| **on).count();
+            assert_eq!(on, selected + 1);
+        }
+        assert_eq!(
+            sequence(1),
+            vec![true, false, true, false, false, false, false, false, false]
+        );
+        // repeats
+        assert!(blink(1, 9));
+    }
+}
@@ -0,0 +1,121 @@
+//! TIM2 as the RTIC monotonic timer, an alternative to CYCCNT
+//!
+//! ```ignore
+//! #[rtic::app(device = stm32f4xx_hal::stm32, monotonic = app::monotonic::Tim2Monotonic, peripherals = true)]
+//! ...
+//!     Tim2Monotonic::start(&device.RCC, device.TIM2);
+//!     cx.schedule.blink(cx.start + PERIOD).unwrap();
+//! ```
+//!
+//! TIM2 is a 32 bit timer, free running from 0 to 0xFFFF_FFFF without
+//! prescaler. The timer clock is HCLK as long as the APB1 prescaler is 1 or
+//! 2 (timers on APB1 run at 2 x PCLK1 if the prescaler is not 1, RM0368 6.2),
+//! so a tick is a core clock cycle, just like CYCCNT.
+
+use core::{
+    cmp::Ordering,
+    ops::{Add, Sub},
+};
+use rtic::{Fraction, Monotonic};
+use stm32f2xx_hal::stm32::{RCC, TIM2};
+
+/// A point in time, a TIM2 count
+///
+/// Compared by the sign of the (wrapping) difference, so ordering holds across
+/// the counter wrap, for instants less than 2^31 ticks apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tim2Instant(pub u32);
+
+impl Ord for Tim2Instant {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.wrapping_sub(other.0) as i32).cmp(&0)
+    }
+}
+
+impl PartialOrd for Tim2Instant {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The signed number of ticks between two instants
+impl Sub for Tim2Instant {
+    type Output = i32;
+
+    fn sub(self, rhs: Self) -> i32 {
+        self.0.wrapping_sub(rhs.0) as i32
+    }
+}
+
+/// The instant `ticks` later
+impl Add<u32> for Tim2Instant {
+    type Output = Self;
+
+    fn add(self, ticks: u32) -> Self {
+        Tim2Instant(self.0.wrapping_add(ticks))
+    }
+}
+
+/// TIM2 based monotonic timer
+pub struct Tim2Monotonic;
+
+impl Tim2Monotonic {
+    /// Power on and start TIM2, counting up at the timer clock
+    ///
+    /// Takes the TIM2 peripheral, as it must not be reconfigured after this.
+    /// Call in `init`, RTIC zeroes the count (`reset`) after `init` returns.
+    pub fn start(rcc: &RCC, tim2: TIM2) {
+        rcc.apb1enr.modify(|_, w| w.tim2en().enabled());
+
+        tim2.psc.write(|w| w.psc().bits(0));
+        tim2.arr.write(|w| w.arr().bits(u32::MAX));
+        // load PSC (buffered) by an update event, RM0368 13.3.1
+        tim2.egr.write(|w| w.ug().update());
+        tim2.cr1.modify(|_, w| w.cen().enabled());
+    }
+}
+
+impl Monotonic for Tim2Monotonic {
+    type Instant = Tim2Instant;
+
+    // a tick is a core clock cycle, the SysTick (timer queue) runs at the core clock
+    fn ratio() -> Fraction {
+        Fraction {
+            numerator: 1,
+            denominator: 1,
+        }
+    }
+
+    fn now() -> Tim2Instant {
+        // SAFETY: read only, TIM2 is owned by the monotonic since `start`
+        Tim2Instant(unsafe { (*TIM2::ptr()).cnt.read().cnt().bits() })
+    }
+
+    unsafe fn reset() {
+        (*TIM2::ptr()).cnt.write(|w| w.cnt().bits(0));
+    }
+
+    fn zero() -> Tim2Instant {
+        Tim2Instant(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordering_across_the_wrap() {
+        let before = Tim2Instant(0xFFFF_FF00);
+        let after = before + 0x200;
+        assert_eq!(after, Tim2Instant(0x100));
+        assert!(before < after);
+        assert_eq!(after - before, 0x200);
+        assert_eq!(before - after, -0x200);
+    }
+
+    #[test]
+    fn ordering_equal() {
+        assert_eq!(Tim2Instant(5).cmp(&Tim2Instant(5)), Ordering::Equal);
+    }
+}
@@ -0,0 +1,203 @@
+//! Morse code encoding, hardware independent
+//!
+//! `encode` gives the symbols of a character, `render` turns a text into
+//! (on, units) runs with the standard timing, to drive an LED or a buzzer:
+//!
+//! ```ignore
+//! for (on, units) in render("SOS") {
+//!     // LED on or off for `units * UNIT_MS` ms
+//! }
+//! ```
+//!
+//! Timing (in units): dot 1, dash 3, gap within a letter 1, gap between
+//! letters 3, gap between words 7. The text ends with a word gap, so a
+//! repeated message stays readable.
+
+use core::str::Chars;
+
+/// Suggested duration of a unit in milliseconds (~10 words per minute)
+pub const UNIT_MS: u32 = 120;
+
+const SYMBOL_GAP: u32 = 1;
+const LETTER_GAP: u32 = 3;
+const WORD_GAP: u32 = 7;
+
+/// A morse symbol
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symbol {
+    Dot,
+    Dash,
+}
+
+impl Symbol {
+    /// The duration in units, dot 1, dash 3
+    pub fn units(self) -> u32 {
+        match self {
+            Symbol::Dot => 1,
+            Symbol::Dash => 3,
+        }
+    }
+}
+
+/// The symbols of `c`, letters (either case) and digits, `None` otherwise
+pub fn encode(c: char) -> Option<&'static [Symbol]> {
+    use Symbol::{Dash, Dot};
+
+    let symbols: &'static [Symbol] = match c.to_ascii_uppercase() {
+        'A' => &[Dot, Dash],
+        'B' => &[Dash, Dot, Dot, Dot],
+        'C' => &[Dash, Dot, Dash, Dot],
+        'D' => &[Dash, Dot, Dot],
+        'E' => &[Dot],
+        'F' => &[Dot, Dot, Dash, Dot],
+        'G' => &[Dash, Dash, Dot],
+        'H' => &[Dot, Dot, Dot, Dot],
+        'I' => &[Dot, Dot],
+        'J' => &[Dot, Dash, Dash, Dash],
+        'K' => &[Dash, Dot, Dash],
+        'L' => &[Dot, Dash, Dot, Dot],
+        'M' => &[Dash, Dash],
+        'N' => &[Dash, Dot],
+        'O' => &[Dash, Dash, Dash],
+        'P' => &[Dot, Dash, Dash, Dot],
+        'Q' => &[Dash, Dash, Dot, Dash],
+        'R' => &[Dot, Dash, Dot],
+        'S' => &[Dot, Dot, Dot],
+        'T' => &[Dash],
+        'U' => &[Dot, Dot, Dash],
+        'V' => &[Dot, Dot, Dot, Dash],
+        'W' => &[Dot, Dash, Dash],
+        'X' => &[Dash, Dot, Dot, Dash],
+        'Y' => &[Dash, Dot, Dash, Dash],
+        'Z' => &[Dash, Dash, Dot, Dot],
+        '0' => &[Dash, Dash, Dash, Dash, Dash],
+        '1' => &[Dot, Dash, Dash, Dash, Dash],
+        '2' => &[Dot, Dot, Dash, Dash, Dash],
+        '3' => &[Dot, Dot, Dot, Dash, Dash],
+        '4' => &[Dot, Dot, Dot, Dot, Dash],
+        '5' => &[Dot, Dot, Dot, Dot, Dot],
+        '6' => &[Dash, Dot, Dot, Dot, Dot],
+        '7' => &[Dash, Dash, Dot, Dot, Dot],
+        '8' => &[Dash, Dash, Dash, Dot, Dot],
+        '9' => &[Dash, Dash, Dash, Dash, Dot],
+        _ => return None,
+    };
+    Some(symbols)
+}
+
+/// The (on, units) runs of `text`, see the module documentation
+///
+/// Whitespace separates words, other characters without a code are skipped.
+pub fn render(text: &str) -> Render<'_> {
+    Render {
+        chars: text.chars(),
+        symbols: &[],
+        gap: None,
+    }
+}
+
+/// Iterator over the (on, units) runs of a text, see `render`
+#[derive(Clone, Debug)]
+pub struct Render<'a> {
+    chars: Chars<'a>,
+    // the symbols left of the current letter
+    symbols: &'static [Symbol],
+    // the gap (off) following the last symbol
+    gap: Option<u32>,
+}
+
+impl<'a> Render<'a> {
+    // The gap after the current letter, a word gap if whitespace (or the end
+    // of the text) comes before the next letter
+    fn letter_gap(&self) -> u32 {
+        let mut space = false;
+        for c in self.chars.clone() {
+            if encode(c).is_some() {
+                return if space { WORD_GAP } else { LETTER_GAP };
+            }
+            space |= c.is_whitespace();
+        }
+        WORD_GAP
+    }
+}
+
+impl<'a> Iterator for Render<'a> {
+    type Item = (bool, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(units) = self.gap.take() {
+                return Some((false, units));
+            }
+            if let Some((symbol, rest)) = self.symbols.split_first() {
+                self.symbols = rest;
+                self.gap = Some(if rest.is_empty() {
+                    self.letter_gap()
+                } else {
+                    SYMBOL_GAP
+                });
+                return Some((true, symbol.units()));
+            }
+            // the next letter, skipping characters without a code
+            self.symbols = encode(self.chars.next()?).unwrap_or(&[]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Symbol::{Dash, Dot};
+
+    #[test]
+    fn letters_and_digits() {
+        assert_eq!(encode('S'), Some(&[Dot, Dot, Dot][..]));
+        assert_eq!(encode('o'), Some(&[Dash, Dash, Dash][..]));
+        assert_eq!(encode('Q'), Some(&[Dash, Dash, Dot, Dash][..]));
+        assert_eq!(encode('0'), Some(&[Dash; 5][..]));
+        assert_eq!(encode('5'), Some(&[Dot; 5][..]));
+        assert_eq!(encode(' '), None);
+        assert_eq!(encode('!'), None);
+        assert_eq!(encode('å'), None);
+    }
+
+    #[test]
+    fn all_codes_are_distinct() {
+        let chars: Vec<char> = ('A'..='Z').chain('0'..='9').collect();
+        for (i, a) in chars.iter().enumerate() {
+            for b in &chars[i + 1..] {
+                assert_ne!(encode(*a), encode(*b), "{} and {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn letter_and_word_gaps() {
+        let runs: Vec<_> = render("ET").collect();
+        assert_eq!(runs, vec![(true, 1), (false, 3), (true, 3), (false, 7)]);
+
+        let runs: Vec<_> = render("E  T").collect();
+        assert_eq!(runs, vec![(true, 1), (false, 7), (true, 3), (false, 7)]);
+    }
+
+    #[test]
+    fn sos() {
+        let runs: Vec<_> = render("sos").collect();
+        #[rustfmt::skip]
+        assert_eq!(runs, vec![
+            (true, 1), (false, 1), (true, 1), (false, 1), (true, 1), (false, 3),
+            (true, 3), (false, 1), (true, 3), (false, 1), (true, 3), (false, 3),
+            (true, 1), (false, 1), (true, 1), (false, 1), (true, 1), (false, 7),
+        ]);
+        // 27 units, plus the word gap
+        assert_eq!(render("SOS").map(|(_, units)| units).sum::<u32>(), 34);
+    }
+
+    #[test]
+    fn skips_unknown_characters() {
+        assert_eq!(render("").count(), 0);
+        assert_eq!(render(" !? ").count(), 0);
+        assert!(render("E!").eq(render("E")));
+        assert!(render("E!T").eq(render("ET")));
+    }
+}
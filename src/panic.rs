@@ -0,0 +1,107 @@
+//! Panic handler reporting over RTT
+//!
+//! Prints the panic message and its location (file and line), and then
+//! halts on a breakpoint, so the debugger stops at the point of failure.
+//! With the `nightly` feature (cortex-m `inline-asm`, nightly toolchain
+//! only), a snapshot of the PC and LR is printed as well.
+//!
+//! The module defines a `#[panic_handler]`, and is thus only compiled with
+//! the `panic-rtt` feature (only one panic handler may be linked). Use it in
+//! place of `panic_halt`/`panic_rtt_target`:
+//!
+//! ```ignore
+//! use app::panic as _;
+//! ```
+//!
+//! > cargo run --example <example> --features panic-rtt
+//!
+//! The RTT channel must be set up (e.g., by `trace_init!()`) for the output
+//! to be visible.
+//!
+//! With the `panic-count` feature, the handler also counts the panics in a
+//! backup register (`PANIC_COUNT_BKP`), surviving system resets. Read it at
+//! boot to tell how often the firmware crashed, e.g., during a long test run:
+//!
+//! ```ignore
+//! enable_backup_access(&device.RCC, &device.PWR);
+//! rprintln!("{} panics so far", panic_count(&device.RTC));
+//! ```
+
+use core::{
+    fmt::Write,
+    panic::PanicInfo,
+    sync::atomic::{compiler_fence, Ordering},
+};
+use cortex_m::{asm, interrupt};
+use rtt_target::{ChannelMode, UpChannel};
+
+#[cfg(feature = "panic-count")]
+use crate::backup::{bkp_read, bkp_write, enable_backup_access, BKP_REGISTERS};
+#[cfg(feature = "panic-count")]
+use stm32f2xx_hal::stm32::{PWR, RCC, RTC};
+
+/// The backup register holding the panic count, the last one
+#[cfg(feature = "panic-count")]
+pub const PANIC_COUNT_BKP: usize = BKP_REGISTERS - 1;
+
+/// The number of panics since the count was last cleared (or the backup
+/// domain was reset), requires `enable_backup_access`
+#[cfg(feature = "panic-count")]
+pub fn panic_count(rtc: &RTC) -> u32 {
+    bkp_read(rtc, PANIC_COUNT_BKP)
+}
+
+/// Clear the panic count, requires `enable_backup_access`
+#[cfg(feature = "panic-count")]
+pub fn clear_panic_count(rtc: &RTC) {
+    bkp_write(rtc, PANIC_COUNT_BKP, 0);
+}
+
+// Increment the panic count, returns the new count
+#[cfg(feature = "panic-count")]
+fn count_panic() -> u32 {
+    // SAFETY: interrupts are disabled and we never return, no one else
+    // accesses the peripherals from now on
+    let (rcc, pwr, rtc) = unsafe { (&*RCC::ptr(), &*PWR::ptr(), &*RTC::ptr()) };
+    // the application may have panicked before enabling the access
+    enable_backup_access(rcc, pwr);
+    let count = bkp_read(rtc, PANIC_COUNT_BKP).wrapping_add(1);
+    bkp_write(rtc, PANIC_COUNT_BKP, count);
+    count
+}
+
+#[inline(never)]
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    interrupt::disable();
+
+    // PC and LR as seen by the handler, i.e., inside the panic machinery.
+    // The file and line of the `panic!` are found in `info`. Reading the
+    // registers requires inline assembly, thus a nightly toolchain.
+    #[cfg(feature = "nightly")]
+    let (pc, lr) = (
+        cortex_m::register::pc::read(),
+        cortex_m::register::lr::read(),
+    );
+
+    // counted first, even if the output below fails
+    #[cfg(feature = "panic-count")]
+    let count = count_panic();
+
+    // SAFETY: interrupts are disabled, and we never return
+    if let Some(mut channel) = unsafe { UpChannel::conjure(0) } {
+        // never block on a full buffer (no host attached)
+        channel.set_mode(ChannelMode::NoBlockTrim);
+        // "panicked at '<message>', <file>:<line>:<column>"
+        writeln!(channel, "{}", info).ok();
+        #[cfg(feature = "nightly")]
+        writeln!(channel, "PC 0x{:08x}, LR 0x{:08x}", pc, lr).ok();
+        #[cfg(feature = "panic-count")]
+        writeln!(channel, "panic count {}", count).ok();
+    }
+
+    loop {
+        asm::bkpt();
+        compiler_fence(Ordering::SeqCst);
+    }
+}
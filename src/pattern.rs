@@ -0,0 +1,129 @@
+//! LED blink patterns
+//!
+//! Pure pattern logic, independent of the hardware: `next_step` tells whether
+//! the LED is on at a given tick.
+//!
+//! ```ignore
+//! led_on = next_step(pattern, tick);
+//! tick += 1; // every TICK_MS
+//! ```
+
+/// Duration of a tick in milliseconds (a morse "unit" for `Sos`)
+pub const TICK_MS: u32 = 150;
+
+/// The available patterns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlinkPattern {
+    /// Constantly on
+    Steady,
+    /// On and off every tick
+    Fast,
+    /// ... --- ... in morse code
+    Sos,
+}
+
+impl BlinkPattern {
+    /// The next pattern, in the order Steady -> Fast -> Sos -> Steady
+    pub fn next(self) -> Self {
+        match self {
+            BlinkPattern::Steady => BlinkPattern::Fast,
+            BlinkPattern::Fast => BlinkPattern::Sos,
+            BlinkPattern::Sos => BlinkPattern::Steady,
+        }
+    }
+}
+
+// SOS as (on, ticks) runs. Morse timing: dot 1, dash 3, gap within a letter 1,
+// gap between letters 3, gap between words 7 (units).
+const SOS: [(bool, u32); 18] = [
+    // S
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 3),
+    // O
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 3),
+    // S
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 7),
+];
+
+/// Returns `true` if the LED should be on at `tick` (repeating)
+pub fn next_step(pattern: BlinkPattern, tick: u32) -> bool {
+    match pattern {
+        BlinkPattern::Steady => true,
+        BlinkPattern::Fast => tick % 2 == 0,
+        BlinkPattern::Sos => {
+            let period: u32 = SOS.iter().map(|(_, ticks)| ticks).sum();
+            let mut t = tick % period;
+            for (on, ticks) in SOS.iter() {
+                if t < *ticks {
+                    return *on;
+                }
+                t -= ticks;
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steady_is_always_on() {
+        assert!((0..100).all(|tick| next_step(BlinkPattern::Steady, tick)));
+    }
+
+    #[test]
+    fn fast_alternates() {
+        assert!(next_step(BlinkPattern::Fast, 0));
+        assert!(!next_step(BlinkPattern::Fast, 1));
+        assert!(next_step(BlinkPattern::Fast, 2));
+        assert!(!next_step(BlinkPattern::Fast, u32::MAX));
+    }
+
+    #[test]
+    fn sos_morse_timing() {
+        let sos: [bool; 34] = [
+            // S: dot, gap, dot, gap, dot, letter gap
+            true, false, true, false, true, false, false, false,
+            // O: dash, gap, dash, gap, dash, letter gap
+            true, true, true, false, true, true, true, false, true, true, true, false, false, false,
+            // S: dot, gap, dot, gap, dot, word gap
+            true, false, true, false, true, false, false, false, false, false, false, false,
+        ];
+        for (tick, on) in sos.iter().enumerate() {
+            assert_eq!(
+                next_step(BlinkPattern::Sos, tick as u32),
+                *on,
+                "tick {}",
+                tick
+            );
+        }
+        // repeats
+        for (tick, on) in sos.iter().enumerate() {
+            assert_eq!(next_step(BlinkPattern::Sos, 34 + tick as u32), *on);
+        }
+    }
+
+    #[test]
+    fn next_cycles_through_all() {
+        let p = BlinkPattern::Steady;
+        assert_eq!(p.next(), BlinkPattern::Fast);
+        assert_eq!(p.next().next(), BlinkPattern::Sos);
+        assert_eq!(p.next().next().next(), BlinkPattern::Steady);
+    }
+}
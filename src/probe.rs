@@ -0,0 +1,53 @@
+//! Scope marker, a GPIO bracketing code under measurement
+//!
+//! Connect a scope to the pin and trigger on the rising edge, the pulse width
+//! is the execution time of the measured code (plus a few cycles for the pin
+//! writes).
+//!
+//! ```ignore
+//! let mut probe = Probe::new(gpioa.pa7.into_push_pull_output());
+//! let result = probe.measure(|| do_work());
+//! ```
+//!
+//! Pin errors are ignored, the GPIO pins of the STM32 HAL are infallible.
+
+use embedded_hal::digital::v2::OutputPin;
+
+/// A scope marker on `PIN`, low when idle
+pub struct Probe<PIN> {
+    pin: PIN,
+}
+
+impl<PIN> Probe<PIN>
+where
+    PIN: OutputPin,
+{
+    /// Wraps `pin`, the marker is initially low
+    pub fn new(mut pin: PIN) -> Self {
+        pin.set_low().ok();
+        Self { pin }
+    }
+
+    /// Runs `f` with the marker high, returns the result of `f`
+    #[inline(always)]
+    pub fn measure<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        self.pin.set_high().ok();
+        let r = f();
+        self.pin.set_low().ok();
+        r
+    }
+
+    /// A short pulse, marking a point in time (e.g., a task start)
+    #[inline(always)]
+    pub fn pulse(&mut self) {
+        self.measure(|| {})
+    }
+
+    /// Releases the pin
+    pub fn free(self) -> PIN {
+        self.pin
+    }
+}
@@ -0,0 +1,141 @@
+//! Task execution times, an on-target profiler on the DWT cycle counter
+//!
+//! Each task reads CYCCNT at the start of its body, and records the elapsed
+//! cycles at the end. The last `N` records are kept in a `HistoryBuffer`
+//! (older ones are overwritten), to be dumped on demand:
+//!
+//! ```ignore
+//! // in a task, with `profile: Profile<U32>` as a resource
+//! let start = DWT::get_cycle_count();
+//! // ... the task body
+//! cx.resources.profile.lock(|p| p.record(TASK_ID, start));
+//! ```
+//!
+//! The cycle counter must be enabled first, see `bench`.
+
+use cortex_m::peripheral::DWT;
+use heapless::{ArrayLength, HistoryBuffer, Vec};
+
+/// Identifies the task of a record, e.g., an index into a table of names
+pub type TaskId = u8;
+
+/// One task execution
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Record {
+    /// Sequence number, 1 for the first record (0 for an unused slot)
+    pub seq: u32,
+    /// The task
+    pub task: TaskId,
+    /// Execution time in clock cycles
+    pub cycles: u32,
+}
+
+/// The last `N` task executions
+pub struct Profile<N>
+where
+    N: ArrayLength<Record>,
+{
+    history: HistoryBuffer<Record, N>,
+    seq: u32,
+}
+
+impl<N> Profile<N>
+where
+    N: ArrayLength<Record>,
+{
+    /// An empty profile
+    pub fn new() -> Self {
+        Profile {
+            history: HistoryBuffer::new(),
+            seq: 0,
+        }
+    }
+
+    /// Record an execution of `task`, started at CYCCNT `start`, ending now
+    #[inline]
+    pub fn record(&mut self, task: TaskId, start: u32) {
+        let cycles = DWT::get_cycle_count().wrapping_sub(start);
+        self.push(task, cycles);
+    }
+
+    /// Record an execution of `task`, taking `cycles`
+    pub fn push(&mut self, task: TaskId, cycles: u32) {
+        self.seq = self.seq.wrapping_add(1);
+        self.history.write(Record {
+            seq: self.seq,
+            task,
+            cycles,
+        });
+    }
+
+    /// The number of records so far (including the overwritten ones)
+    pub fn count(&self) -> u32 {
+        self.seq
+    }
+
+    /// A copy of the kept records, oldest first
+    ///
+    /// Copy inside the lock, and print outside, the printing is slow.
+    pub fn in_order(&self) -> Vec<Record, N> {
+        let mut records: Vec<Record, N> = self
+            .history
+            .as_slice()
+            .iter()
+            .filter(|record| record.seq != 0)
+            .cloned()
+            .collect();
+        records.sort_unstable_by_key(|record| record.seq);
+        records
+    }
+}
+
+impl<N> Default for Profile<N>
+where
+    N: ArrayLength<Record>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::consts::U4;
+
+    #[test]
+    fn empty() {
+        let profile: Profile<U4> = Profile::new();
+        assert_eq!(profile.count(), 0);
+        assert!(profile.in_order().is_empty());
+    }
+
+    #[test]
+    fn oldest_first() {
+        let mut profile: Profile<U4> = Profile::new();
+        profile.push(0, 100);
+        profile.push(1, 200);
+        let records = profile.in_order();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0],
+            Record {
+                seq: 1,
+                task: 0,
+                cycles: 100
+            }
+        );
+        assert_eq!(records[1].task, 1);
+    }
+
+    #[test]
+    fn keeps_the_last() {
+        let mut profile: Profile<U4> = Profile::new();
+        for cycles in 0..10 {
+            profile.push(0, cycles);
+        }
+        assert_eq!(profile.count(), 10);
+        let cycles: Vec<u32, U4> = profile.in_order().iter().map(|r| r.cycles).collect();
+        assert_eq!(&cycles[..], &[6, 7, 8, 9]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Pseudo random numbers for demos (NOT cryptographically secure)
+//!
+//! A xorshift32 generator, seeded from ADC noise (see `examples/rtic_random.rs`).
+//! The sequence is fully determined by the seed, and the seed is gathered
+//! from a weak (and possibly biased or externally influenced) noise source.
+//! Never use it for keys, nonces or anything security related.
+//!
+//! ```ignore
+//! let mut rng = XorShift32::new(seed_from_lsbs(samples));
+//! let delay_ms = 100 + rng.below(400);
+//! ```
+
+/// Collect the least significant bit of each sample into a 32 bit seed
+///
+/// Each new bit is XOR:ed into the seed, which is rotated one step per
+/// sample, so more than 32 samples are folded in.
+pub fn seed_from_lsbs<I>(samples: I) -> u32
+where
+    I: IntoIterator<Item = u16>,
+{
+    samples.into_iter().fold(0, |seed: u32, sample| {
+        seed.rotate_left(1) ^ (sample & 1) as u32
+    })
+}
+
+/// xorshift32 generator (Marsaglia, 2003), period 2^32 - 1
+pub struct XorShift32 {
+    state: u32,
+}
+
+impl XorShift32 {
+    /// A generator starting from `seed`
+    ///
+    /// The all zero state is a fixed point, a zero seed is replaced.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+
+    /// The next number in the sequence, never 0
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// A number in `0..n` (slightly biased for large `n`)
+    ///
+    /// Panics if `n` is 0 (the range is empty).
+    pub fn below(&mut self, n: u32) -> u32 {
+        assert!(n > 0, "XorShift32::below: empty range (n == 0)");
+        self.next_u32() % n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_from_lsbs_collects_bits() {
+        assert_eq!(seed_from_lsbs(vec![1, 0, 1, 1]), 0b1011);
+        assert_eq!(seed_from_lsbs(vec![2, 4, 6]), 0);
+        // 33 ones, the first bit is rotated around and cancelled
+        assert_eq!(seed_from_lsbs(vec![1; 33]), 0xFFFF_FFFE);
+    }
+
+    #[test]
+    fn xorshift_reference_sequence() {
+        let mut rng = XorShift32::new(1);
+        assert_eq!(rng.next_u32(), 270_369);
+        assert_eq!(rng.next_u32(), 67_634_689);
+    }
+
+    #[test]
+    fn xorshift_zero_seed_is_replaced() {
+        let mut rng = XorShift32::new(0);
+        assert_ne!(rng.next_u32(), 0);
+    }
+
+    #[test]
+    fn below_in_range() {
+        let mut rng = XorShift32::new(42);
+        assert!((0..1_000).all(|_| rng.below(6) < 6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn below_zero_panics() {
+        XorShift32::new(42).below(0);
+    }
+}
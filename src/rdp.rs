@@ -0,0 +1,66 @@
+//! Read-out protection (RDP) level, from the option bytes (read only)
+//!
+//! ```ignore
+//! let level = read_protection_level(&device.FLASH);
+//! rprintln!("RDP {:?}", level);
+//! ```
+//!
+//! Changing the level is deliberately left out. Level 2 is permanent, a
+//! board set to Level 2 can never be debugged or re-flashed again.
+
+use stm32f2xx_hal::stm32::FLASH;
+
+/// RDP byte value for Level 0 (no protection)
+pub const RDP_LEVEL0: u8 = 0xAA;
+/// RDP byte value for Level 2 (permanent protection)
+pub const RDP_LEVEL2: u8 = 0xCC;
+
+/// Read-out protection level, the RDP option byte, RM0368 3.6.3
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RdpLevel {
+    /// No protection, as shipped
+    Level0,
+    /// The flash cannot be read by the debugger (or from RAM/system memory
+    /// boot), reverting to Level 0 mass erases the flash
+    Level1,
+    /// Level 1, and the debug port and boot from RAM/system memory are
+    /// disabled, irreversible
+    Level2,
+}
+
+impl RdpLevel {
+    /// Decode the FLASH_OPTCR register value (RDP in bits 15:8), RM0368 3.8.6
+    ///
+    /// Any value other than 0xAA and 0xCC is Level 1.
+    pub fn from_optcr(optcr: u32) -> Self {
+        match (optcr >> 8) as u8 {
+            RDP_LEVEL0 => RdpLevel::Level0,
+            RDP_LEVEL2 => RdpLevel::Level2,
+            _ => RdpLevel::Level1,
+        }
+    }
+}
+
+/// Read the read-out protection level from FLASH_OPTCR
+pub fn read_protection_level(flash: &FLASH) -> RdpLevel {
+    RdpLevel::from_optcr(flash.optcr.read().bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_default_is_level0() {
+        // FLASH_OPTCR reset value (as shipped)
+        assert_eq!(RdpLevel::from_optcr(0x0FFF_AAED), RdpLevel::Level0);
+    }
+
+    #[test]
+    fn only_0xcc_is_level2() {
+        assert_eq!(RdpLevel::from_optcr(0x0FFF_CCED), RdpLevel::Level2);
+        assert_eq!(RdpLevel::from_optcr(0x0FFF_00ED), RdpLevel::Level1);
+        assert_eq!(RdpLevel::from_optcr(0x0FFF_FFED), RdpLevel::Level1);
+        assert_eq!(RdpLevel::from_optcr(0x0FFF_CDED), RdpLevel::Level1);
+    }
+}
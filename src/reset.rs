@@ -0,0 +1,161 @@
+//! Software reset, decoding the reset cause, and the brown-out reset level
+//!
+//! ```ignore
+//! let cause = take_reset_cause(&device.RCC);
+//! rprintln!("reset cause {:?}", cause);
+//! ...
+//! system_reset();
+//! ```
+
+use cortex_m::peripheral::SCB;
+use stm32f2xx_hal::stm32::RCC;
+
+/// Request a system reset, AIRCR SYSRESETREQ
+///
+/// Outstanding memory writes are completed (DSB), then SYSRESETREQ is written
+/// together with the VECTKEY (0x05FA, writes without the key are ignored),
+/// ARMv7-M ARM B3.2.6. The reset is not instantaneous, the core spins until
+/// it takes effect. Resets the core and all peripherals, but not the debug
+/// logic or the backup domain (RTC, backup registers).
+pub fn system_reset() -> ! {
+    SCB::sys_reset()
+}
+
+/// The cause of the last reset, from the flags in RCC_CSR (RM0368 6.3.21)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResetCause {
+    LowPower,
+    WindowWatchdog,
+    IndependentWatchdog,
+    Software,
+    PowerOn,
+    Brownout,
+    Pin,
+    Unknown,
+}
+
+impl ResetCause {
+    /// Decode the RCC_CSR register value
+    ///
+    /// Several flags may be set at once, every reset drives the NRST pin (PINRSTF),
+    /// and a power-on reset also sets BORRSTF. The most specific flag wins.
+    pub fn from_csr(csr: u32) -> Self {
+        const FLAGS: [(u32, ResetCause); 7] = [
+            (31, ResetCause::LowPower),
+            (30, ResetCause::WindowWatchdog),
+            (29, ResetCause::IndependentWatchdog),
+            (28, ResetCause::Software),
+            (27, ResetCause::PowerOn),
+            (25, ResetCause::Brownout),
+            (26, ResetCause::Pin),
+        ];
+        FLAGS
+            .iter()
+            .find(|(bit, _)| csr & (1 << bit) != 0)
+            .map(|(_, cause)| *cause)
+            .unwrap_or(ResetCause::Unknown)
+    }
+}
+
+/// Read the reset cause, and clear the flags (RCC_CSR RMVF)
+///
+/// The flags are sticky, without clearing them the next boot would report
+/// the accumulated flags of all resets since power on. Call once, early in `init`.
+pub fn take_reset_cause(rcc: &RCC) -> ResetCause {
+    let cause = ResetCause::from_csr(rcc.csr.read().bits());
+    rcc.csr.modify(|_, w| w.rmvf().clear());
+    cause
+}
+
+/// Brown-out reset threshold, the BOR_LEV option bits (FLASH_OPTCR[3:2])
+///
+/// Below the threshold the device is held in reset. The power-on/power-down
+/// reset (POR/PDR, ~1.7 V) is always active, `Off` only disables the BOR.
+/// Thresholds for a falling supply, DS10086 (BOR1..BOR3).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorLevel {
+    Off,
+    Level1,
+    Level2,
+    Level3,
+}
+
+impl BorLevel {
+    /// Decode the FLASH_OPTCR register value, RM0368 3.8.6
+    pub fn from_optcr(optcr: u32) -> Self {
+        match (optcr >> 2) & 0b11 {
+            0b00 => BorLevel::Level3,
+            0b01 => BorLevel::Level2,
+            0b10 => BorLevel::Level1,
+            _ => BorLevel::Off,
+        }
+    }
+
+    /// The BOR_LEV field value
+    pub fn bits(self) -> u8 {
+        match self {
+            BorLevel::Level3 => 0b00,
+            BorLevel::Level2 => 0b01,
+            BorLevel::Level1 => 0b10,
+            BorLevel::Off => 0b11,
+        }
+    }
+
+    /// Typical reset threshold in millivolts (falling edge), `None` if off
+    pub fn threshold_mv(self) -> Option<u32> {
+        match self {
+            BorLevel::Off => None,
+            BorLevel::Level1 => Some(2_100),
+            BorLevel::Level2 => Some(2_400),
+            BorLevel::Level3 => Some(2_700),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_on_sets_several_flags() {
+        // PORRSTF, PINRSTF, BORRSTF
+        assert_eq!(ResetCause::from_csr(0x0E00_0000), ResetCause::PowerOn);
+    }
+
+    #[test]
+    fn software_and_watchdog_also_set_pin() {
+        assert_eq!(ResetCause::from_csr(0x1400_0000), ResetCause::Software);
+        assert_eq!(
+            ResetCause::from_csr(0x2400_0000),
+            ResetCause::IndependentWatchdog
+        );
+        assert_eq!(ResetCause::from_csr(0x0400_0000), ResetCause::Pin);
+    }
+
+    #[test]
+    fn no_flags() {
+        // LSION/LSIRDY are not reset flags
+        assert_eq!(ResetCause::from_csr(0x0000_0003), ResetCause::Unknown);
+    }
+
+    #[test]
+    fn bor_level_round_trip() {
+        for level in [
+            BorLevel::Off,
+            BorLevel::Level1,
+            BorLevel::Level2,
+            BorLevel::Level3,
+        ]
+        .iter()
+        {
+            let optcr = 0x0FFF_AAE1 | (level.bits() as u32) << 2;
+            assert_eq!(BorLevel::from_optcr(optcr), *level);
+        }
+    }
+
+    #[test]
+    fn bor_factory_default_is_off() {
+        // FLASH_OPTCR reset value (as shipped)
+        assert_eq!(BorLevel::from_optcr(0x0FFF_AAED), BorLevel::Off);
+    }
+}
@@ -0,0 +1,49 @@
+//! Handling of failed `schedule` calls
+//!
+//! `cx.schedule.task(instant)` returns `Err(payload)` if the message queue of
+//! the task is full. A periodic task rescheduling itself then panics on
+//! `.unwrap()`, which is easy to miss. Use `try_reschedule` to log an error
+//! instead, and act on the returned `bool`:
+//!
+//! ```ignore
+//! if !try_reschedule(cx.schedule.toggle(cx.scheduled + OFFSET.cycles()), "toggle") {
+//!     // the task stopped, leave the LED off
+//!     led.set_low().ok();
+//! }
+//! ```
+//!
+//! The queue of a task holds `capacity` messages (default 1), so it is full if:
+//!
+//! - the task is scheduled (or spawned) from more than one place, e.g., it
+//!   reschedules itself and is also scheduled by a button task
+//! - it is scheduled again before the previous message has been dispatched,
+//!   e.g., higher priority tasks keep it from running for a full period
+//!
+//! A periodic task that reschedules itself once per run, and is never
+//! scheduled elsewhere, always has room for the next message (its own message
+//! is taken out of the queue before it runs).
+//!
+//! Notice, a failed reschedule stops a self-rescheduling task, its periodic
+//! chain of messages ends there. It runs again only if scheduled elsewhere,
+//! e.g., by the message that filled the queue. Put whatever the task drives
+//! in a safe state (an LED off, a PWM output idle), the error message tells
+//! you where to look.
+
+use rtt_target::rprintln;
+
+/// Log an error over RTT if scheduling `task` failed
+///
+/// Returns `true` if the task was scheduled, `false` if it was not, i.e.,
+/// the task stopped. The payload (if any) of a failed schedule is dropped.
+pub fn try_reschedule<T>(result: Result<(), T>, task: &str) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(_) => {
+            rprintln!(
+                "error: schedule `{}` failed, queue full (increase `capacity`?), task stopped",
+                task
+            );
+            false
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Power on self test (POST)
+//!
+//! A quick "is my board alive" check, run at the end of `init`:
+//!
+//! ```ignore
+//! let clocks = SysClkConfig::new().sysclk(84).apply(device.RCC);
+//! let mut led = Led::new(gpioa.pa5.into_push_pull_output());
+//!
+//! if let Err(err) = self_test(&clocks) {
+//!     rprintln!("self test failed: {}", err);
+//!     loop {
+//!         asm::bkpt();
+//!     }
+//! }
+//! blink(&mut led, &clocks, 3).ok();
+//! ```
+//!
+//! The visual part (`blink`) is deliberately kept out of `self_test`. The
+//! check stays free of busy waiting and of the LED type, and a failing check
+//! can be reported (or halted on) without the LED suggesting a pass.
+//!
+//! See `examples/rtic_selftest.rs` for a complete application.
+
+use cortex_m::asm;
+use embedded_hal::digital::v2::OutputPin;
+use stm32f2xx_hal::{rcc::Clocks, stm32::RCC};
+
+use crate::{clock::SYSCLK_MAX_MHZ, delay::ms_to_cycles, led::Led};
+
+/// Minimum SYSCLK frequency in MHz, an external clock on OSC_IN (HSE bypass)
+/// goes down to 1 MHz (STM32F401 data sheet, fHSE_ext)
+pub const SYSCLK_MIN_MHZ: u32 = 1;
+/// Maximum APB1 (PCLK1) frequency in MHz (STM32F401)
+pub const PCLK1_MAX_MHZ: u32 = 42;
+
+/// Check the frozen clock configuration and the RCC state
+///
+/// - SYSCLK within SYSCLK_MIN..=SYSCLK_MAX, and PCLK1 within its maximum
+/// - the PLL is locked if selected as the system clock
+/// - GPIOA (the LED) is powered
+pub fn self_test(clocks: &Clocks) -> Result<(), &'static str> {
+    check_clocks(clocks.sysclk().0, clocks.pclk1().0)?;
+
+    // SAFETY: read only access, the RCC is owned by the HAL after `constrain`
+    let rcc = unsafe { &*RCC::ptr() };
+
+    // RCC_CFGR SWS, RM0368 6.3.3
+    if rcc.cfgr.read().sws().is_pll() && rcc.cr.read().pllrdy().is_not_ready() {
+        return Err("PLL selected but not locked");
+    }
+    // RCC_AHB1ENR, RM0368 6.3.9
+    if rcc.ahb1enr.read().gpioaen().is_disabled() {
+        return Err("GPIOA clock not enabled");
+    }
+    Ok(())
+}
+
+/// Check SYSCLK and PCLK1 (in Hz) against the device limits
+pub fn check_clocks(sysclk_hz: u32, pclk1_hz: u32) -> Result<(), &'static str> {
+    if sysclk_hz < SYSCLK_MIN_MHZ * 1_000_000 {
+        return Err("sysclk below the minimum");
+    }
+    if sysclk_hz > SYSCLK_MAX_MHZ * 1_000_000 {
+        return Err("sysclk above the maximum");
+    }
+    if pclk1_hz > PCLK1_MAX_MHZ * 1_000_000 {
+        return Err("pclk1 above the maximum");
+    }
+    Ok(())
+}
+
+/// Blink `led` `times` times (100 ms on, 100 ms off) as a visual POST
+///
+/// Busy waits, call from `init` only.
+pub fn blink<PIN: OutputPin>(
+    led: &mut Led<PIN>,
+    clocks: &Clocks,
+    times: u32,
+) -> Result<(), PIN::Error> {
+    let half_period = ms_to_cycles(100, clocks.sysclk().0);
+    for _ in 0..times {
+        led.on()?;
+        asm::delay(half_period);
+        led.off()?;
+        asm::delay(half_period);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_clocks_in_range() {
+        assert_eq!(check_clocks(16_000_000, 16_000_000), Ok(()));
+        assert_eq!(check_clocks(84_000_000, 42_000_000), Ok(()));
+        // the 8 MHz HSE (ST-LINK MCO, bypass) as SYSCLK
+        assert_eq!(check_clocks(8_000_000, 8_000_000), Ok(()));
+    }
+
+    #[test]
+    fn check_clocks_out_of_range() {
+        assert!(check_clocks(0, 0).is_err());
+        assert!(check_clocks(100_000_000, 42_000_000).is_err());
+        assert!(check_clocks(84_000_000, 84_000_000).is_err());
+    }
+}
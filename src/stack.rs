@@ -0,0 +1,96 @@
+//! Stack usage, the high-water mark by stack painting
+//!
+//! At startup the free stack is filled with a known pattern (`paint`). Any
+//! word the stack has grown into is overwritten, so the lowest word no
+//! longer holding the pattern marks the peak stack usage (`high_water_mark`).
+//!
+//! ```ignore
+//! #[init]
+//! fn init(_cx: init::Context) {
+//!     stack::paint();
+//! }
+//! // later, e.g., in `idle`
+//! rprintln!("stack peak {} of {} bytes", stack::high_water_mark(), stack::size());
+//! ```
+//!
+//! The stack bounds are linker symbols of `cortex-m-rt` (see its `link.x`):
+//!
+//! - `_stack_start`, the top of the stack (the initial SP), by default the
+//!   end of RAM (set in `memory.x` to move it)
+//! - `__sheap`, the end of the static variables (.data, .bss, .uninit)
+//!
+//! The stack grows down from `_stack_start`, towards `__sheap` (no heap is
+//! used). A symbol has no value as such, only its address is meaningful, it
+//! is declared as an `extern` static and read by taking its address.
+
+use core::{mem, ptr};
+
+/// The pattern painted on the unused stack
+pub const PAINT: u32 = 0xC0FF_EE00;
+
+extern "C" {
+    static __sheap: u32;
+    static _stack_start: u32;
+}
+
+/// The stack region, (bottom, top) addresses
+pub fn bounds() -> (usize, usize) {
+    // SAFETY: only the addresses of the symbols are taken, never read
+    unsafe {
+        (
+            &__sheap as *const u32 as usize,
+            &_stack_start as *const u32 as usize,
+        )
+    }
+}
+
+/// The size of the stack region in bytes
+pub fn size() -> usize {
+    let (bottom, top) = bounds();
+    top - bottom
+}
+
+/// Paint the unused stack (below the current stack pointer) with `PAINT`
+///
+/// Call first thing in `init`, with interrupts disabled, so that no
+/// exception frame is pushed below the stack pointer while painting.
+pub fn paint() {
+    let (bottom, _) = bounds();
+    let sp = cortex_m::register::msp::read() as usize;
+    let mut p = bottom as *mut u32;
+    while (p as usize) < sp {
+        // SAFETY: the region between the statics and the stack pointer is unused
+        unsafe { ptr::write_volatile(p, PAINT) };
+        p = p.wrapping_add(1);
+    }
+}
+
+/// The peak stack usage in bytes, since `paint`
+pub fn high_water_mark() -> usize {
+    let (bottom, top) = bounds();
+    let words = (top - bottom) / mem::size_of::<u32>();
+    // SAFETY: the stack region is valid RAM, only read. The words above the
+    // stack pointer are in use by the stack, read as plain values.
+    let region = unsafe { core::slice::from_raw_parts(bottom as *const u32, words) };
+    (words - untouched(region)) * mem::size_of::<u32>()
+}
+
+/// The number of words still holding `PAINT`, from the bottom of `region`
+pub fn untouched(region: &[u32]) -> usize {
+    region.iter().take_while(|word| **word == PAINT).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_from_the_bottom() {
+        assert_eq!(untouched(&[]), 0);
+        assert_eq!(untouched(&[PAINT, PAINT, PAINT]), 3);
+        assert_eq!(untouched(&[PAINT, PAINT, 0, 1]), 2);
+        assert_eq!(untouched(&[0, PAINT, PAINT]), 0);
+        // a painted word higher up (e.g., a local with that value) is not counted
+        assert_eq!(untouched(&[PAINT, 7, PAINT, 0]), 1);
+    }
+}
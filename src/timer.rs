@@ -0,0 +1,109 @@
+//! Timer update rate computations, prescaler (PSC) and auto-reload (ARR)
+
+/// Prescaler and auto-reload register values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divider {
+    /// TIMx_PSC, the counter clock is the timer clock / (PSC + 1)
+    pub psc: u16,
+    /// TIMx_ARR, the counter counts from 0 to ARR
+    pub arr: u32,
+}
+
+impl Divider {
+    /// The actual update rate in Hz at `timer_clk_hz`
+    pub fn update_hz(&self, timer_clk_hz: u32) -> f32 {
+        timer_clk_hz as f32 / ((self.psc as f32 + 1.0) * (self.arr as f32 + 1.0))
+    }
+}
+
+/// PSC and ARR for `update_hz` updates (overflows) per second at `timer_clk_hz`
+///
+/// The update rate is timer_clk / ((PSC + 1) x (ARR + 1)), RM0368 13.3.1.
+/// The smallest PSC is chosen (giving the finest ARR resolution), with ARR
+/// at most `arr_max` (0xFFFF for the 16 bit timers, 0xFFFF_FFFF for TIM2/TIM5),
+/// rounded to the nearest integer.
+///
+/// Returns `None` if `update_hz` is 0, above half the timer clock (ARR 0
+/// stops the counter), or too low to reach with a 16 bit PSC.
+pub fn divider(timer_clk_hz: u32, update_hz: u32, arr_max: u32) -> Option<Divider> {
+    if update_hz == 0 || update_hz > timer_clk_hz / 2 {
+        return None;
+    }
+    // u64, (PSC + 1) x (ARR + 1) goes up to 2^48 for a 32 bit timer
+    let (clk, hz) = (timer_clk_hz as u64, update_hz as u64);
+    // timer clock cycles per update
+    let ticks = (clk + hz / 2) / hz;
+    // PSC + 1, rounded up so that ARR fits
+    let prescale = (ticks + arr_max as u64) / (arr_max as u64 + 1);
+    if prescale > 0x1_0000 {
+        return None;
+    }
+    // ARR + 1, rounded to nearest
+    let reload = (clk + prescale * hz / 2) / (prescale * hz);
+    Some(Divider {
+        psc: (prescale - 1) as u16,
+        arr: (reload - 1) as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact() {
+        // 16 MHz / 16_000 = 1 kHz, no prescaling needed
+        assert_eq!(
+            divider(16_000_000, 1_000, 0xFFFF),
+            Some(Divider {
+                psc: 0,
+                arr: 15_999
+            })
+        );
+        // a 32 bit timer counts 1 Hz without prescaling
+        assert_eq!(
+            divider(84_000_000, 1, 0xFFFF_FFFF),
+            Some(Divider {
+                psc: 0,
+                arr: 83_999_999
+            })
+        );
+    }
+
+    #[test]
+    fn prescaled() {
+        // 84 MHz / 1 Hz needs PSC + 1 >= 84e6 / 65536 = 1281.7
+        let d = divider(84_000_000, 1, 0xFFFF).unwrap();
+        assert_eq!(
+            d,
+            Divider {
+                psc: 1281,
+                arr: 65_522
+            }
+        );
+        assert!((d.update_hz(84_000_000) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rounded() {
+        // 16 MHz / 880 Hz = 18_181.8 cycles, rounded up
+        let d = divider(16_000_000, 880, 0xFFFF).unwrap();
+        assert_eq!(
+            d,
+            Divider {
+                psc: 0,
+                arr: 18_181
+            }
+        );
+        assert!((d.update_hz(16_000_000) - 879.99).abs() < 0.01);
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(divider(16_000_000, 0, 0xFFFF), None);
+        // ARR would be 0
+        assert_eq!(divider(16_000_000, 16_000_000, 0xFFFF), None);
+        // 84e6 / 256 > 65536, beyond the prescaler with an 8 bit ARR
+        assert_eq!(divider(84_000_000, 1, 0xFF), None);
+    }
+}
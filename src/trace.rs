@@ -0,0 +1,83 @@
+//! RTT tracing
+//!
+//! `trace_init!()` replaces the `rtt_init_print!()` + `rprintln!("init")`
+//! boilerplate found in the examples' `init`.
+//!
+//! ```ignore
+//! use app::trace_init;
+//!
+//! #[init]
+//! fn init(_cx: init::Context) {
+//!     trace_init!();
+//! }
+//! ```
+//!
+//! `print_banner` identifies the running firmware in more detail, call it
+//! once the clocks are frozen:
+//!
+//! ```ignore
+//! let clocks = rcc.cfgr.freeze();
+//! print_banner(clocks.sysclk().0);
+//! ```
+
+// re-exported for use in `trace_init!`
+#[doc(hidden)]
+pub use rtt_target;
+
+/// Crate name, as given in `Cargo.toml`
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+/// Crate version, as given in `Cargo.toml`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Short git hash of the build (`-dirty` with uncommitted changes), see `build.rs`
+pub const GIT_HASH: &str = env!("GIT_HASH");
+/// DBGMCU_IDCODE address, RM0368 23.6.1
+pub const DBGMCU_IDCODE: usize = 0xE004_2000;
+
+/// Read the device identifier, DEV_ID (bits 11:0) of DBGMCU_IDCODE
+///
+/// Reads as 0 on some devices unless a debugger is attached (ES0222).
+pub fn dev_id() -> u32 {
+    // SAFETY: read only access to an always present debug register
+    unsafe { core::ptr::read_volatile(DBGMCU_IDCODE as *const u32) & 0xfff }
+}
+
+/// The device (family) for a DEV_ID, see `rtic_device_info.rs`
+pub fn chip(dev_id: u32) -> &'static str {
+    match dev_id {
+        0x411 => "STM32F2xx",
+        0x423 => "STM32F401xB/C",
+        0x433 => "STM32F401xD/E",
+        0x431 => "STM32F411xC/E",
+        0x000 => "unknown (read without debugger?)",
+        _ => "unknown",
+    }
+}
+
+/// Print the startup banner, crate, version, git hash, chip and SYSCLK
+///
+/// Tells which firmware is actually flashed, compare the hash to `git log`.
+/// The chip is decoded from DBGMCU_IDCODE at run-time, the same firmware runs
+/// on several devices. Requires RTT to be set up, e.g., by `trace_init!()`.
+pub fn print_banner(sysclk_hz: u32) {
+    rtt_target::rprintln!(
+        "{} v{} ({}) on {} @ {} MHz",
+        NAME,
+        VERSION,
+        GIT_HASH,
+        chip(dev_id()),
+        sysclk_hz / 1_000_000
+    );
+}
+
+/// Initialize RTT printing and print an init banner
+#[macro_export]
+macro_rules! trace_init {
+    () => {
+        $crate::trace::rtt_target::rtt_init_print!();
+        $crate::trace::rtt_target::rprintln!(
+            "init {} v{} @ --:--:--",
+            $crate::trace::NAME,
+            $crate::trace::VERSION
+        );
+    };
+}
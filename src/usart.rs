@@ -0,0 +1,46 @@
+//! USART baud rate computations
+
+/// USART_BRR value for `baud` at `pclk_hz`, with oversampling by 16 (OVER8 = 0)
+///
+/// USARTDIV = pclk / (16 x baud), BRR holds the mantissa in bits 15:4 and the
+/// fraction (in 1/16) in bits 3:0, RM0368 19.3.4. That is, BRR is
+/// pclk / baud, rounded to the nearest integer (the fraction carries over
+/// into the mantissa). The result must fit in 16 bits, i.e., baud >= pclk / 65535.
+pub fn usart_div(pclk_hz: u32, baud: u32) -> u16 {
+    ((pclk_hz + baud / 2) / baud) as u16
+}
+
+/// The actual baud rate for a BRR value at `pclk_hz`
+pub fn baud_rate(pclk_hz: u32, brr: u16) -> u32 {
+    pclk_hz / brr as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RM0368 Table 75, oversampling by 16
+    #[test]
+    fn usart_div_reference_manual() {
+        // USARTDIV 104.1875
+        assert_eq!(usart_div(16_000_000, 9_600), 0x683);
+        // USARTDIV 8.6875
+        assert_eq!(usart_div(16_000_000, 115_200), 0x8B);
+        // USARTDIV 45.5625
+        assert_eq!(usart_div(84_000_000, 115_200), 0x2D9);
+    }
+
+    #[test]
+    fn usart_div_fraction_carry() {
+        // 16.96875 rounds to 17.0, the fraction carries into the mantissa
+        assert_eq!(usart_div(16_290_000, 60_000), 0x110);
+    }
+
+    #[test]
+    fn baud_rate_error_is_small() {
+        let brr = usart_div(42_000_000, 115_200);
+        let actual = baud_rate(42_000_000, brr);
+        // within 0.5%
+        assert!((actual as i32 - 115_200).abs() < 576);
+    }
+}
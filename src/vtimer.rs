@@ -0,0 +1,168 @@
+//! Virtual timers, any number of periodic software timers on one tick
+//!
+//! Each timer has a period (in ticks) and a payload, handed back when the
+//! timer is due. The payload identifies the action, e.g., an enum matched in
+//! the tick handler, or a plain `fn()` callback.
+//!
+//! ```ignore
+//! let mut timers: VTimers<Event> = VTimers::new();
+//! timers.add(25, Event::Blink).ok();
+//! // in the periodic (hardware) tick handler
+//! timers.tick(|event| match event { ... });
+//! ```
+//!
+//! The timers live in a fixed array of `SLOTS`, no allocation.
+
+/// Number of timer slots
+pub const SLOTS: usize = 8;
+
+/// Index of a registered timer
+pub type TimerId = usize;
+
+#[derive(Clone, Copy)]
+struct Slot<T> {
+    period: u32,
+    // the tick when due next
+    next: u32,
+    payload: T,
+}
+
+/// A set of virtual timers with payload `T`
+pub struct VTimers<T: Copy> {
+    now: u32,
+    slots: [Option<Slot<T>>; SLOTS],
+}
+
+impl<T: Copy> VTimers<T> {
+    /// No timers, at tick 0
+    pub fn new() -> Self {
+        VTimers {
+            now: 0,
+            slots: [None; SLOTS],
+        }
+    }
+
+    /// The number of ticks so far
+    pub fn now(&self) -> u32 {
+        self.now
+    }
+
+    /// Register a timer, first due `period` ticks from now
+    ///
+    /// Returns `Err(payload)` if all slots are taken. `period` must be at least 1.
+    pub fn add(&mut self, period: u32, payload: T) -> Result<TimerId, T> {
+        assert!(period > 0, "period must be at least 1 tick");
+        match self.slots.iter().position(|slot| slot.is_none()) {
+            Some(id) => {
+                self.slots[id] = Some(Slot {
+                    period,
+                    next: self.now.wrapping_add(period),
+                    payload,
+                });
+                Ok(id)
+            }
+            None => Err(payload),
+        }
+    }
+
+    /// Unregister timer `id`, returns its payload (`None` if not registered)
+    pub fn remove(&mut self, id: TimerId) -> Option<T> {
+        self.slots
+            .get_mut(id)
+            .and_then(|slot| slot.take())
+            .map(|slot| slot.payload)
+    }
+
+    /// Advance one tick, calls `f` with the payload of each due timer
+    ///
+    /// Due timers are re-armed a period after their previous deadline, so
+    /// the periods do not drift. Called in slot order.
+    pub fn tick<F: FnMut(T)>(&mut self, mut f: F) {
+        self.now = self.now.wrapping_add(1);
+        let now = self.now;
+        for slot in self.slots.iter_mut().flatten() {
+            // signed difference, correct across the wrap of `now`
+            if now.wrapping_sub(slot.next) as i32 >= 0 {
+                slot.next = slot.next.wrapping_add(slot.period);
+                f(slot.payload);
+            }
+        }
+    }
+}
+
+impl<T: Copy> Default for VTimers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the payloads due at each of the first `ticks` ticks
+    fn run(timers: &mut VTimers<char>, ticks: u32) -> Vec<(u32, char)> {
+        let mut due = Vec::new();
+        for _ in 0..ticks {
+            let now = timers.now().wrapping_add(1);
+            timers.tick(|c| due.push((now, c)));
+        }
+        due
+    }
+
+    #[test]
+    fn due_at_multiples_of_the_period() {
+        let mut timers = VTimers::new();
+        timers.add(2, 'a').unwrap();
+        timers.add(3, 'b').unwrap();
+        assert_eq!(
+            run(&mut timers, 6),
+            vec![(2, 'a'), (3, 'b'), (4, 'a'), (6, 'a'), (6, 'b')]
+        );
+    }
+
+    #[test]
+    fn period_one_fires_every_tick() {
+        let mut timers = VTimers::new();
+        timers.add(1, 'x').unwrap();
+        assert_eq!(run(&mut timers, 3), vec![(1, 'x'), (2, 'x'), (3, 'x')]);
+    }
+
+    #[test]
+    fn added_later_counts_from_now() {
+        let mut timers = VTimers::new();
+        run(&mut timers, 5);
+        timers.add(2, 'a').unwrap();
+        assert_eq!(run(&mut timers, 4), vec![(7, 'a'), (9, 'a')]);
+    }
+
+    #[test]
+    fn removed_timers_stop() {
+        let mut timers = VTimers::new();
+        let id = timers.add(1, 'a').unwrap();
+        timers.add(2, 'b').unwrap();
+        assert_eq!(timers.remove(id), Some('a'));
+        assert_eq!(timers.remove(id), None);
+        assert_eq!(run(&mut timers, 2), vec![(2, 'b')]);
+        // the slot is reused
+        assert_eq!(timers.add(1, 'c'), Ok(id));
+    }
+
+    #[test]
+    fn full() {
+        let mut timers = VTimers::new();
+        for _ in 0..SLOTS {
+            timers.add(1, 'a').unwrap();
+        }
+        assert_eq!(timers.add(1, 'z'), Err('z'));
+    }
+
+    #[test]
+    fn across_the_wrap() {
+        let mut timers = VTimers::new();
+        timers.now = u32::MAX - 1;
+        timers.add(3, 'a').unwrap();
+        // due at MAX + 2 = 1 (wrapped)
+        assert_eq!(run(&mut timers, 3), vec![(1, 'a')]);
+    }
+}